@@ -0,0 +1,69 @@
+// Anisotropic displacement parameters (thermal ellipsoids)
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{symmetric_eigen, Matrix3, Vec3};
+use crate::MolecularSystem;
+
+/// Displacement tensor as `[U11, U22, U33, U12, U13, U23]` in Å², the ANISOU
+/// order. All zeros for an atom without one.
+pub(crate) type Adp = [f32; 6];
+
+fn to_matrix(u: &Adp) -> Matrix3 {
+    [[u[0], u[3], u[4]], [u[3], u[1], u[5]], [u[4], u[5], u[2]]]
+}
+
+/// Unit eigenvectors of the tensor with their eigenvalues, largest first
+pub(crate) fn principal_axes(u: &Adp) -> [(Vec3, f32); 3] {
+    let (values, vectors) = symmetric_eigen(to_matrix(u));
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+    order.map(|k| ((vectors[0][k], vectors[1][k], vectors[2][k]), values[k]))
+}
+
+/// `R U Rᵀ`: the tensor of an atom after rotating the structure by `r`
+pub(crate) fn rotate_adp(r: &Matrix3, u: &Adp) -> Adp {
+    let u = to_matrix(u);
+    let ru: Matrix3 = std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| r[i][k] * u[k][j]).sum()));
+    let out = |i: usize, j: usize| (0..3).map(|k| ru[i][k] * r[j][k]).sum::<f32>();
+    [out(0, 0), out(1, 1), out(2, 2), out(0, 1), out(0, 2), out(1, 2)]
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Per-atom displacement tensors as flat `[U11, U22, U33, U12, U13, U23, ...]`
+    /// (Å²) in storage order, six values per atom. An empty slice clears them.
+    /// `load_pdb` fills them from ANISOU records; loading new atoms clears them.
+    pub fn set_anisotropic_displacements(&mut self, u: &[f32]) -> Result<(), JsValue> {
+        if !u.is_empty() && u.len() != self.all_atoms.len() * 6 {
+            return Err(JsValue::from_str(&format!(
+                "expected {} displacement components for {} atoms, got {}",
+                self.all_atoms.len() * 6, self.all_atoms.len(), u.len()
+            )));
+        }
+        self.adps = u.chunks_exact(6).map(|c| [c[0], c[1], c[2], c[3], c[4], c[5]]).collect();
+        Ok(())
+    }
+
+    /// Thermal ellipsoid of each atom of the cached visible set, in visible-list
+    /// order: 12 floats per atom, three unit axes `[ax, ay, az, bx, ..., cz]`
+    /// followed by their semi-axis lengths, largest first. Lengths are RMS
+    /// displacements in Å (the square roots of the tensor's eigenvalues); scale
+    /// them by 1.5382 for the usual 50% probability surface. Atoms without a
+    /// tensor, or with a non-positive-definite one, get zero lengths along
+    /// whatever axes apply. Empty when no displacements are set.
+    pub fn get_visible_atom_ellipsoids(&self) -> Vec<f32> {
+        if self.adps.is_empty() {
+            return Vec::new();
+        }
+        let mut out = Vec::with_capacity(self.cached_visible_indices.len() * 12);
+        for &i in &self.cached_visible_indices {
+            let axes = principal_axes(&self.adps[i as usize]);
+            for (axis, _) in &axes {
+                out.extend_from_slice(&[axis.0, axis.1, axis.2]);
+            }
+            out.extend(axes.iter().map(|&(_, value)| value.max(0.0).sqrt()));
+        }
+        out
+    }
+}
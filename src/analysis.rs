@@ -0,0 +1,236 @@
+use wasm_bindgen::prelude::*;
+
+use crate::crystal::UnitCell;
+use crate::grid::SpatialGrid;
+use crate::math::{length, sub, Vec3};
+use crate::MolecularSystem;
+
+type CellInverse = [[f32; 3]; 3];
+
+/// Selections larger than this get a warning from `distance_matrix`
+const LARGE_MATRIX_SELECTION: usize = 1_000;
+
+impl MolecularSystem {
+    // Unit cell for minimum-image distances, when PBC is enabled and a usable cell is set
+    pub(crate) fn periodic_cell(&self) -> Option<(UnitCell, CellInverse)> {
+        if !self.use_pbc {
+            return None;
+        }
+        let cell = self.unit_cell?;
+        Some((cell, cell.inverse()?))
+    }
+
+    fn atom_position(&self, index: usize) -> Vec3 {
+        let atom = &self.all_atoms[index];
+        (atom.x, atom.y, atom.z)
+    }
+
+    // Displacement from atom `i` to atom `j`, minimum-imaged under PBC
+    pub(crate) fn pair_displacement(&self, periodic: Option<&(UnitCell, CellInverse)>, i: usize, j: usize) -> Vec3 {
+        let d = sub(self.atom_position(j), self.atom_position(i));
+        match periodic {
+            Some((cell, inverse)) => cell.minimum_image(inverse, d),
+            None => d,
+        }
+    }
+
+    /// Call `f(j, distance_squared)` for each atom `j != i` within `cutoff` of atom `i`.
+    /// Under PBC the search also covers the 26 neighboring cell images; this finds
+    /// minimum images as long as atoms are wrapped into the cell and `cutoff` is
+    /// under half the shortest cell width.
+    pub(crate) fn for_each_neighbor(
+        &self,
+        grid: &SpatialGrid,
+        periodic: Option<&(UnitCell, CellInverse)>,
+        i: usize,
+        cutoff: f32,
+        mut f: impl FnMut(usize, f32),
+    ) {
+        let p = self.atom_position(i);
+        let Some((cell, _)) = periodic else {
+            grid.for_each_within(&self.all_atoms, p, cutoff, |j, d| if j != i { f(j, d) });
+            return;
+        };
+
+        for u in -1..=1 {
+            for v in -1..=1 {
+                for w in -1..=1 {
+                    // Atom j's image at p_j + T is near p_i exactly when p_j is near p_i - T
+                    let query = sub(p, cell.offset(u as f32, v as f32, w as f32));
+                    if !grid.overlaps(query, cutoff) {
+                        continue;
+                    }
+                    grid.for_each_within(&self.all_atoms, query, cutoff, |j, d| if j != i { f(j, d) });
+                }
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Use the minimum-image convention in distance analyses when a unit cell is set
+    pub fn set_use_pbc(&mut self, enabled: bool) {
+        self.use_pbc = enabled;
+    }
+
+    /// Distance between two atoms (minimum image under PBC), `None` for bad indices
+    pub fn distance(&self, i: usize, j: usize) -> Option<f32> {
+        if i >= self.all_atoms.len() || j >= self.all_atoms.len() {
+            return None;
+        }
+        let periodic = self.periodic_cell();
+        Some(length(self.pair_displacement(periodic.as_ref(), i, j)))
+    }
+
+    /// Row-major `k × k` matrix of pairwise distances (minimum image under PBC)
+    /// for `k` selected atoms. Output grows as k², so keep selections small.
+    pub fn distance_matrix(&self, indices: &[usize]) -> Result<Vec<f32>, JsValue> {
+        if let Some(&bad) = indices.iter().find(|&&i| i >= self.all_atoms.len()) {
+            return Err(JsValue::from_str(&format!(
+                "atom index {} out of range for {} atoms", bad, self.all_atoms.len()
+            )));
+        }
+        let k = indices.len();
+        if k > LARGE_MATRIX_SELECTION {
+            log!(self, LOG_ERRORS, "Warning: distance matrix for {} atoms has {} entries", k, k * k);
+        }
+
+        let periodic = self.periodic_cell();
+        let mut matrix = vec![0.0; k * k];
+        for (row, &i) in indices.iter().enumerate() {
+            for (col, &j) in indices.iter().enumerate().skip(row + 1) {
+                let d = length(self.pair_displacement(periodic.as_ref(), i, j));
+                matrix[row * k + col] = d;
+                matrix[col * k + row] = d;
+            }
+        }
+        Ok(matrix)
+    }
+
+    /// Storage indices, ascending, of every atom within `radius` of `(x, y, z)`,
+    /// e.g. to select everything near a residue. Plain distances (no periodic
+    /// images); the spatial grid is shared with `estimate_visible_count`.
+    pub fn atoms_within(&self, x: f32, y: f32, z: f32, radius: f32) -> Vec<u32> {
+        let mut indices = Vec::new();
+        if !(radius >= 0.0 && radius.is_finite()) || self.all_atoms.is_empty() {
+            return indices;
+        }
+        self.coarse_grid().for_each_within(&self.all_atoms, (x, y, z), radius, |i, _| indices.push(i as u32));
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Number of atoms within `cutoff` of each atom, in storage order
+    pub fn coordination_numbers(&self, cutoff: f32) -> Vec<u32> {
+        let periodic = self.periodic_cell();
+        let grid = SpatialGrid::build(&self.all_atoms, cutoff);
+
+        (0..self.all_atoms.len())
+            .map(|i| {
+                let mut count = 0;
+                self.for_each_neighbor(&grid, periodic.as_ref(), i, cutoff, |_, _| count += 1);
+                count
+            })
+            .collect()
+    }
+
+    /// Radial distribution function g(r) over `bins` equal shells up to `r_max`.
+    ///
+    /// Density is atoms per unit-cell volume under PBC, otherwise per
+    /// bounding-box volume. Returns all zeros when that volume is zero.
+    pub fn radial_distribution(&self, r_max: f32, bins: usize) -> Vec<f32> {
+        let n = self.all_atoms.len();
+        let mut histogram = vec![0u64; bins];
+        if n < 2 || bins == 0 || r_max <= 0.0 {
+            return vec![0.0; bins];
+        }
+
+        let periodic = self.periodic_cell();
+        let grid = SpatialGrid::build(&self.all_atoms, r_max);
+        let bin_width = r_max / bins as f32;
+
+        for i in 0..n {
+            self.for_each_neighbor(&grid, periodic.as_ref(), i, r_max, |j, d| {
+                if j > i {
+                    let bin = ((d.sqrt() / bin_width) as usize).min(bins - 1);
+                    histogram[bin] += 1;
+                }
+            });
+        }
+
+        let volume = match &periodic {
+            Some((cell, _)) => cell.volume(),
+            None => {
+                let (min, max) = self.atom_extent();
+                let size = sub(max, min);
+                size.0 * size.1 * size.2
+            }
+        };
+        if volume <= 0.0 {
+            return vec![0.0; bins];
+        }
+
+        let density = n as f32 / volume;
+        histogram
+            .iter()
+            .enumerate()
+            .map(|(bin, &pairs)| {
+                let r0 = bin as f32 * bin_width;
+                let r1 = r0 + bin_width;
+                let shell = 4.0 / 3.0 * std::f32::consts::PI * (r1 * r1 * r1 - r0 * r0 * r0);
+                // Each unordered pair counts for both of its atoms
+                2.0 * pairs as f32 / (n as f32 * density * shell)
+            })
+            .collect()
+    }
+
+    /// Atom counts in `bins` equal slabs along one axis (`0` = x, `1` = y,
+    /// `2` = z) spanning the bounding box, in increasing coordinate order, e.g.
+    /// to show where a membrane's layers lie next to a slice slider. Atoms on
+    /// the upper edge fall in the last bin, and a flat extent puts everything
+    /// in the first. An invalid axis is logged and gives an empty result.
+    pub fn axis_profile(&self, axis: u32, bins: usize) -> Vec<u32> {
+        if axis > 2 {
+            log!(self, LOG_ERRORS, "Invalid axis {} - expected 0 (x), 1 (y) or 2 (z)", axis);
+            return Vec::new();
+        }
+        let mut counts = vec![0u32; bins];
+        if bins == 0 || self.all_atoms.is_empty() {
+            return counts;
+        }
+
+        let coordinate = |p: Vec3| match axis {
+            0 => p.0,
+            1 => p.1,
+            _ => p.2,
+        };
+        let (min, max) = self.atom_extent();
+        let (low, span) = (coordinate(min), coordinate(max) - coordinate(min));
+        for atom in &self.all_atoms {
+            let t = if span > 0.0 { (coordinate((atom.x, atom.y, atom.z)) - low) / span } else { 0.0 };
+            counts[((t * bins as f32) as usize).min(bins - 1)] += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    #[test]
+    fn atoms_within_matches_a_brute_force_scan() {
+        let mut system = MolecularSystem::new();
+        system.load_atoms_from_file(2000);
+
+        for (x, y, z, radius) in [(0.3, -0.2, 0.1, 2.5), (5.1, 4.7, -3.3, 6.0), (-40.0, 0.0, 0.0, 1.0), (0.0, 0.0, 0.0, 500.0)] {
+            let expected: Vec<u32> = system.all_atoms.iter()
+                .enumerate()
+                .filter(|(_, a)| (a.x - x).powi(2) + (a.y - y).powi(2) + (a.z - z).powi(2) <= radius * radius)
+                .map(|(i, _)| i as u32)
+                .collect();
+            assert_eq!(system.atoms_within(x, y, z, radius), expected, "sphere at ({}, {}, {}) r {}", x, y, z, radius);
+        }
+    }
+}
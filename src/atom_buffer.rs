@@ -0,0 +1,115 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{apply_lod_budget, AtomCull, AtomData, Camera, MolecularSystem, Projection};
+
+/// Reusable cull output for renderers that composite several passes (say the
+/// whole structure, then a ligand) into one list without a fresh allocation
+/// per pass. Created and owned by the caller: it holds copies of the atoms,
+/// so it stays valid across later culls, loads and `clear` on any system,
+/// and its storage is released only by `free()` (or garbage collection).
+/// Capacity is kept when the buffer is overwritten or cleared.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct AtomBuffer {
+    atoms: Vec<AtomData>,
+}
+
+#[wasm_bindgen]
+impl AtomBuffer {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        AtomBuffer { atoms: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+
+    /// Empty the buffer, keeping its capacity
+    pub fn clear(&mut self) {
+        self.atoms.clear();
+    }
+
+    pub fn get(&self, index: usize) -> Option<AtomData> {
+        self.atoms.get(index).cloned()
+    }
+
+    /// Copy of the whole buffer; the buffer itself is unchanged
+    pub fn to_vec(&self) -> Vec<AtomData> {
+        self.atoms.clone()
+    }
+}
+
+impl MolecularSystem {
+    // Append the survivors of `indices` to `out`, applying the high-LOD
+    // budget within the appended atoms only
+    pub(crate) fn cull_indices_into(&self, camera: &Camera, proj: &Projection, indices: &[u32], out: &mut Vec<AtomData>) {
+        let camera = self.local_camera(camera);
+        let frame = self.cull_frame(&camera, proj);
+        let start = out.len();
+        let mut distances = Vec::new();
+        for (index, atom) in indices.iter().filter_map(|&i| Some((i as usize, self.all_atoms.get(i as usize)?))) {
+            if self.element_hidden(atom.element) {
+                continue;
+            }
+            // Pinned atoms skip culling and rank nearest, as in the main cull
+            let (lod_level, distance) = match self.cull_atom(&frame, index, self.exploded_position(index)) {
+                _ if self.pinned(index) => (frame.lod.top(), 0.0),
+                AtomCull::Visible(distance) => {
+                    let distance = self.lod_distance(index, distance);
+                    let lod_level = match self.atom_lods.get(index) {
+                        Some(&previous) => frame.lod.sticky_level(distance, previous, self.lod_hysteresis),
+                        None => frame.lod.level(distance),
+                    };
+                    (lod_level, distance)
+                }
+                _ => continue,
+            };
+            out.push(self.visible_atom_data(index, lod_level));
+            distances.push(distance);
+        }
+        if let Some(budget) = self.max_high_lod_atoms {
+            apply_lod_budget(budget, frame.lod.top(), &distances, &mut out[start..]);
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// `get_visible_atoms` into a caller-owned buffer: with `append` the atoms
+    /// are added after what `out` already holds, otherwise they replace it.
+    /// Shares the camera cache with `get_visible_atoms`. Returns how many
+    /// atoms were written.
+    pub fn get_visible_atoms_into(&mut self, camera: &Camera, proj: &Projection, out: &mut AtomBuffer, append: bool) -> usize {
+        self.refresh_visibility(camera, proj);
+        if !append {
+            out.atoms.clear();
+        }
+        out.atoms.extend_from_slice(&self.cached_visible_atoms);
+        self.cached_visible_atoms.len()
+    }
+
+    /// `get_visible_atoms_for_indices` into a caller-owned buffer, replacing or
+    /// (with `append`) extending it. The high-LOD budget applies to this pass's
+    /// atoms alone, not to what the buffer already held. Bypasses the camera
+    /// cache. Returns how many atoms were written.
+    pub fn get_visible_atoms_for_indices_into(
+        &self,
+        camera: &Camera,
+        proj: &Projection,
+        indices: &[u32],
+        out: &mut AtomBuffer,
+        append: bool,
+    ) -> usize {
+        if !append {
+            out.atoms.clear();
+        }
+        let start = out.atoms.len();
+        self.cull_indices_into(camera, proj, indices, &mut out.atoms);
+        out.atoms.len() - start
+    }
+}
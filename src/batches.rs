@@ -0,0 +1,46 @@
+// Progressive delivery of the cached visible set, most important atoms first
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{length, sub, Vec3};
+use crate::{AtomData, MolecularSystem};
+
+/// Priority order over the cached visible set and how much of it has been handed out
+pub(crate) struct VisibleBatches {
+    // Positions in `cached_visible_atoms`, highest LOD first, nearest first within a LOD
+    order: Vec<u32>,
+    cursor: usize,
+}
+
+impl VisibleBatches {
+    fn new(atoms: &[AtomData], eye: Vec3) -> Self {
+        let distances: Vec<f32> = atoms.iter().map(|a| length(sub((a.x, a.y, a.z), eye))).collect();
+        let mut order: Vec<u32> = (0..atoms.len() as u32).collect();
+        order.sort_unstable_by(|&a, &b| {
+            let (a, b) = (a as usize, b as usize);
+            atoms[b].lod_level.cmp(&atoms[a].lod_level).then(distances[a].total_cmp(&distances[b]))
+        });
+        VisibleBatches { order, cursor: 0 }
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Next at most `max` atoms of the cached visible set in priority order
+    /// (highest LOD first, nearest first within a LOD), for drawing the most
+    /// important atoms first and filling in the rest over later frames. Empty
+    /// once the whole set has been returned. The order is computed once per
+    /// cull; any new cull or cache invalidation, such as a camera change, or an
+    /// `update` advancing the animation starts again from the top. Works on whatever
+    /// the last `get_visible_*` call cached.
+    pub fn next_visible_batch(&mut self, max: usize) -> Vec<AtomData> {
+        let Some(eye) = self.last_cull_eye else { return Vec::new() };
+        let atoms = &self.cached_visible_atoms;
+        let batches = self.visible_batches.get_or_insert_with(|| VisibleBatches::new(atoms, eye));
+
+        let end = (batches.cursor + max).min(batches.order.len());
+        let batch = batches.order[batches.cursor..end].iter().map(|&i| atoms[i as usize].clone()).collect();
+        batches.cursor = end;
+        batch
+    }
+}
@@ -0,0 +1,389 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::elements::covalent_radius;
+use crate::grid::SpatialGrid;
+use crate::math::{add, dot, length, normalize, scale, sub};
+use crate::perf::{elapsed_ms, now_ms};
+use crate::{MolecularSystem, RawAtom};
+
+/// Pairs closer than this (Å) are overlapping atoms, not bonds
+const MIN_BOND_DISTANCE: f32 = 0.1;
+
+const HYDROGEN: u32 = 0;
+
+/// Systems smaller than this detect bonds on one thread even with `parallel`
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_ATOMS: usize = 20_000;
+
+// Hydrogen-bond donors and acceptors: F, O, N
+fn is_electronegative(element: u32) -> bool {
+    matches!(element, 1..=3)
+}
+
+/// Bond graph in compressed sparse row form: the neighbors of atom `i` are
+/// `neighbors[offsets[i]..offsets[i + 1]]`, in ascending order
+pub(crate) struct Adjacency {
+    pub(crate) offsets: Vec<u32>,
+    pub(crate) neighbors: Vec<u32>,
+}
+
+impl Adjacency {
+    pub(crate) fn of(&self, atom: usize) -> &[u32] {
+        &self.neighbors[self.offsets[atom] as usize..self.offsets[atom + 1] as usize]
+    }
+}
+
+// Key for the bond cutoff table: the pair in ascending order
+fn element_pair(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+// Bonds as ascending (lower, higher) pairs with duplicates merged
+pub(crate) fn canonical_bonds(mut bonds: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    for bond in &mut bonds {
+        *bond = (bond.0.min(bond.1), bond.0.max(bond.1));
+    }
+    bonds.sort_unstable();
+    bonds.dedup();
+    bonds
+}
+
+// Everything the distance search needs, borrowed apart from the system so
+// threads can share it
+struct BondSearch<'a> {
+    atoms: &'a [RawAtom],
+    grid: SpatialGrid,
+    overrides: &'a HashMap<(u32, u32), f32>,
+    tolerance: f32,
+    max_cutoff: f32,
+}
+
+impl BondSearch<'_> {
+    // Longest bond between two elements: the `set_bond_cutoff` override, else
+    // the covalent radii sum scaled by `tolerance`
+    fn cutoff(&self, a: u32, b: u32) -> f32 {
+        if !self.overrides.is_empty() {
+            if let Some(&cutoff) = self.overrides.get(&element_pair(a, b)) {
+                return cutoff;
+            }
+        }
+        (covalent_radius(a) + covalent_radius(b)) * self.tolerance
+    }
+
+    // Bonds from each atom of `atoms` to higher-indexed atoms, so every pair is
+    // found exactly once however the atoms are split up
+    fn bonds_from(&self, atoms: impl Iterator<Item = usize>, bonds: &mut Vec<(u32, u32)>) {
+        for i in atoms {
+            let atom = &self.atoms[i];
+            self.grid.for_each_within(self.atoms, (atom.x, atom.y, atom.z), self.max_cutoff, |j, distance_sq| {
+                if j <= i || distance_sq < MIN_BOND_DISTANCE * MIN_BOND_DISTANCE {
+                    return;
+                }
+                let cutoff = self.cutoff(atom.element, self.atoms[j].element);
+                if distance_sq <= cutoff * cutoff {
+                    bonds.push((i as u32, j as u32));
+                }
+            });
+        }
+    }
+
+    fn serial(&self) -> Vec<(u32, u32)> {
+        let mut bonds = Vec::new();
+        self.bonds_from(0..self.atoms.len(), &mut bonds);
+        bonds
+    }
+
+    // One thread per core for large systems, else serial
+    #[cfg(feature = "parallel")]
+    fn parallel(&self) -> Vec<(u32, u32)> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        if threads < 2 || self.atoms.len() < PARALLEL_MIN_ATOMS {
+            return self.serial();
+        }
+        self.split_across(threads)
+    }
+
+    // Split the grid's cell-ordered atoms into one contiguous run of cells
+    // (roughly a z slab) per thread. Pairs straddling two runs are still only
+    // found from their lower-indexed atom, so the merged list has no duplicates.
+    #[cfg(feature = "parallel")]
+    fn split_across(&self, threads: usize) -> Vec<(u32, u32)> {
+        let order = self.grid.atoms_in_cell_order();
+        let run = order.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = order
+                .chunks(run)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut bonds = Vec::new();
+                        self.bonds_from(chunk.iter().map(|&i| i as usize), &mut bonds);
+                        bonds
+                    })
+                })
+                .collect();
+            workers.into_iter().flat_map(|worker| worker.join().expect("bond detection thread panicked")).collect()
+        })
+    }
+}
+
+impl MolecularSystem {
+    pub(crate) fn adjacency(&self) -> Adjacency {
+        let n = self.all_atoms.len();
+        let pairs = self.bond_pairs();
+
+        let mut offsets = vec![0u32; n + 1];
+        for &(a, b) in pairs {
+            offsets[a as usize + 1] += 1;
+            offsets[b as usize + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+
+        // Pairs are sorted with a < b, so filling in pair order keeps each list ascending
+        let mut cursor = offsets.clone();
+        let mut neighbors = vec![0u32; offsets[n] as usize];
+        for &(a, b) in pairs {
+            neighbors[cursor[a as usize] as usize] = b;
+            cursor[a as usize] += 1;
+            neighbors[cursor[b as usize] as usize] = a;
+            cursor[b as usize] += 1;
+        }
+
+        Adjacency { offsets, neighbors }
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// CSR offsets of the bond graph (`atom count + 1` entries): atom `i`'s
+    /// neighbors are `adjacency_neighbors()[offsets[i]..offsets[i + 1]]`
+    pub fn adjacency_offsets(&self) -> Vec<u32> {
+        self.adjacency().offsets
+    }
+
+    /// Bonded neighbor indices for every atom, concatenated in atom order and
+    /// ascending within each atom; slice with `adjacency_offsets`
+    pub fn adjacency_neighbors(&self) -> Vec<u32> {
+        self.adjacency().neighbors
+    }
+
+    /// Per bond, in bond-list order: `[mx, my, mz, dirx, diry, dirz, length]`,
+    /// the midpoint, the unit direction from the lower-indexed atom to the
+    /// higher, and the length in Å. For bond-length labels and cylinder impostors.
+    pub fn get_bond_midpoints(&self) -> Vec<f32> {
+        let pairs = self.bond_pairs();
+        let mut out = Vec::with_capacity(pairs.len() * 7);
+        for &(a, b) in pairs {
+            let (a, b) = (&self.all_atoms[a as usize], &self.all_atoms[b as usize]);
+            let d = sub((b.x, b.y, b.z), (a.x, a.y, a.z));
+            let dir = normalize(d).unwrap_or((0.0, 0.0, 0.0));
+            out.extend_from_slice(&[
+                (a.x + b.x) * 0.5, (a.y + b.y) * 0.5, (a.z + b.z) * 0.5,
+                dir.0, dir.1, dir.2,
+                length(d),
+            ]);
+        }
+        out
+    }
+
+    /// Bicolor form of the cached visible bonds, in `get_visible_bonds` order:
+    /// each bond is two half-segments, start to midpoint then midpoint to end,
+    /// of 9 floats each, `[x0, y0, z0, x1, y1, z1, r, g, b]`, colored by the
+    /// element at that end (remapped like the atoms). 18 floats per bond.
+    pub fn get_visible_bonds_split(&self) -> Vec<f32> {
+        let mut out = Vec::with_capacity(self.cached_visible_bonds.len() * 18);
+        for (bond, &(a, b)) in self.cached_visible_bonds.iter().zip(&self.cached_visible_bond_pairs) {
+            let start = (bond.start_x, bond.start_y, bond.start_z);
+            let end = (bond.end_x, bond.end_y, bond.end_z);
+            let mid = scale(add(start, end), 0.5);
+            for (from, to, atom) in [(start, mid, a), (mid, end, b)] {
+                let color = self.element_color(self.display_element(self.all_atoms[atom as usize].element));
+                out.extend_from_slice(&[from.0, from.1, from.2, to.0, to.1, to.2, color[0], color[1], color[2]]);
+            }
+        }
+        out
+    }
+
+    /// Replace the bond list with explicit connectivity: flat `[a, b, a, b, ...]`
+    /// atom index pairs, in any order (duplicates are merged). Explicit bonds
+    /// are kept by `detect_bonds`; see `force_detect_bonds`. Returns the bond
+    /// count; odd-length lists, self-bonds and out-of-range indices are errors
+    /// and leave the bonds unchanged.
+    pub fn set_bonds(&mut self, pairs: &[u32]) -> Result<usize, JsValue> {
+        if !pairs.len().is_multiple_of(2) {
+            return Err(JsValue::from_str(&format!("bond list must be index pairs, got {} indices", pairs.len())));
+        }
+        let n = self.all_atoms.len();
+        let mut bonds = Vec::with_capacity(pairs.len() / 2);
+        for pair in pairs.chunks_exact(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if a as usize >= n || b as usize >= n {
+                return Err(JsValue::from_str(&format!("bond {}-{} is out of range for {} atoms", a, b, n)));
+            }
+            if a == b {
+                return Err(JsValue::from_str(&format!("bond {}-{} joins an atom to itself", a, b)));
+            }
+            bonds.push((a, b));
+        }
+
+        self.bonds = canonical_bonds(bonds);
+        self.explicit_bonds = true;
+        self.explode_directions = OnceCell::new();
+        self.invalidate_camera_cache();
+        Ok(self.bonds.len())
+    }
+
+    /// Whether the bonds were given explicitly (`set_bonds`, or a MOL2 or SDF
+    /// load) rather than detected
+    pub fn has_explicit_bonds(&self) -> bool {
+        self.explicit_bonds
+    }
+
+    /// Detect covalent bonds by distance: atoms `i`, `j` are bonded when closer than
+    /// the cutoff set for their element pair with `set_bond_cutoff`, or otherwise
+    /// `(covalent_radius(i) + covalent_radius(j)) * tolerance`. Replaces the bond
+    /// list and returns the number of bonds found.
+    ///
+    /// Explicit bonds (`has_explicit_bonds`) are kept instead, returning their
+    /// count; `force_detect_bonds` replaces them.
+    ///
+    /// With the `parallel` feature, large systems split the search across
+    /// threads by spatial region; the bonds found are the same.
+    pub fn detect_bonds(&mut self, tolerance: f32) -> usize {
+        if self.explicit_bonds {
+            log!(self, LOG_INFO, "Keeping {} explicit bonds - use force_detect_bonds to replace them", self.bonds.len());
+            return self.bonds.len();
+        }
+        self.force_detect_bonds(tolerance)
+    }
+
+    /// `detect_bonds` even when explicit bonds are present, replacing them
+    pub fn force_detect_bonds(&mut self, tolerance: f32) -> usize {
+        let start = now_ms();
+        let max_radius = self.all_atoms.iter()
+            .map(|atom| covalent_radius(atom.element))
+            .fold(0.0, f32::max);
+        // Overrides only widen the search if both elements are actually present
+        let max_cutoff = self.bond_cutoff_overrides.iter()
+            .filter(|((a, b), _)| self.element_atoms.contains_key(a) && self.element_atoms.contains_key(b))
+            .map(|(_, &cutoff)| cutoff)
+            .fold(2.0 * max_radius * tolerance, f32::max);
+        let search = BondSearch {
+            atoms: &self.all_atoms,
+            grid: SpatialGrid::build(&self.all_atoms, max_cutoff),
+            overrides: &self.bond_cutoff_overrides,
+            tolerance,
+            max_cutoff,
+        };
+
+        #[cfg(feature = "parallel")]
+        let mut bonds = search.parallel();
+        #[cfg(not(feature = "parallel"))]
+        let mut bonds = search.serial();
+        bonds.sort_unstable();
+        self.timings.bonds_ms = elapsed_ms(start);
+
+        log!(self, LOG_INFO, "Detected {} bonds among {} atoms", bonds.len(), self.all_atoms.len());
+
+        self.bonds = bonds;
+        self.explicit_bonds = false;
+        self.explode_directions = OnceCell::new();
+        self.invalidate_camera_cache();
+        self.bonds.len()
+    }
+
+    /// Bond `e1`-`e2` pairs (either order) up to `max` Å in subsequent
+    /// `detect_bonds` calls, in place of the covalent-radius rule and regardless
+    /// of its tolerance, e.g. to stop metal-metal contacts in a dense crystal
+    /// from bonding or to catch long hydrogen bonds to metals. A non-positive or
+    /// non-finite `max` removes the override.
+    pub fn set_bond_cutoff(&mut self, e1: u32, e2: u32, max: f32) {
+        if max > 0.0 && max.is_finite() {
+            self.bond_cutoff_overrides.insert(element_pair(e1, e2), max);
+        } else {
+            self.bond_cutoff_overrides.remove(&element_pair(e1, e2));
+        }
+    }
+
+    /// Drop every `set_bond_cutoff` override
+    pub fn clear_bond_cutoffs(&mut self) {
+        self.bond_cutoff_overrides.clear();
+    }
+
+    /// Find hydrogen bonds as flattened `[donor, hydrogen, acceptor]` index triples.
+    ///
+    /// A hydrogen covalently bonded to an F/O/N donor forms an H-bond with an F/O/N
+    /// acceptor when H···A is within `max_distance` and the D-H···A angle is at
+    /// least `min_angle` degrees. Requires bonds (`detect_bonds`) to identify donors.
+    pub fn detect_hydrogen_bonds(&self, max_distance: f32, min_angle: f32) -> Vec<u32> {
+        let bond_pairs = self.bond_pairs();
+        if bond_pairs.is_empty() {
+            log!(self, LOG_ERRORS, "No bonds available - call detect_bonds before detect_hydrogen_bonds");
+            return Vec::new();
+        }
+
+        let grid = SpatialGrid::build(&self.all_atoms, max_distance);
+        let min_cos = min_angle.to_radians().cos();
+        let mut triples = Vec::new();
+
+        for &(a, b) in bond_pairs {
+            let (a, b) = (a as usize, b as usize);
+            let (donor, hydrogen) = match (self.all_atoms[a].element, self.all_atoms[b].element) {
+                (HYDROGEN, e) if is_electronegative(e) => (b, a),
+                (e, HYDROGEN) if is_electronegative(e) => (a, b),
+                _ => continue,
+            };
+
+            let d = &self.all_atoms[donor];
+            let h = &self.all_atoms[hydrogen];
+            let h_pos = (h.x, h.y, h.z);
+            let Some(to_donor) = normalize(sub((d.x, d.y, d.z), h_pos)) else { continue };
+
+            grid.for_each_within(&self.all_atoms, h_pos, max_distance, |acceptor, _| {
+                let atom = &self.all_atoms[acceptor];
+                if acceptor == donor || !is_electronegative(atom.element) {
+                    return;
+                }
+                let Some(to_acceptor) = normalize(sub((atom.x, atom.y, atom.z), h_pos)) else { return };
+                // Angle at H between H->D and H->A; a straight D-H···A is 180°
+                if dot(to_donor, to_acceptor) <= min_cos {
+                    triples.extend_from_slice(&[donor as u32, hydrogen as u32, acceptor as u32]);
+                }
+            });
+        }
+
+        triples
+    }
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use crate::grid::SpatialGrid;
+    use crate::MolecularSystem;
+
+    use super::BondSearch;
+
+    #[test]
+    fn parallel_search_finds_the_serial_bonds() {
+        let mut system = MolecularSystem::new();
+        system.load_atoms_from_file(5000);
+        let search = BondSearch {
+            atoms: &system.all_atoms,
+            grid: SpatialGrid::build(&system.all_atoms, 3.0),
+            overrides: &system.bond_cutoff_overrides,
+            tolerance: 1.2,
+            max_cutoff: 3.0,
+        };
+
+        let mut serial = search.serial();
+        // Forced split, whatever this machine's thread count
+        let mut parallel = search.split_across(4);
+        serial.sort_unstable();
+        parallel.sort_unstable();
+        assert!(!serial.is_empty());
+        assert_eq!(parallel, serial);
+    }
+}
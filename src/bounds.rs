@@ -0,0 +1,183 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::{add, cross, dot, length, scale, sub, SplitMix64, Vec3};
+use crate::{Camera, MolecularSystem};
+
+type Sphere = (Vec3, f32);
+
+// Near plane never comes closer than this fraction of the far distance, which
+// bounds the depth-buffer precision loss when the camera is inside the molecule
+const MIN_NEAR_FRACTION: f32 = 1e-3;
+
+// Headroom on both planes so atoms right on the sphere aren't clipped
+const CLIP_MARGIN: f32 = 1.05;
+
+// Relative slack in the containment test so rounding can't keep a support
+// point "outside" forever
+const CONTAIN_TOLERANCE: f32 = 1e-5;
+
+fn contains(sphere: Sphere, p: Vec3) -> bool {
+    length(sub(p, sphere.0)) <= sphere.1 * (1.0 + CONTAIN_TOLERANCE) + 1e-6
+}
+
+fn diameter_sphere(a: Vec3, b: Vec3) -> Sphere {
+    let center = scale(add(a, b), 0.5);
+    (center, length(sub(a, center)))
+}
+
+// Smallest sphere with `a`, `b`, `c` on its surface: their circumcircle
+fn circle_sphere(a: Vec3, b: Vec3, c: Vec3) -> Sphere {
+    let u = sub(b, a);
+    let v = sub(c, a);
+    let w = cross(u, v);
+    let w2 = dot(w, w);
+    if w2 <= f32::EPSILON * dot(u, u) * dot(v, v) {
+        // Collinear: the two farthest-apart points span the sphere
+        let pairs = [diameter_sphere(a, b), diameter_sphere(a, c), diameter_sphere(b, c)];
+        return pairs.into_iter().fold(pairs[0], |best, s| if s.1 > best.1 { s } else { best });
+    }
+    let offset = scale(add(scale(cross(v, w), dot(u, u)), scale(cross(w, u), dot(v, v))), 0.5 / w2);
+    (add(a, offset), length(offset))
+}
+
+// Sphere through four points, falling back to the smallest containing
+// circle sphere when they are (nearly) coplanar
+fn four_point_sphere(a: Vec3, b: Vec3, c: Vec3, d: Vec3) -> Sphere {
+    let u = sub(b, a);
+    let v = sub(c, a);
+    let w = sub(d, a);
+    let det = dot(u, cross(v, w));
+    let scale_ref = length(u) * length(v) * length(w);
+    if det.abs() > 1e-6 * scale_ref {
+        let offset = scale(
+            add(add(scale(cross(v, w), dot(u, u)), scale(cross(w, u), dot(v, v))), scale(cross(u, v), dot(w, w))),
+            0.5 / det,
+        );
+        return (add(a, offset), length(offset));
+    }
+
+    let points = [a, b, c, d];
+    [circle_sphere(a, b, d), circle_sphere(a, c, d), circle_sphere(b, c, d), circle_sphere(a, b, c)]
+        .into_iter()
+        .filter(|&s| points.iter().all(|&p| contains(s, p)))
+        .fold(None, |best: Option<Sphere>, s| match best {
+            Some(b) if b.1 <= s.1 => Some(b),
+            _ => Some(s),
+        })
+        .unwrap_or_else(|| circle_sphere(a, b, c))
+}
+
+/// Minimal enclosing sphere (Welzl's algorithm, iterative form). Expected
+/// linear time; points are shuffled with a fixed seed so results are repeatable.
+fn minimal_sphere(mut points: Vec<Vec3>) -> Sphere {
+    let mut rng = SplitMix64::new(0x5EED);
+    for i in (1..points.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        points.swap(i, j);
+    }
+
+    let mut sphere = (points[0], 0.0);
+    for i in 1..points.len() {
+        if contains(sphere, points[i]) {
+            continue;
+        }
+        sphere = (points[i], 0.0);
+        for j in 0..i {
+            if contains(sphere, points[j]) {
+                continue;
+            }
+            sphere = diameter_sphere(points[i], points[j]);
+            for k in 0..j {
+                if contains(sphere, points[k]) {
+                    continue;
+                }
+                sphere = circle_sphere(points[i], points[j], points[k]);
+                for l in 0..k {
+                    if !contains(sphere, points[l]) {
+                        sphere = four_point_sphere(points[i], points[j], points[k], points[l]);
+                    }
+                }
+            }
+        }
+    }
+    sphere
+}
+
+impl MolecularSystem {
+    fn compute_bounding_sphere(&self, exact: bool) -> [f32; 4] {
+        let (center, radius) = if exact {
+            minimal_sphere(self.all_atoms.iter().map(|a| (a.x, a.y, a.z)).collect())
+        } else {
+            let (min, max) = self.atom_extent();
+            diameter_sphere(min, max)
+        };
+        [center.0, center.1, center.2, radius]
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Sphere around all atom centers as `[cx, cy, cz, radius]`, empty when there
+    /// are no atoms. By default it is the bounding box's center and half-diagonal;
+    /// `exact` computes the minimal enclosing sphere instead, which is tighter for
+    /// irregular shapes. Both are cached until atoms move.
+    pub fn bounding_sphere(&self, exact: bool) -> Vec<f32> {
+        if self.all_atoms.is_empty() {
+            return Vec::new();
+        }
+        let cache = &self.bounding_spheres[exact as usize];
+        let sphere = cache.get().unwrap_or_else(|| {
+            let sphere = self.compute_bounding_sphere(exact);
+            cache.set(Some(sphere));
+            sphere
+        });
+        sphere.to_vec()
+    }
+
+    /// `[near, far]` that fit the whole structure (drawn atom radii included) as
+    /// seen from `camera`, for passing straight to the cull calls: `far` already
+    /// accounts for distance culling at part of the far plane. Empty when there
+    /// are no atoms.
+    pub fn suggest_clip_planes(&self, camera: &Camera) -> Vec<f32> {
+        let sphere = self.bounding_sphere(false);
+        if sphere.is_empty() {
+            return Vec::new();
+        }
+        // Animated radii swing up to 0.02 past the element radius
+        let radius = sphere[3] + self.max_element_radius() + 0.02;
+        let camera = self.local_camera(camera);
+        let distance = length(sub((camera.x, camera.y, camera.z), (sphere[0], sphere[1], sphere[2])));
+
+        let far = (distance + radius) * CLIP_MARGIN / self.far_cull_fraction;
+        let near = ((distance - radius) / CLIP_MARGIN).max(far * MIN_NEAR_FRACTION);
+        vec![near, far]
+    }
+
+    /// `[min_x, min_y, min_z, max_x, max_y, max_z]` of every atom center in
+    /// `camera`'s view space (x right, y up, z towards the viewer, origin at
+    /// the eye), so atoms in front have negative z and `-max_z..-min_z` is the
+    /// depth range the structure occupies: the input for tight near/far
+    /// planes or a depth color ramp. Exact, one pass over the atoms; radii are
+    /// not included. Empty when there are no atoms or the camera looks at its
+    /// own position.
+    pub fn view_space_bounds(&self, camera: &Camera) -> Vec<f32> {
+        let camera = self.local_camera(camera);
+        let Some((forward, right, up)) = camera.view_basis() else { return Vec::new() };
+        if self.all_atoms.is_empty() {
+            return Vec::new();
+        }
+
+        let eye = (camera.x, camera.y, camera.z);
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for atom in &self.all_atoms {
+            let rel = sub((atom.x, atom.y, atom.z), eye);
+            let view = [dot(rel, right), dot(rel, up), -dot(rel, forward)];
+            for k in 0..3 {
+                min[k] = min[k].min(view[k]);
+                max[k] = max[k].max(view[k]);
+            }
+        }
+        vec![min[0], min[1], min[2], max[0], max[1], max[2]]
+    }
+}
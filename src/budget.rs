@@ -0,0 +1,95 @@
+// Closed-loop LOD quality: shrink LOD distances while frames run over budget,
+// grow them back when there is headroom
+
+use wasm_bindgen::prelude::*;
+
+use crate::MolecularSystem;
+
+/// Weight of the newest sample in the smoothed frame time
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+
+/// Frames between adjustments, so each change shows up in the average first
+const ADJUST_INTERVAL: u32 = 10;
+
+/// Quality drops when the smoothed time is this far over target, and rises when
+/// it is this far under; in between nothing changes
+const OVER_BUDGET: f32 = 1.1;
+const UNDER_BUDGET: f32 = 0.8;
+
+/// Per-adjustment steps: back off quickly, recover slowly
+const QUALITY_DECREASE: f32 = 0.85;
+const QUALITY_INCREASE: f32 = 1.05;
+
+pub(crate) const MIN_LOD_QUALITY: f32 = 0.1;
+pub(crate) const MAX_LOD_QUALITY: f32 = 1.0;
+
+pub(crate) struct FrameBudget {
+    target_ms: f32,
+    average_ms: Option<f32>,
+    frames_since_adjust: u32,
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Hold frames to `ms` by adapting the LOD quality from the times passed to
+    /// `report_frame_time`. Off by default; a non-positive or non-finite value
+    /// turns it off again and restores full quality.
+    pub fn set_target_frame_ms(&mut self, ms: f32) {
+        if !(ms > 0.0 && ms.is_finite()) {
+            self.frame_budget = None;
+            self.set_lod_quality(MAX_LOD_QUALITY);
+            return;
+        }
+        self.frame_budget = Some(FrameBudget { target_ms: ms, average_ms: None, frames_since_adjust: 0 });
+    }
+
+    /// Report how long the last frame took. With a target set, the times are
+    /// smoothed (exponential moving average) and every 10 frames the quality
+    /// multiplier on the LOD distance thresholds is adjusted: ×0.85 when the
+    /// average is over 110% of target, ×1.05 when under 80%, unchanged in
+    /// between. Quality stays within 0.1..1.0, where 1.0 is the normal LOD
+    /// distances, so it can only make rendering cheaper than the default. A
+    /// change invalidates the camera cache. Does nothing without a target.
+    pub fn report_frame_time(&mut self, ms: f32) {
+        let Some(budget) = &mut self.frame_budget else { return };
+        if !(ms >= 0.0 && ms.is_finite()) {
+            return;
+        }
+
+        let average = match budget.average_ms {
+            Some(average) => average + FRAME_TIME_SMOOTHING * (ms - average),
+            None => ms,
+        };
+        budget.average_ms = Some(average);
+        budget.frames_since_adjust += 1;
+        if budget.frames_since_adjust < ADJUST_INTERVAL {
+            return;
+        }
+        budget.frames_since_adjust = 0;
+
+        let step = if average > budget.target_ms * OVER_BUDGET {
+            QUALITY_DECREASE
+        } else if average < budget.target_ms * UNDER_BUDGET {
+            QUALITY_INCREASE
+        } else {
+            return;
+        };
+        let quality = (self.lod_quality * step).clamp(MIN_LOD_QUALITY, MAX_LOD_QUALITY);
+        log!(self, LOG_VERBOSE, "Frame time {:.1} ms vs target {:.1} ms - LOD quality {:.2}", average, budget.target_ms, quality);
+        self.set_lod_quality(quality);
+    }
+
+    /// Current multiplier on the LOD distance thresholds (1.0 unless adapted)
+    pub fn get_lod_quality(&self) -> f32 {
+        self.lod_quality
+    }
+}
+
+impl MolecularSystem {
+    fn set_lod_quality(&mut self, quality: f32) {
+        if quality != self.lod_quality {
+            self.lod_quality = quality;
+            self.invalidate_camera_cache();
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::Vec3;
+use crate::MolecularSystem;
+
+/// Arrangement of atoms in the synthetic generator's cubic cells
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lattice {
+    /// One atom per cell
+    SimpleCubic,
+    /// Corner plus body center: two atoms per cell
+    BodyCentered,
+    /// Corner plus face centers: four atoms per cell
+    FaceCentered,
+}
+
+impl Lattice {
+    /// Atom offsets within one cell, in units of the cell edge
+    pub(crate) fn basis(self) -> &'static [Vec3] {
+        match self {
+            Lattice::SimpleCubic => &[(0.0, 0.0, 0.0)],
+            Lattice::BodyCentered => &[(0.0, 0.0, 0.0), (0.5, 0.5, 0.5)],
+            Lattice::FaceCentered => &[(0.0, 0.0, 0.0), (0.5, 0.5, 0.0), (0.5, 0.0, 0.5), (0.0, 0.5, 0.5)],
+        }
+    }
+}
+
+/// Order in which the synthetic generator fills its cubic grid of cells,
+/// which is also the storage order of the generated atoms
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FillOrder {
+    /// x fastest, then y, then z
+    Xyz,
+    /// z fastest, then y, then x
+    Zyx,
+    /// Z-order curve: neighbors in storage are neighbors in space, which helps
+    /// cache locality in culling and grid builds
+    Morton,
+}
+
+// Bits of a Morton code that belong to one axis, packed together
+fn compact_bits(mut code: u64) -> usize {
+    code &= 0x1249_2492_4924_9249;
+    code = (code | (code >> 2)) & 0x10c3_0c30_c30c_30c3;
+    code = (code | (code >> 4)) & 0x100f_00f0_0f00_f00f;
+    code = (code | (code >> 8)) & 0x001f_0000_ff00_00ff;
+    code = (code | (code >> 16)) & 0x001f_0000_0000_ffff;
+    code = (code | (code >> 32)) & 0x0000_0000_001f_ffff;
+    code as usize
+}
+
+impl FillOrder {
+    /// Cell coordinates `[x, y, z]` of an `n`-per-axis grid in fill order
+    pub(crate) fn cells(self, n: usize) -> Box<dyn Iterator<Item = [usize; 3]>> {
+        match self {
+            FillOrder::Xyz => Box::new((0..n * n * n).map(move |c| [c % n, (c / n) % n, c / (n * n)])),
+            FillOrder::Zyx => Box::new((0..n * n * n).map(move |c| [c / (n * n), (c / n) % n, c % n])),
+            // Walk the curve over the enclosing power-of-two cube, skipping
+            // cells outside the grid
+            FillOrder::Morton => {
+                let side = n.next_power_of_two() as u64;
+                Box::new((0..side * side * side)
+                    .map(|code| [compact_bits(code), compact_bits(code >> 1), compact_bits(code >> 2)])
+                    .filter(move |cell| cell.iter().all(|&c| c < n)))
+            }
+        }
+    }
+}
+
+/// Collects generator settings and creates a populated system in one `build()`,
+/// instead of a chain of setters on a live system.
+///
+/// ```js
+/// const system = new MolecularSystemBuilder()
+///     .atom_count(100000)
+///     .grid_size(1.5)
+///     .lattice(Lattice.FaceCentered)
+///     .build();
+/// ```
+#[wasm_bindgen]
+pub struct MolecularSystemBuilder {
+    atom_count: usize,
+    grid_size: f32,
+    lattice: Lattice,
+    fill_order: FillOrder,
+    seed: u64,
+    element_weights: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl MolecularSystemBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        MolecularSystemBuilder {
+            atom_count: 0,
+            grid_size: 1.0,
+            lattice: Lattice::SimpleCubic,
+            fill_order: FillOrder::Xyz,
+            seed: 0,
+            element_weights: Vec::new(),
+        }
+    }
+
+    pub fn atom_count(mut self, count: usize) -> Self {
+        self.atom_count = count;
+        self
+    }
+
+    /// Cell edge length
+    pub fn grid_size(mut self, size: f32) -> Self {
+        self.grid_size = size;
+        self
+    }
+
+    pub fn lattice(mut self, lattice: Lattice) -> Self {
+        self.lattice = lattice;
+        self
+    }
+
+    pub fn fill_order(mut self, order: FillOrder) -> Self {
+        self.fill_order = order;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Relative element weights, as for `set_generator_element_distribution`
+    pub fn element_distribution(mut self, weights: &[f32]) -> Self {
+        self.element_weights = weights.to_vec();
+        self
+    }
+
+    /// Create the system and generate its atoms once. Fails on a non-positive grid
+    /// size or invalid element weights.
+    pub fn build(self) -> Result<MolecularSystem, JsValue> {
+        if !(self.grid_size > 0.0 && self.grid_size.is_finite()) {
+            return Err(JsValue::from_str("grid size must be positive"));
+        }
+
+        let mut system = MolecularSystem::new();
+        system.set_grid_size(self.grid_size);
+        system.set_generator_lattice(self.lattice);
+        system.set_generator_fill_order(self.fill_order);
+        system.set_generator_seed(self.seed);
+        system.set_generator_element_distribution(&self.element_weights)?;
+        system.load_atoms_from_file(self.atom_count);
+        Ok(system)
+    }
+}
+
+impl Default for MolecularSystemBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,54 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::{dot, length, scale, Vec3};
+use crate::MolecularSystem;
+
+/// Half-space `normal · p + offset >= 0`, with a unit normal so `offset` is in Å
+#[derive(Clone, Copy)]
+pub(crate) struct ClipPlane {
+    normal: Vec3,
+    offset: f32,
+}
+
+impl ClipPlane {
+    // Signed distance of `p` from the plane, negative on the hidden side
+    fn distance(&self, p: Vec3) -> f32 {
+        dot(self.normal, p) + self.offset
+    }
+
+    pub(crate) fn hides(&self, p: Vec3) -> bool {
+        self.distance(p) < 0.0
+    }
+
+    // Whether every point of the sphere is on the hidden side
+    pub(crate) fn hides_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.distance(center) < -radius
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Hide atoms on the negative side of the plane `nx*x + ny*y + nz*z + d = 0`
+    /// for cutaway views; with several planes an atom must be on the positive
+    /// side of all of them. The normal need not be unit length. Planes are in
+    /// the atoms' own coordinates, under any `set_world_transform`, and clipped
+    /// atoms count as frustum-culled in `get_cull_stats`. A zero or non-finite
+    /// plane is logged and ignored.
+    pub fn add_clip_plane(&mut self, nx: f32, ny: f32, nz: f32, d: f32) {
+        let norm = length((nx, ny, nz));
+        if !(norm > 0.0 && norm.is_finite() && d.is_finite()) {
+            log!(self, LOG_ERRORS, "Ignoring clip plane ({}, {}, {}, {}): needs a finite non-zero normal", nx, ny, nz, d);
+            return;
+        }
+        self.clip_planes.push(ClipPlane { normal: scale((nx, ny, nz), 1.0 / norm), offset: d / norm });
+        self.invalidate_camera_cache();
+    }
+
+    /// Remove every `add_clip_plane` plane
+    pub fn clear_clip_planes(&mut self) {
+        if !self.clip_planes.is_empty() {
+            self.clip_planes.clear();
+            self.invalidate_camera_cache();
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::adp::rotate_adp;
+use crate::backbone::NOT_ALPHA_CARBON;
+use crate::math::{add, cross, dot, scale, sub, Matrix3, Vec3};
+use crate::{MolecularSystem, RawAtom};
+
+/// Parallelepiped spanned by three lattice vectors from an origin
+#[derive(Clone, Copy)]
+pub(crate) struct UnitCell {
+    pub(crate) a: Vec3,
+    pub(crate) b: Vec3,
+    pub(crate) c: Vec3,
+    pub(crate) origin: Vec3,
+}
+
+impl UnitCell {
+    // Inverse of the column matrix [a b c], None if the cell is degenerate
+    pub(crate) fn inverse(&self) -> Option<[[f32; 3]; 3]> {
+        let (a, b, c) = (self.a, self.b, self.c);
+        let det = dot(a, cross(b, c));
+        if det.abs() < 1e-8 {
+            return None;
+        }
+        // Rows of the inverse are the reciprocal vectors
+        let ra = scale(cross(b, c), 1.0 / det);
+        let rb = scale(cross(c, a), 1.0 / det);
+        let rc = scale(cross(a, b), 1.0 / det);
+        Some([[ra.0, ra.1, ra.2], [rb.0, rb.1, rb.2], [rc.0, rc.1, rc.2]])
+    }
+
+    fn fractional(&self, inverse: &[[f32; 3]; 3], p: Vec3) -> Vec3 {
+        fractional_offset(inverse, sub(p, self.origin))
+    }
+
+    /// Shortest periodic image of a displacement (minimum-image convention)
+    pub(crate) fn minimum_image(&self, inverse: &[[f32; 3]; 3], d: Vec3) -> Vec3 {
+        let f = fractional_offset(inverse, d);
+        self.offset(f.0 - f.0.round(), f.1 - f.1.round(), f.2 - f.2.round())
+    }
+
+    pub(crate) fn volume(&self) -> f32 {
+        dot(self.a, cross(self.b, self.c)).abs()
+    }
+
+    // Cartesian offset of a fractional displacement (no origin)
+    pub(crate) fn offset(&self, u: f32, v: f32, w: f32) -> Vec3 {
+        add(add(scale(self.a, u), scale(self.b, v)), scale(self.c, w))
+    }
+
+    // Cartesian position of fractional coordinates (u, v, w)
+    fn point(&self, u: f32, v: f32, w: f32) -> Vec3 {
+        add(self.origin, (
+            u * self.a.0 + v * self.b.0 + w * self.c.0,
+            u * self.a.1 + v * self.b.1 + w * self.c.1,
+            u * self.a.2 + v * self.b.2 + w * self.c.2,
+        ))
+    }
+}
+
+// Fractional form of a Cartesian displacement
+fn fractional_offset(inverse: &[[f32; 3]; 3], d: Vec3) -> Vec3 {
+    (
+        inverse[0][0] * d.0 + inverse[0][1] * d.1 + inverse[0][2] * d.2,
+        inverse[1][0] * d.0 + inverse[1][1] * d.1 + inverse[1][2] * d.2,
+        inverse[2][0] * d.0 + inverse[2][1] * d.1 + inverse[2][2] * d.2,
+    )
+}
+
+// Fractional coordinate wrapped into [0, 1)
+fn wrap_unit(f: f32) -> f32 {
+    let wrapped = f - f.floor();
+    // Rounding can land exactly on 1.0 for tiny negative inputs
+    if wrapped >= 1.0 { 0.0 } else { wrapped }
+}
+
+fn vec3_arg(name: &str, values: &[f32]) -> Result<Vec3, JsValue> {
+    match values {
+        [x, y, z] => Ok((*x, *y, *z)),
+        _ => Err(JsValue::from_str(&format!("{} must have 3 components, got {}", name, values.len()))),
+    }
+}
+
+/// Symmetry copies closer than this (Å) to an existing atom of the same element
+/// are treated as the same atom sitting on a special position
+const SYMMETRY_MERGE_TOLERANCE: f32 = 0.1;
+
+// Cell of the merge hash for a position
+fn merge_cell(x: f32, y: f32, z: f32) -> (i32, i32, i32) {
+    (
+        (x / SYMMETRY_MERGE_TOLERANCE).floor() as i32,
+        (y / SYMMETRY_MERGE_TOLERANCE).floor() as i32,
+        (z / SYMMETRY_MERGE_TOLERANCE).floor() as i32,
+    )
+}
+
+// Append each copy's entry, made from its source atom's, to a per-atom
+// vector; empty vectors stay empty
+fn extend_copies<T: Copy>(values: &mut Vec<T>, copies: &[(usize, usize)], entry: impl Fn(T, usize) -> T) {
+    if !values.is_empty() {
+        let appended: Vec<T> = copies.iter().map(|&(i, k)| entry(values[i], k)).collect();
+        values.extend(appended);
+    }
+}
+
+impl MolecularSystem {
+    fn cell_with_inverse(&self) -> Result<(UnitCell, [[f32; 3]; 3]), JsValue> {
+        let cell = self.unit_cell.ok_or_else(|| JsValue::from_str("no unit cell set"))?;
+        let inverse = cell.inverse().ok_or_else(|| JsValue::from_str("unit cell is degenerate"))?;
+        Ok((cell, inverse))
+    }
+
+    // Per-atom data and bonds for the copies `apply_symmetry_operators`
+    // appended: `copies` holds each copy's source atom and operator,
+    // `placed[k][i]` the atom operator `k` turned atom `i` into
+    fn expand_per_atom_data(&mut self, operators: &[f32], copies: &[(usize, usize)], placed: &[Vec<usize>]) {
+        let rotations: Vec<Matrix3> = operators.chunks_exact(12)
+            .map(|op| [[op[0], op[1], op[2]], [op[4], op[5], op[6]], [op[8], op[9], op[10]]])
+            .collect();
+        let turn = |v: Vec3, k: usize| {
+            let [x, y, z] = rotations[k].map(|row| dot((row[0], row[1], row[2]), v));
+            (x, y, z)
+        };
+        // Copies of f64 positions are placed in f64, then narrowed
+        extend_copies(&mut self.precise_positions, copies, |p, k| {
+            let op = &operators[k * 12..k * 12 + 12];
+            std::array::from_fn(|r| (0..3).map(|c| op[r * 4 + c] as f64 * p[c]).sum::<f64>() + op[r * 4 + 3] as f64)
+        });
+        let n = self.all_atoms.len() - copies.len();
+        for (atom, p) in self.all_atoms[n..].iter_mut().zip(self.precise_positions.get(n..).unwrap_or_default()) {
+            (atom.x, atom.y, atom.z) = (p[0] as f32, p[1] as f32, p[2] as f32);
+        }
+        extend_copies(&mut self.velocities, copies, turn);
+        extend_copies(&mut self.forces, copies, turn);
+        extend_copies(&mut self.adps, copies, |u, k| rotate_adp(&rotations[k], &u));
+        extend_copies(&mut self.b_factors, copies, |b, _| b);
+        extend_copies(&mut self.selected, copies, |selected, _| selected);
+        extend_copies(&mut self.secondary_structure, copies, |code, _| code);
+        extend_copies(&mut self.importance, copies, |importance, _| importance);
+
+        // Chain codes past the previous operator's, so backbone traces never
+        // join one copy to another
+        let stride = self.ca_chains.iter().filter(|&&c| c != NOT_ALPHA_CARBON).max().map_or(0, |&c| c + 1);
+        extend_copies(&mut self.ca_chains, copies, |c, k| {
+            if c == NOT_ALPHA_CARBON { c } else { c + (k as u32 + 1) * stride }
+        });
+
+        if !copies.is_empty() {
+            let originals: Vec<u32> = (0..n).chain(copies.iter().map(|&(i, _)| i))
+                .map(|i| self.original_index(i))
+                .collect();
+            self.original_indices = originals;
+        }
+
+        // A bond to a merged copy lands on the atom it merged into
+        let mut bonds = self.bonds.clone();
+        for at in placed {
+            bonds.extend(self.bonds.iter()
+                .map(|&(a, b)| (at[a as usize] as u32, at[b as usize] as u32))
+                .filter(|&(a, b)| a != b)
+                .map(|(a, b)| (a.min(b), a.max(b))));
+        }
+        bonds.sort_unstable();
+        bonds.dedup();
+        self.bonds = bonds;
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Set the unit cell from lattice vectors `a`, `b`, `c` and the cell origin (3 floats each)
+    pub fn set_unit_cell(&mut self, a: &[f32], b: &[f32], c: &[f32], origin: &[f32]) -> Result<(), JsValue> {
+        self.unit_cell = Some(UnitCell {
+            a: vec3_arg("a", a)?,
+            b: vec3_arg("b", b)?,
+            c: vec3_arg("c", c)?,
+            origin: vec3_arg("origin", origin)?,
+        });
+        Ok(())
+    }
+
+    /// Load atoms given in fractional coordinates, replacing the current data:
+    /// `frac` is `[u, v, w]` per atom, `elements` one element id per atom and
+    /// `cell` the lattice vectors `a`, `b`, `c` as 9 floats. Positions are
+    /// converted to Cartesian and the cell becomes the unit cell, with its
+    /// origin at zero. Returns the atom count.
+    pub fn load_fractional(&mut self, frac: &[f32], elements: &[u32], cell: &[f32]) -> Result<usize, JsValue> {
+        if frac.len() != elements.len() * 3 {
+            return Err(JsValue::from_str(&format!(
+                "fractional coordinates must be 3 floats per atom: got {} floats for {} elements",
+                frac.len(), elements.len()
+            )));
+        }
+        if cell.len() != 9 {
+            return Err(JsValue::from_str(&format!("cell must have 9 floats (a, b, c), got {}", cell.len())));
+        }
+        let unit_cell = UnitCell {
+            a: (cell[0], cell[1], cell[2]),
+            b: (cell[3], cell[4], cell[5]),
+            c: (cell[6], cell[7], cell[8]),
+            origin: (0.0, 0.0, 0.0),
+        };
+        if unit_cell.inverse().is_none() {
+            return Err(JsValue::from_str("unit cell is degenerate"));
+        }
+
+        self.all_atoms = frac
+            .chunks_exact(3)
+            .zip(elements)
+            .map(|(f, &element)| {
+                let (x, y, z) = unit_cell.point(f[0], f[1], f[2]);
+                RawAtom { x, y, z, element, occupancy: 1.0 }
+            })
+            .collect();
+        self.finish_load();
+        self.unit_cell = Some(unit_cell);
+        self.load_warnings.clear();
+        self.loaded_from_file = true;
+
+        log!(self, LOG_INFO, "Loaded {} atoms from fractional coordinates", self.all_atoms.len());
+        Ok(self.all_atoms.len())
+    }
+
+    /// The 12 cell edges as line segments: 24 endpoints, `[x, y, z]` each.
+    /// Empty if no unit cell is set.
+    pub fn get_unit_cell_edges(&self) -> Vec<f32> {
+        let Some(cell) = &self.unit_cell else { return Vec::new() };
+
+        let mut edges = Vec::with_capacity(12 * 2 * 3);
+        for i in 0..2 {
+            for j in 0..2 {
+                let (i, j) = (i as f32, j as f32);
+                // Edges along a, b and c respectively
+                for (start, end) in [
+                    ((0.0, i, j), (1.0, i, j)),
+                    ((i, 0.0, j), (i, 1.0, j)),
+                    ((i, j, 0.0), (i, j, 1.0)),
+                ] {
+                    let p = cell.point(start.0, start.1, start.2);
+                    let q = cell.point(end.0, end.1, end.2);
+                    edges.extend_from_slice(&[p.0, p.1, p.2, q.0, q.1, q.2]);
+                }
+            }
+        }
+        edges
+    }
+
+    /// Map every atom back into the primary cell (fractional coordinates in [0, 1))
+    pub fn wrap_into_cell(&mut self) -> Result<(), JsValue> {
+        let (cell, inverse) = self.cell_with_inverse()?;
+
+        // f64 positions move by the same whole cells, applied in f64
+        let whole_cells = |f: Vec3| cell.offset(f.0 - wrap_unit(f.0), f.1 - wrap_unit(f.1), f.2 - wrap_unit(f.2));
+        let moved = self.move_precise(|p| {
+            let shift = whole_cells(cell.fractional(&inverse, (p[0] as f32, p[1] as f32, p[2] as f32)));
+            [p[0] - shift.0 as f64, p[1] - shift.1 as f64, p[2] - shift.2 as f64]
+        });
+        if !moved {
+            for atom in &mut self.all_atoms {
+                let f = cell.fractional(&inverse, (atom.x, atom.y, atom.z));
+                let p = cell.point(wrap_unit(f.0), wrap_unit(f.1), wrap_unit(f.2));
+                (atom.x, atom.y, atom.z) = p;
+            }
+        }
+
+        self.positions_changed();
+        Ok(())
+    }
+
+    /// Undo wrapping for bonded groups: walk each connected component of the bond
+    /// graph and shift atoms by whole lattice vectors so every bond takes its
+    /// shortest periodic image. Bonds must describe intact molecules (explicit
+    /// connectivity, or `detect_bonds` run before wrapping). Returns how many
+    /// atoms were moved.
+    pub fn unwrap_molecules(&mut self) -> Result<usize, JsValue> {
+        let (cell, inverse) = self.cell_with_inverse()?;
+
+        let n = self.all_atoms.len();
+        let adjacency = self.adjacency();
+
+        let mut visited = vec![false; n];
+        let mut moved = 0;
+        let mut stack = Vec::new();
+        for root in 0..n {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            stack.push(root);
+
+            while let Some(u) = stack.pop() {
+                let pu = cell.fractional(&inverse, (self.all_atoms[u].x, self.all_atoms[u].y, self.all_atoms[u].z));
+                for &v in adjacency.of(u) {
+                    let v = v as usize;
+                    if visited[v] {
+                        continue;
+                    }
+                    visited[v] = true;
+                    stack.push(v);
+
+                    let atom = &mut self.all_atoms[v];
+                    let pv = cell.fractional(&inverse, (atom.x, atom.y, atom.z));
+                    let shift = ((pv.0 - pu.0).round(), (pv.1 - pu.1).round(), (pv.2 - pu.2).round());
+                    if shift != (0.0, 0.0, 0.0) {
+                        let offset = cell.offset(shift.0, shift.1, shift.2);
+                        (atom.x, atom.y, atom.z) = sub((atom.x, atom.y, atom.z), offset);
+                        if let Some(p) = self.precise_positions.get_mut(v) {
+                            *p = [p[0] - offset.0 as f64, p[1] - offset.1 as f64, p[2] - offset.2 as f64];
+                            (atom.x, atom.y, atom.z) = (p[0] as f32, p[1] as f32, p[2] as f32);
+                        }
+                        moved += 1;
+                    }
+                }
+            }
+        }
+
+        self.positions_changed();
+        Ok(moved)
+    }
+
+    /// Expand the current atoms by crystallographic symmetry operators.
+    ///
+    /// `operators` is a flat list of 3x4 row-major matrices (12 floats each:
+    /// rotation row then translation for x, y, z) acting on Cartesian
+    /// coordinates. Copies landing on an existing atom are merged away; every
+    /// original atom is kept at its index, even ones closer together than the
+    /// merge tolerance. Returns the new atom count.
+    ///
+    /// Per-atom data carries over to each copy: velocities, forces and
+    /// displacement tensors turned by the operator's rotation, B-factors,
+    /// selection, importance and secondary structure as they are. Bonds are
+    /// repeated between copies, each copy's backbone traces as chains of its
+    /// own, and `get_original_indices` reports a copy's source atom.
+    pub fn apply_symmetry_operators(&mut self, operators: &[f32]) -> Result<usize, JsValue> {
+        if !operators.len().is_multiple_of(12) {
+            return Err(JsValue::from_str(&format!(
+                "symmetry operators must be 12 floats each, got {} floats", operators.len()
+            )));
+        }
+
+        let source = std::mem::take(&mut self.all_atoms);
+        let n = source.len();
+        let mut expanded: Vec<RawAtom> = Vec::with_capacity(n * (operators.len() / 12).max(1));
+        let mut occupied: HashMap<(i32, i32, i32), Vec<usize>> = HashMap::new();
+        let tolerance_sq = SYMMETRY_MERGE_TOLERANCE * SYMMETRY_MERGE_TOLERANCE;
+
+        // Copies are merged into atoms already placed; originals never are.
+        // Returns where the atom ended up
+        let mut insert = |atom: RawAtom, expanded: &mut Vec<RawAtom>, merge: bool| {
+            let (cx, cy, cz) = merge_cell(atom.x, atom.y, atom.z);
+            if merge {
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        for dz in -1..=1 {
+                            let Some(bucket) = occupied.get(&(cx + dx, cy + dy, cz + dz)) else { continue };
+                            for &i in bucket {
+                                let other = &expanded[i];
+                                let d = (other.x - atom.x).powi(2) + (other.y - atom.y).powi(2) + (other.z - atom.z).powi(2);
+                                if other.element == atom.element && d <= tolerance_sq {
+                                    return i;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            occupied.entry((cx, cy, cz)).or_default().push(expanded.len());
+            expanded.push(atom);
+            expanded.len() - 1
+        };
+
+        // Originals first, all of them, so they keep their indices
+        for &atom in &source {
+            insert(atom, &mut expanded, false);
+        }
+        // Source atom and operator of each copy, and where each operator
+        // put each source atom
+        let mut copies: Vec<(usize, usize)> = Vec::new();
+        let mut placed: Vec<Vec<usize>> = Vec::new();
+        for (k, op) in operators.chunks_exact(12).enumerate() {
+            let mut at = Vec::with_capacity(n);
+            for (i, &atom) in source.iter().enumerate() {
+                let x = op[0] * atom.x + op[1] * atom.y + op[2] * atom.z + op[3];
+                let y = op[4] * atom.x + op[5] * atom.y + op[6] * atom.z + op[7];
+                let z = op[8] * atom.x + op[9] * atom.y + op[10] * atom.z + op[11];
+                let index = insert(RawAtom { x, y, z, ..atom }, &mut expanded, true);
+                if index == n + copies.len() {
+                    copies.push((i, k));
+                }
+                at.push(index);
+            }
+            placed.push(at);
+        }
+
+        log!(self, LOG_INFO, "Symmetry expansion: {} operators, {} -> {} atoms",
+             operators.len() / 12, n, expanded.len());
+
+        self.all_atoms = expanded;
+        self.expand_per_atom_data(operators, &copies, &placed);
+        self.total_atom_count = self.all_atoms.len();
+        self.indices_changed();
+        self.positions_changed();
+        Ok(self.all_atoms.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    #[test]
+    fn symmetry_copies_keep_per_atom_data_and_bonds() {
+        let mut system = MolecularSystem::new();
+        system.load_pdb(concat!(
+            "ATOM      1  C   LIG A   1       0.000   0.000   0.000  1.00 10.00           C\n",
+            "ATOM      2  O   LIG A   1       1.200   0.000   0.000  1.00 20.00           O\n",
+        )).unwrap();
+        system.detect_bonds(1.2);
+        system.set_selection(&[1]).unwrap();
+
+        // Inversion through the carbon, which sits on it and merges away
+        let inversion = [-1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0];
+        assert_eq!(system.apply_symmetry_operators(&inversion).unwrap(), 3);
+        assert_eq!(system.all_atoms[2].x, -1.2);
+        assert_eq!(system.b_factors, [10.0, 20.0, 20.0]);
+        assert_eq!(system.get_selection(), [1, 2]);
+        assert_eq!(system.get_original_indices(), [0, 1, 1]);
+        assert_eq!(system.bond_pairs(), [(0, 1), (0, 2)]);
+    }
+}
@@ -0,0 +1,117 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::SpatialGrid;
+use crate::MolecularSystem;
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Merge atoms closer than `tolerance` (any element), keeping the lowest
+    /// index of each group. Bonds to dropped atoms move to the atom that was
+    /// kept. Returns the number of atoms removed.
+    pub fn deduplicate(&mut self, tolerance: f32) -> usize {
+        let n = self.all_atoms.len();
+        if n < 2 || tolerance.is_nan() || tolerance <= 0.0 {
+            return 0;
+        }
+
+        // Each atom maps to the survivor it merges into (itself if kept)
+        let grid = SpatialGrid::build(&self.all_atoms, tolerance);
+        let mut survivor: Vec<usize> = (0..n).collect();
+        for i in 0..n {
+            if survivor[i] != i {
+                continue;
+            }
+            let atom = &self.all_atoms[i];
+            grid.for_each_within(&self.all_atoms, (atom.x, atom.y, atom.z), tolerance, |j, _| {
+                if j > i && survivor[j] == j {
+                    survivor[j] = i;
+                }
+            });
+        }
+
+        let mut remap = vec![u32::MAX; n];
+        let mut kept = 0;
+        for i in 0..n {
+            if survivor[i] == i {
+                remap[i] = kept;
+                kept += 1;
+            }
+        }
+        let removed = n - kept as usize;
+        if removed == 0 {
+            return 0;
+        }
+
+        let new_index = |i: u32| remap[survivor[i as usize]];
+        let mut bonds: Vec<(u32, u32)> = self.bonds.iter()
+            .map(|&(a, b)| (new_index(a), new_index(b)))
+            .filter(|&(a, b)| a != b)
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        bonds.sort_unstable();
+        bonds.dedup();
+
+        let is_kept = |i: &usize| survivor[*i] == *i;
+        self.original_indices = (0..n).filter(is_kept).map(|i| self.original_index(i)).collect();
+        self.all_atoms = (0..n).filter(is_kept).map(|i| self.all_atoms[i]).collect();
+        if !self.precise_positions.is_empty() {
+            self.precise_positions = (0..n).filter(is_kept).map(|i| self.precise_positions[i]).collect();
+        }
+        if !self.velocities.is_empty() {
+            self.velocities = (0..n).filter(is_kept).map(|i| self.velocities[i]).collect();
+        }
+        if !self.forces.is_empty() {
+            self.forces = (0..n).filter(is_kept).map(|i| self.forces[i]).collect();
+        }
+        if !self.adps.is_empty() {
+            self.adps = (0..n).filter(is_kept).map(|i| self.adps[i]).collect();
+        }
+        if !self.b_factors.is_empty() {
+            self.b_factors = (0..n).filter(is_kept).map(|i| self.b_factors[i]).collect();
+        }
+        if !self.ca_chains.is_empty() {
+            self.ca_chains = (0..n).filter(is_kept).map(|i| self.ca_chains[i]).collect();
+        }
+        if !self.selected.is_empty() {
+            self.selected = (0..n).filter(is_kept).map(|i| self.selected[i]).collect();
+        }
+        if !self.secondary_structure.is_empty() {
+            self.secondary_structure = (0..n).filter(is_kept).map(|i| self.secondary_structure[i]).collect();
+        }
+        if !self.importance.is_empty() {
+            self.importance = (0..n).filter(is_kept).map(|i| self.importance[i]).collect();
+        }
+        self.bonds = bonds;
+        self.total_atom_count = self.all_atoms.len();
+        self.indices_changed();
+        self.positions_changed();
+
+        log!(self, LOG_INFO, "Removed {} duplicate atoms, {} remain", removed, self.all_atoms.len());
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    #[test]
+    fn duplicates_merge_into_the_lowest_index_and_keep_bonds() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz(concat!(
+            "5\n\n",
+            "C 0.0 0.0 0.0\n",
+            "O 1.4 0.0 0.0\n",
+            "C 0.01 0.0 0.0\n",
+            "H 2.4 0.0 0.0\n",
+            "O 1.4 0.005 0.0\n",
+        )).unwrap();
+        // 2 and 4 are copies of 0 and 1, each bonded like its original
+        system.detect_bonds(1.2);
+
+        assert_eq!(system.deduplicate(0.05), 2);
+        assert_eq!(system.get_all_atom_positions(), vec![0.0, 0.0, 0.0, 4.0, 1.4, 0.0, 0.0, 2.0, 2.4, 0.0, 0.0, 0.0]);
+        assert_eq!(system.bond_pairs(), [(0, 1), (1, 2)]);
+        assert_eq!(system.deduplicate(0.05), 0);
+    }
+}
@@ -0,0 +1,31 @@
+// Per-atom view depth for compositing with other render pipelines
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{dot, sub};
+use crate::{Camera, MolecularSystem};
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// The cached visible set with each atom's linear depth, 5 floats per atom
+    /// in visible-list order: `[x, y, z, radius, depth]`. `depth` is the
+    /// distance of the atom's center in front of `camera` along its view
+    /// axis (negative behind it), in the same units as the positions, so a
+    /// sphere shader can subtract the surface offset and write a depth that
+    /// agrees with meshes drawn separately with the same camera (surfaces,
+    /// ribbons). Unlike a normalized fog factor it does not depend on the
+    /// near and far planes. With `set_world_transform` it is measured in
+    /// local units, which match world units for rigid transforms.
+    pub fn get_visible_atoms_with_world_depth(&self, camera: &Camera) -> Vec<f32> {
+        let camera = self.local_camera(camera);
+        let eye = (camera.x, camera.y, camera.z);
+        let forward = camera.view_basis().map_or((0.0, 0.0, -1.0), |(forward, _, _)| forward);
+
+        let mut out = Vec::with_capacity(self.cached_visible_atoms.len() * 5);
+        for atom in &self.cached_visible_atoms {
+            let depth = dot(sub((atom.x, atom.y, atom.z), eye), forward);
+            out.extend_from_slice(&[atom.x, atom.y, atom.z, atom.radius, depth]);
+        }
+        out
+    }
+}
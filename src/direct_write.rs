@@ -0,0 +1,73 @@
+// Culling straight into a buffer in wasm memory, for hot loops that upload
+// every frame without building a Vec or typed array
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Camera, MolecularSystem, Projection, PACKED_ATOM_FLOATS};
+
+/// Allocate a zeroed buffer in wasm memory for `atoms` atoms of
+/// `write_visible_atoms_to` output (`6 * atoms` floats) and return its
+/// address. JS views it as `new Float32Array(memory.buffer, ptr, 6 * atoms)`;
+/// recreate the view after any call that may grow memory, since growing
+/// detaches it. Release it with `free_atom_floats` and the same count.
+#[wasm_bindgen]
+pub fn alloc_atom_floats(atoms: usize) -> *mut f32 {
+    let buffer = vec![0.0f32; atoms * PACKED_ATOM_FLOATS].into_boxed_slice();
+    Box::into_raw(buffer) as *mut f32
+}
+
+/// Release a buffer from `alloc_atom_floats`.
+///
+/// # Safety
+///
+/// `ptr` must come from `alloc_atom_floats(atoms)` with the same `atoms`, and
+/// must not be used or freed again afterwards. A null `ptr` is ignored.
+#[wasm_bindgen]
+pub unsafe fn free_atom_floats(ptr: *mut f32, atoms: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    let slice = std::ptr::slice_from_raw_parts_mut(ptr, atoms * PACKED_ATOM_FLOATS);
+    drop(unsafe { Box::from_raw(slice) });
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// `get_visible_atoms` written straight into wasm memory at `ptr`, which
+    /// holds room for `capacity` atoms, so a render loop reusing one buffer
+    /// (`alloc_atom_floats`) makes no output allocation per frame. Shares the
+    /// camera cache with `get_visible_atoms`. Packs 6 floats per atom in
+    /// visible-list order, the same records as `get_render_payload`:
+    /// `[x, y, z, element, radius, lod_level]`, element and LOD as
+    /// whole-number floats.
+    ///
+    /// Takes a `Projection` rather than `get_render_payload`'s separate
+    /// `fov, aspect, near, far`, which keeps the argument list short; create
+    /// it once and update its fields on resize, so the loop allocates
+    /// nothing on either side of the boundary.
+    ///
+    /// Returns the number of visible atoms. When that exceeds `capacity` only
+    /// the first `capacity` are written: grow the buffer to the returned count
+    /// and call again, which is a cache hit for the same camera. A null `ptr`
+    /// writes nothing and just reports the count.
+    ///
+    /// # Safety
+    ///
+    /// Unless null, `ptr` must be 4-byte aligned and valid for writes of
+    /// `6 * capacity` floats (24 bytes per atom) that nothing else reads or
+    /// writes during the call, e.g. a live buffer from `alloc_atom_floats`
+    /// with at least `capacity` atoms.
+    pub unsafe fn write_visible_atoms_to(&mut self, camera: &Camera, proj: &Projection, ptr: *mut f32, capacity: usize) -> usize {
+        self.refresh_visibility(camera, proj);
+        let atoms = &self.cached_visible_atoms;
+        let written = atoms.len().min(capacity);
+        if ptr.is_null() || written == 0 {
+            return atoms.len();
+        }
+        let out = unsafe { std::slice::from_raw_parts_mut(ptr, written * PACKED_ATOM_FLOATS) };
+        for (slot, atom) in out.chunks_exact_mut(PACKED_ATOM_FLOATS).zip(atoms) {
+            slot.copy_from_slice(&atom.packed());
+        }
+        atoms.len()
+    }
+}
@@ -0,0 +1,52 @@
+// Screen-space edge feathering for sphere impostors
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{dot, sub};
+use crate::{Camera, MolecularSystem};
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Width in pixels of the soft edge `get_visible_edge_softness` asks
+    /// sphere shaders to feather, the same on screen for every atom whatever
+    /// its size, distance or LOD. Default 0, hard edges. Negative or
+    /// non-finite widths are ignored.
+    pub fn set_edge_softness(&mut self, px: f32) {
+        if !(px >= 0.0 && px.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid edge softness {} - keeping {}", px, self.edge_softness);
+            return;
+        }
+        self.edge_softness = px;
+    }
+
+    /// Edge feather for each atom of the cached visible set, in visible-list
+    /// order: `set_edge_softness` divided by the atom's projected radius in
+    /// pixels, so a shader fading alpha over the outer `softness` of the
+    /// sphere's normalized radius (`smoothstep(1 - softness, 1, r)`) gets the
+    /// same pixel width on every atom. Capped at 1 for atoms smaller than the
+    /// edge; 0 for atoms at or behind the eye. Uses the vertical `fov` in
+    /// radians and the viewport height in pixels, like
+    /// `get_visible_point_sizes`.
+    pub fn get_visible_edge_softness(&self, camera: &Camera, fov: f32, viewport_h: f32) -> Vec<f32> {
+        let camera = self.local_camera(camera);
+        let eye = (camera.x, camera.y, camera.z);
+        let forward = camera.view_basis().map(|(forward, _, _)| forward);
+        let tan_half = (fov * 0.5).tan();
+
+        self.cached_visible_atoms
+            .iter()
+            .map(|atom| match forward {
+                Some(forward) if self.edge_softness > 0.0 && tan_half > 0.0 => {
+                    // Projected radius is radius / (depth * tan(fov/2)) * viewport_h / 2
+                    let depth = dot(sub((atom.x, atom.y, atom.z), eye), forward);
+                    let pixel_radius = atom.radius * viewport_h * 0.5 / (depth * tan_half);
+                    if depth <= 0.0 || pixel_radius.is_nan() || pixel_radius <= 0.0 {
+                        return 0.0;
+                    }
+                    (self.edge_softness / pixel_radius).min(1.0)
+                }
+                _ => 0.0,
+            })
+            .collect()
+    }
+}
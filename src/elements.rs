@@ -0,0 +1,272 @@
+use wasm_bindgen::prelude::*;
+
+use crate::MolecularSystem;
+
+// Element table
+//
+// Ids 0-3 are the legacy H/F/O/N buckets the renderer keys its colors on.
+// Elements read from real files are appended after them so the legacy ids
+// keep their meaning.
+
+pub(crate) struct ElementInfo {
+    pub(crate) symbol: &'static str,
+    /// Standard atomic weight (g/mol)
+    pub(crate) mass: f32,
+    /// Single-bond covalent radius (Å)
+    pub(crate) covalent_radius: f32,
+    /// Default display radius
+    pub(crate) radius: f32,
+    /// Default CPK-style color
+    pub(crate) color: [f32; 3],
+}
+
+const fn element(symbol: &'static str, mass: f32, covalent_radius: f32, radius: f32, color: [f32; 3]) -> ElementInfo {
+    ElementInfo { symbol, mass, covalent_radius, radius, color }
+}
+
+/// Element table indexed by element id
+pub(crate) const ELEMENTS: [ElementInfo; 20] = [
+    // legacy ids 0-3
+    element("H", 1.008, 0.31, 0.25, [1.0, 1.0, 1.0]),
+    element("F", 18.998, 0.57, 0.35, [0.2, 0.8, 0.3]),
+    element("O", 15.999, 0.66, 0.3, [1.0, 0.2, 0.2]),
+    element("N", 14.007, 0.71, 0.28, [0.2, 0.2, 1.0]),
+    element("C", 12.011, 0.76, 0.3, [0.5, 0.5, 0.5]),
+    element("S", 32.06, 1.05, 0.38, [1.0, 0.8, 0.2]),
+    element("P", 30.974, 1.07, 0.36, [1.0, 0.5, 0.0]),
+    element("Cl", 35.45, 1.02, 0.36, [0.12, 0.94, 0.12]),
+    element("Br", 79.904, 1.20, 0.4, [0.65, 0.16, 0.16]),
+    element("I", 126.904, 1.39, 0.44, [0.58, 0.0, 0.58]),
+    element("Na", 22.990, 1.66, 0.45, [0.67, 0.36, 0.95]),
+    element("K", 39.098, 2.03, 0.5, [0.56, 0.25, 0.83]),
+    element("Mg", 24.305, 1.41, 0.4, [0.54, 1.0, 0.0]),
+    element("Ca", 40.078, 1.76, 0.45, [0.24, 1.0, 0.0]),
+    element("Fe", 55.845, 1.32, 0.36, [0.88, 0.4, 0.2]),
+    element("Zn", 65.38, 1.22, 0.36, [0.49, 0.5, 0.69]),
+    element("Cu", 63.546, 1.32, 0.36, [0.78, 0.5, 0.2]),
+    element("Mn", 54.938, 1.39, 0.36, [0.61, 0.48, 0.78]),
+    element("Se", 78.971, 1.20, 0.38, [1.0, 0.63, 0.0]),
+    element("Si", 28.085, 1.11, 0.38, [0.94, 0.78, 0.63]),
+];
+
+/// Covalent radius (Å) for ids outside the table
+pub(crate) const FALLBACK_COVALENT_RADIUS: f32 = 0.75;
+
+/// Display radius for ids outside the table
+pub(crate) const FALLBACK_RADIUS: f32 = 0.28;
+
+/// Color for ids outside the table
+pub(crate) const FALLBACK_COLOR: [f32; 3] = [1.0, 0.08, 0.58];
+
+/// Element id used when a record names an element we don't know
+pub(crate) const DEFAULT_ELEMENT: u32 = 4; // C
+
+pub(crate) fn element_info(element: u32) -> Option<&'static ElementInfo> {
+    ELEMENTS.get(element as usize)
+}
+
+/// Look up an element id by symbol (case-insensitive)
+pub(crate) fn element_from_symbol(symbol: &str) -> Option<u32> {
+    let symbol = symbol.trim();
+    ELEMENTS
+        .iter()
+        .position(|e| e.symbol.eq_ignore_ascii_case(symbol))
+        .map(|id| id as u32)
+}
+
+/// Element id for a symbol such as `"C"` or `"cl"`, `None` if the symbol is unknown
+#[wasm_bindgen]
+pub fn element_id_from_symbol(symbol: &str) -> Option<u32> {
+    element_from_symbol(symbol)
+}
+
+fn symbol_arg(symbol: &str) -> Result<u32, JsValue> {
+    element_from_symbol(symbol).ok_or_else(|| JsValue::from_str(&format!("Unknown element symbol '{}'", symbol)))
+}
+
+/// Atomic mass (g/mol) for an element id, or 0 for unknown ids
+#[wasm_bindgen]
+pub fn atomic_mass(element: u32) -> f32 {
+    element_info(element).map_or(0.0, |e| e.mass)
+}
+
+/// Covalent radius (Å) for an element id, with a carbon-like fallback for unknown ids
+#[wasm_bindgen]
+pub fn covalent_radius(element: u32) -> f32 {
+    element_info(element).map_or(FALLBACK_COVALENT_RADIUS, |e| e.covalent_radius)
+}
+
+impl MolecularSystem {
+    /// Id an element is drawn as: its `set_element_remap` target, else itself
+    pub(crate) fn display_element(&self, element: u32) -> u32 {
+        if self.element_remap.is_empty() {
+            return element;
+        }
+        self.element_remap.get(&element).copied().unwrap_or(element)
+    }
+
+    /// Display radius for an element: runtime override, else table default
+    pub(crate) fn element_radius(&self, element: u32) -> f32 {
+        if let Some(&radius) = self.element_radius_overrides.get(&element) {
+            return radius;
+        }
+        element_info(element).map_or(FALLBACK_RADIUS, |e| e.radius)
+    }
+
+    /// Upper bound on any atom's display radius, overrides included
+    pub(crate) fn max_element_radius(&self) -> f32 {
+        ELEMENTS.iter()
+            .map(|e| e.radius)
+            .chain(self.element_radius_overrides.values().copied())
+            .fold(FALLBACK_RADIUS, f32::max)
+    }
+
+    /// `set_element_lod_bias` for a stored element, after any remap
+    pub(crate) fn element_lod_bias(&self, element: u32) -> f32 {
+        self.element_lod_bias.get(&self.display_element(element)).copied().unwrap_or(1.0)
+    }
+
+    /// Display color for an element: runtime override, else table default
+    pub(crate) fn element_color(&self, element: u32) -> [f32; 3] {
+        if let Some(&color) = self.element_color_overrides.get(&element) {
+            return color;
+        }
+        element_info(element).map_or(FALLBACK_COLOR, |e| e.color)
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Override the display radius of one element. Non-positive or
+    /// non-finite radii are ignored.
+    pub fn set_element_radius(&mut self, element: u32, radius: f32) {
+        if !(radius > 0.0 && radius.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid radius {} for element {} - keeping {}", radius, element, self.element_radius(element));
+            return;
+        }
+        self.element_radius_overrides.insert(element, radius);
+        // Radii are baked into the visible atom list
+        self.invalidate_camera_cache();
+    }
+
+    /// Override the display color of one element. Channels are clamped to
+    /// [0, 1]; a color with a non-finite channel is ignored.
+    pub fn set_element_color(&mut self, element: u32, r: f32, g: f32, b: f32) {
+        if ![r, g, b].iter().all(|c| c.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid color ({}, {}, {}) for element {} - keeping {:?}", r, g, b, element, self.element_color(element));
+            return;
+        }
+        // Colors are a separate output, so the visible set stays valid
+        self.element_color_overrides.insert(element, [r, g, b].map(|c| c.clamp(0.0, 1.0)));
+    }
+
+    /// `set_element_radius` by symbol; errors on an unknown symbol
+    pub fn set_element_radius_by_symbol(&mut self, symbol: &str, radius: f32) -> Result<(), JsValue> {
+        self.set_element_radius(symbol_arg(symbol)?, radius);
+        Ok(())
+    }
+
+    /// `set_element_color` by symbol; errors on an unknown symbol
+    pub fn set_element_color_by_symbol(&mut self, symbol: &str, r: f32, g: f32, b: f32) -> Result<(), JsValue> {
+        self.set_element_color(symbol_arg(symbol)?, r, g, b);
+        Ok(())
+    }
+
+    /// Drop all radius and color overrides, returning to the built-in table
+    pub fn reset_element_styles(&mut self) {
+        self.element_radius_overrides.clear();
+        self.element_color_overrides.clear();
+        self.invalidate_camera_cache();
+    }
+
+    /// Draw element `from` as element `to`, e.g. to fold every element a
+    /// loader produces into the legacy H/F/O/N ids 0-3 that 4-color shaders
+    /// key on. Applied before culling and style lookup: visible atoms report
+    /// `to` as their element, take `to`'s radius and color, and are hidden
+    /// with `set_element_visible(to, false)`. One step only, so remapping
+    /// 5 → 3 and 3 → 0 draws element 5 as 3. Stored atoms, bonds, analysis and
+    /// export keep the real element. `from == to` removes the remap.
+    pub fn set_element_remap(&mut self, from: u32, to: u32) {
+        if from == to {
+            self.element_remap.remove(&from);
+        } else {
+            self.element_remap.insert(from, to);
+        }
+        self.invalidate_camera_cache();
+    }
+
+    /// Drop every `set_element_remap` entry
+    pub fn clear_element_remaps(&mut self) {
+        self.element_remap.clear();
+        self.invalidate_camera_cache();
+    }
+
+    /// Scale the LOD distance thresholds for one element's atoms by `bias`:
+    /// below 1 they drop to lower LODs and points sooner (e.g. 0.5 for
+    /// hydrogens), above 1 they keep detail further out. Applies to the
+    /// element atoms are drawn as (`set_element_remap`) and, like importance,
+    /// also ranks them for `set_visible_atom_cap` and `set_max_high_lod_atoms`;
+    /// culling is unchanged. Default 1, the plain thresholds. Non-positive or
+    /// non-finite biases are ignored.
+    pub fn set_element_lod_bias(&mut self, element: u32, bias: f32) {
+        if !(bias > 0.0 && bias.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid LOD bias {} for element {} - keeping {}", bias, element, self.element_lod_bias(element));
+            return;
+        }
+        if bias == 1.0 {
+            self.element_lod_bias.remove(&element);
+        } else {
+            self.element_lod_bias.insert(element, bias);
+        }
+        self.invalidate_camera_cache();
+    }
+
+    /// Current `[r, g, b]` for an element
+    pub fn get_element_color(&self, element: u32) -> Vec<f32> {
+        self.element_color(element).to_vec()
+    }
+
+    /// Flat `[r, g, b]` per atom in the cached visible set (same order as the visible list)
+    pub fn get_visible_atom_colors(&self) -> Vec<f32> {
+        let mut colors = Vec::with_capacity(self.cached_visible_atoms.len() * 3);
+        for atom in &self.cached_visible_atoms {
+            colors.extend_from_slice(&self.element_color(atom.element));
+        }
+        colors
+    }
+
+    /// Sum of atomic masses over all atoms (g/mol). Unknown elements count as 0.
+    pub fn molecular_weight(&self) -> f32 {
+        let mut unknown = 0usize;
+        let mut weight = 0.0f64;
+
+        for atom in &self.all_atoms {
+            match element_info(atom.element) {
+                Some(e) => weight += e.mass as f64,
+                None => unknown += 1,
+            }
+        }
+
+        if unknown > 0 {
+            log!(self, LOG_ERRORS, "Warning: {} atoms have unknown elements and were left out of the molecular weight", unknown);
+        }
+
+        weight as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    #[test]
+    fn element_colors_are_clamped_and_non_finite_ones_ignored() {
+        let mut system = MolecularSystem::new();
+        system.set_element_color(4, 1.5, -0.25, 0.5);
+        assert_eq!(system.get_element_color(4), [1.0, 0.0, 0.5]);
+
+        system.set_element_color(4, f32::NAN, 0.0, 0.0);
+        system.set_element_color(4, 0.0, f32::INFINITY, 0.0);
+        assert_eq!(system.get_element_color(4), [1.0, 0.0, 0.5]);
+    }
+}
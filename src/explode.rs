@@ -0,0 +1,93 @@
+// Exploded view: bonded groups pushed apart at cull time, leaving the stored
+// coordinates alone
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{add, scale, Vec3};
+use crate::MolecularSystem;
+
+impl MolecularSystem {
+    // Each atom's offset at factor 1: the centroid of its bonded group minus
+    // the centroid of all atoms
+    fn explode_directions(&self) -> &[Vec3] {
+        self.explode_directions.get_or_init(|| {
+            let n = self.all_atoms.len();
+            let adjacency = self.adjacency();
+            let centroid = |atoms: &mut dyn Iterator<Item = usize>| {
+                let (mut sum, mut count) = ([0.0f64; 3], 0usize);
+                for i in atoms {
+                    let atom = &self.all_atoms[i];
+                    sum[0] += atom.x as f64;
+                    sum[1] += atom.y as f64;
+                    sum[2] += atom.z as f64;
+                    count += 1;
+                }
+                sum.map(|s| s / count.max(1) as f64)
+            };
+            let center = centroid(&mut (0..n));
+
+            let mut directions = vec![(0.0, 0.0, 0.0); n];
+            let mut visited = vec![false; n];
+            let mut group = Vec::new();
+            for root in 0..n {
+                if visited[root] {
+                    continue;
+                }
+                visited[root] = true;
+                group.clear();
+                group.push(root);
+                let mut next = 0;
+                while let Some(&u) = group.get(next) {
+                    next += 1;
+                    for &v in adjacency.of(u) {
+                        if !visited[v as usize] {
+                            visited[v as usize] = true;
+                            group.push(v as usize);
+                        }
+                    }
+                }
+                let c = centroid(&mut group.iter().copied());
+                let direction = ((c[0] - center[0]) as f32, (c[1] - center[1]) as f32, (c[2] - center[2]) as f32);
+                for &i in &group {
+                    directions[i] = direction;
+                }
+            }
+            directions
+        })
+    }
+
+    // How far the exploded view moves atom `index`
+    pub(crate) fn explode_offset(&self, index: usize) -> Vec3 {
+        if self.explode_factor == 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+        scale(self.explode_directions()[index], self.explode_factor)
+    }
+
+    // Where the cull sees atom `index`
+    pub(crate) fn exploded_position(&self, index: usize) -> Vec3 {
+        let atom = &self.all_atoms[index];
+        add((atom.x, atom.y, atom.z), self.explode_offset(index))
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Exploded view: each bonded group (molecule) moves away from the
+    /// centroid of all atoms by `factor` times its own centroid's distance
+    /// from it, so overlapping components separate while each keeps its
+    /// shape. Atoms without bonds move on their own, so run `detect_bonds`
+    /// first. Applied when culling, so visible atoms, bonds and impostors
+    /// are displaced while stored coordinates, picking and analysis are not;
+    /// the groups are found once per bond set, so animating `factor` is
+    /// cheap. Chunked culling is bypassed while exploded. `0` (the default)
+    /// is the normal view. Negative or non-finite factors are ignored.
+    pub fn set_explode(&mut self, factor: f32) {
+        if !(factor >= 0.0 && factor.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid explode factor {} - keeping {}", factor, self.explode_factor);
+            return;
+        }
+        self.explode_factor = factor;
+        self.invalidate_camera_cache();
+    }
+}
@@ -0,0 +1,187 @@
+// Recently computed visible sets, so returning to a recent camera - e.g.
+// toggling between saved viewpoints - skips the cull
+
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+use crate::memory::{VISIBLE_ATOM_BYTES, VISIBLE_BOND_BYTES};
+use crate::{AtomData, BondData, Camera, CullStats, MolecularSystem, Projection, LOD_HIDDEN};
+
+struct FrameSnapshot {
+    atoms: Vec<AtomData>,
+    indices: Vec<u32>,
+    bonds: Vec<BondData>,
+    bond_pairs: Vec<(u32, u32)>,
+    impostors: Vec<f32>,
+    stats: CullStats,
+}
+
+impl FrameSnapshot {
+    // Counted the same way as the live visible set
+    fn bytes(&self) -> usize {
+        self.atoms.len() * VISIBLE_ATOM_BYTES
+            + self.bonds.len() * VISIBLE_BOND_BYTES
+            + self.impostors.len() * std::mem::size_of::<f32>()
+    }
+}
+
+/// Least-recently-used first
+#[derive(Default)]
+pub(crate) struct FrameCache {
+    capacity: usize,
+    // Keyed by camera-cache hash; culling does not depend on animation time
+    frames: VecDeque<(u64, FrameSnapshot)>,
+    // Visible sets along the last `precompute_path`, in path order; kept
+    // until the next one rather than cleared with `frames`
+    path: Vec<FrameSnapshot>,
+}
+
+impl FrameCache {
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    fn evict(&mut self) {
+        while self.frames.len() > self.capacity {
+            self.frames.pop_front();
+        }
+    }
+}
+
+impl MolecularSystem {
+    /// Adopt a remembered visible set for this camera, if there is one
+    pub(crate) fn restore_cached_frame(&mut self, camera_hash: u64, camera: &Camera, proj: &Projection) -> bool {
+        let Some(position) = self.frame_cache.frames.iter().position(|(k, _)| *k == camera_hash) else {
+            return false;
+        };
+        // Move to the most-recently-used end
+        let entry = self.frame_cache.frames.remove(position).unwrap();
+        let snapshot = &entry.1;
+
+        self.last_cull_params = Some(*proj);
+        self.last_cull_eye = Some((camera.x, camera.y, camera.z));
+        self.visible_batches = None;
+        self.cull_stats = snapshot.stats;
+        self.cached_visible_atoms = snapshot.atoms.clone();
+        self.cached_visible_indices = snapshot.indices.clone();
+        self.cached_visible_bonds = snapshot.bonds.clone();
+        self.cached_visible_bond_pairs = snapshot.bond_pairs.clone();
+        self.cached_impostors = snapshot.impostors.clone();
+        // Remembered at another animation time
+        self.refresh_animated_radii();
+
+        let mut atom_lods = vec![LOD_HIDDEN; self.all_atoms.len()];
+        for (atom, &index) in self.cached_visible_atoms.iter().zip(&self.cached_visible_indices) {
+            atom_lods[index as usize] = atom.lod_level as u8;
+        }
+        self.adopt_atom_lods(atom_lods);
+
+        self.frame_cache.frames.push_back(entry);
+        true
+    }
+
+    fn snapshot(&self) -> FrameSnapshot {
+        FrameSnapshot {
+            atoms: self.cached_visible_atoms.clone(),
+            indices: self.cached_visible_indices.clone(),
+            bonds: self.cached_visible_bonds.clone(),
+            bond_pairs: self.cached_visible_bond_pairs.clone(),
+            impostors: self.cached_impostors.clone(),
+            stats: self.cull_stats,
+        }
+    }
+
+    /// Remember the visible set just computed for this camera
+    pub(crate) fn remember_frame(&mut self, camera_hash: u64) {
+        if self.frame_cache.capacity == 0 {
+            return;
+        }
+        let snapshot = self.snapshot();
+        self.frame_cache.frames.retain(|(k, _)| *k != camera_hash);
+        self.frame_cache.frames.push_back((camera_hash, snapshot));
+        self.frame_cache.evict();
+
+        // Remembered frames only get what the live visible set leaves of the budget
+        if let Some(budget) = self.cache_memory_budget {
+            let limit = budget.saturating_sub(self.visible_set_bytes());
+            while self.frame_cache_bytes() > limit {
+                self.frame_cache.frames.pop_front();
+            }
+        }
+    }
+
+    pub(crate) fn frame_cache_bytes(&self) -> usize {
+        self.frame_cache.frames.iter().map(|(_, snapshot)| snapshot.bytes()).sum()
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Keep the visible sets of the last `n` distinct cameras, so returning to
+    /// a recent view - toggling between saved viewpoints, an orbit passing
+    /// the same positions - reuses its result instead of culling. Animation
+    /// time is not part of the key: culling does not depend on it, and a
+    /// restored set gets its radii for the current time, as `update` gives
+    /// the live one. When full the least recently used camera is evicted.
+    /// Each entry holds a copy of that camera's visible atoms and bonds, so
+    /// memory grows with `n` times the visible set size, unless
+    /// `set_cache_memory_budget` evicts entries sooner. `0` (the default)
+    /// disables it. Changing atoms or culling settings empties the cache.
+    pub fn set_frame_cache_size(&mut self, n: usize) {
+        self.frame_cache.capacity = n;
+        self.frame_cache.evict();
+    }
+
+    /// Cull every camera of a fly-through path up front, for offline export
+    /// that should not interleave culling with rendering: each frame's
+    /// visible atoms and bonds are stored in path order for
+    /// `get_precomputed_frame` and `get_precomputed_frame_bonds`. Cameras
+    /// are culled in sequence, as if played back, so LOD hysteresis behaves
+    /// as it would live, and the cached visible set ends at the last camera.
+    ///
+    /// At most `max_frames` cameras are culled; the rest of the path is
+    /// dropped and logged. Stored frames replace the previous
+    /// path and are a snapshot: later changes to atoms or settings do not
+    /// update them. They are not counted against `set_cache_memory_budget`.
+    /// An empty path frees them. Returns the number of frames stored.
+    pub fn precompute_path(
+        &mut self,
+        cameras: Vec<Camera>,
+        fov: f32,
+        aspect: f32,
+        near: f32,
+        far: f32,
+        max_frames: usize,
+    ) -> usize {
+        self.frame_cache.path.clear();
+        if cameras.len() > max_frames {
+            log!(self, LOG_ERRORS, "Camera path has {} frames - precomputing only the first {}", cameras.len(), max_frames);
+        }
+        let proj = Projection::new(fov, aspect, near, far);
+        let mut path = Vec::with_capacity(cameras.len().min(max_frames));
+        for camera in cameras.iter().take(max_frames) {
+            self.refresh_visibility(camera, &proj);
+            path.push(self.snapshot());
+        }
+        self.frame_cache.path = path;
+        self.frame_cache.path.len()
+    }
+
+    /// Visible atoms of frame `i` of the last `precompute_path`, as
+    /// `get_visible_atoms` returned them; empty past the end of the path
+    pub fn get_precomputed_frame(&self, i: usize) -> Vec<AtomData> {
+        self.frame_cache.path.get(i).map_or_else(Vec::new, |frame| frame.atoms.clone())
+    }
+
+    /// Visible bonds of frame `i` of the last `precompute_path`; empty past
+    /// the end of the path
+    pub fn get_precomputed_frame_bonds(&self, i: usize) -> Vec<BondData> {
+        self.frame_cache.path.get(i).map_or_else(Vec::new, |frame| frame.bonds.clone())
+    }
+
+    /// Visible atom count of each frame of the last `precompute_path`, in
+    /// path order
+    pub fn get_precomputed_atom_counts(&self) -> Vec<u32> {
+        self.frame_cache.path.iter().map(|frame| frame.atoms.len() as u32).collect()
+    }
+}
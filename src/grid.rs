@@ -0,0 +1,159 @@
+// Uniform spatial grid over atom positions for neighbor queries
+//
+// Atoms are bucketed by cell and stored cell-contiguously (counting sort), so
+// a query only touches the cells overlapping its search sphere.
+
+use crate::math::Vec3;
+use crate::RawAtom;
+
+/// Grids never allocate more than this many cells per atom; sparse scenes
+/// get coarser cells instead
+const MAX_CELLS_PER_ATOM: usize = 2;
+
+pub(crate) struct SpatialGrid {
+    cell_size: f32,
+    origin: Vec3,
+    dims: [usize; 3],
+    // `entries[cell_starts[c]..cell_starts[c + 1]]` are the atoms in cell `c`
+    cell_starts: Vec<u32>,
+    entries: Vec<u32>,
+}
+
+impl SpatialGrid {
+    pub(crate) fn build(atoms: &[RawAtom], cell_size: f32) -> Self {
+        let mut min = (f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for atom in atoms {
+            min = (min.0.min(atom.x), min.1.min(atom.y), min.2.min(atom.z));
+            max = (max.0.max(atom.x), max.1.max(atom.y), max.2.max(atom.z));
+        }
+        if atoms.is_empty() {
+            min = (0.0, 0.0, 0.0);
+            max = (0.0, 0.0, 0.0);
+        }
+
+        let extent = (max.0 - min.0, max.1 - min.1, max.2 - min.2);
+        let max_cells = (atoms.len() * MAX_CELLS_PER_ATOM).max(1);
+        let mut cell_size = cell_size.max(1e-3);
+        let dims = loop {
+            let dims = [
+                (extent.0 / cell_size).floor() as usize + 1,
+                (extent.1 / cell_size).floor() as usize + 1,
+                (extent.2 / cell_size).floor() as usize + 1,
+            ];
+            if dims[0].saturating_mul(dims[1]).saturating_mul(dims[2]) <= max_cells {
+                break dims;
+            }
+            cell_size *= 2.0;
+        };
+
+        let mut grid = SpatialGrid {
+            cell_size,
+            origin: min,
+            dims,
+            cell_starts: vec![0; dims[0] * dims[1] * dims[2] + 1],
+            entries: vec![0; atoms.len()],
+        };
+
+        let cells: Vec<usize> = atoms.iter().map(|a| grid.cell_index(grid.cell_of((a.x, a.y, a.z)))).collect();
+        for &cell in &cells {
+            grid.cell_starts[cell + 1] += 1;
+        }
+        for c in 1..grid.cell_starts.len() {
+            grid.cell_starts[c] += grid.cell_starts[c - 1];
+        }
+        let mut cursor = grid.cell_starts.clone();
+        for (index, &cell) in cells.iter().enumerate() {
+            grid.entries[cursor[cell] as usize] = index as u32;
+            cursor[cell] += 1;
+        }
+
+        grid
+    }
+
+    /// Cell coordinates of a point, clamped to the grid
+    pub(crate) fn cell_of(&self, p: Vec3) -> [usize; 3] {
+        let axis = |v: f32, o: f32, n: usize| (((v - o) / self.cell_size).floor().max(0.0) as usize).min(n - 1);
+        [
+            axis(p.0, self.origin.0, self.dims[0]),
+            axis(p.1, self.origin.1, self.dims[1]),
+            axis(p.2, self.origin.2, self.dims[2]),
+        ]
+    }
+
+    /// Index of the cell holding `p` (clamped to the grid)
+    pub(crate) fn cell_id(&self, p: Vec3) -> usize {
+        self.cell_index(self.cell_of(p))
+    }
+
+    fn cell_index(&self, cell: [usize; 3]) -> usize {
+        cell[0] + self.dims[0] * (cell[1] + self.dims[1] * cell[2])
+    }
+
+    /// Every atom index, grouped by cell with cells in x-fastest, z-slowest
+    /// order, so contiguous runs are spatially coherent
+    #[cfg(feature = "parallel")]
+    pub(crate) fn atoms_in_cell_order(&self) -> &[u32] {
+        &self.entries
+    }
+
+    pub(crate) fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Call `f(min_corner, atom_indices)` for each non-empty cell. Cells are cubes
+    /// of side `cell_size()`.
+    pub(crate) fn for_each_cell(&self, mut f: impl FnMut(Vec3, &[u32])) {
+        for cz in 0..self.dims[2] {
+            for cy in 0..self.dims[1] {
+                for cx in 0..self.dims[0] {
+                    let cell = self.cell_index([cx, cy, cz]);
+                    let range = self.cell_starts[cell] as usize..self.cell_starts[cell + 1] as usize;
+                    if range.is_empty() {
+                        continue;
+                    }
+                    let corner = (
+                        self.origin.0 + cx as f32 * self.cell_size,
+                        self.origin.1 + cy as f32 * self.cell_size,
+                        self.origin.2 + cz as f32 * self.cell_size,
+                    );
+                    f(corner, &self.entries[range]);
+                }
+            }
+        }
+    }
+
+    /// Whether a sphere around `p` reaches into the grid's bounds at all
+    pub(crate) fn overlaps(&self, p: Vec3, radius: f32) -> bool {
+        let extent = |n: usize| n as f32 * self.cell_size;
+        p.0 + radius >= self.origin.0 && p.0 - radius <= self.origin.0 + extent(self.dims[0])
+            && p.1 + radius >= self.origin.1 && p.1 - radius <= self.origin.1 + extent(self.dims[1])
+            && p.2 + radius >= self.origin.2 && p.2 - radius <= self.origin.2 + extent(self.dims[2])
+    }
+
+    /// Call `f(index, distance_squared)` for every atom within `radius` of `p`
+    pub(crate) fn for_each_within(&self, atoms: &[RawAtom], p: Vec3, radius: f32, mut f: impl FnMut(usize, f32)) {
+        if atoms.is_empty() {
+            return;
+        }
+        let lo = self.cell_of((p.0 - radius, p.1 - radius, p.2 - radius));
+        let hi = self.cell_of((p.0 + radius, p.1 + radius, p.2 + radius));
+        let radius_sq = radius * radius;
+
+        for cz in lo[2]..=hi[2] {
+            for cy in lo[1]..=hi[1] {
+                for cx in lo[0]..=hi[0] {
+                    let cell = self.cell_index([cx, cy, cz]);
+                    let range = self.cell_starts[cell] as usize..self.cell_starts[cell + 1] as usize;
+                    for &index in &self.entries[range] {
+                        let atom = &atoms[index as usize];
+                        let d = (atom.x - p.0).powi(2) + (atom.y - p.1).powi(2) + (atom.z - p.2).powi(2);
+                        if d <= radius_sq {
+                            f(index as usize, d);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use wasm_bindgen::prelude::*;
+
+use crate::crystal::UnitCell;
+use crate::math::Vec3;
+use crate::pdb::ParsedAtoms;
+use crate::perf::{elapsed_ms, now_ms};
+use crate::precision::InputFrame;
+use crate::warnings::LoadWarnings;
+use crate::{MolecularSystem, RawAtom};
+
+/// Å per nm; GRO positions are nm and velocities nm/ps
+const NM: f64 = 10.0;
+
+// Fixed-width GRO column, 0-based start and width
+fn field(line: &str, start: usize, width: usize) -> &str {
+    line.get(start..(start + width).min(line.len())).unwrap_or("").trim()
+}
+
+// GRO has no element column. Names start with the element letter (CA is a C
+// alpha, OW a water oxygen); single-atom residues named after their atom are
+// ions such as NA or CL, so those use the whole name.
+fn guess_symbol<'a>(residue: &str, name: &'a str) -> &'a str {
+    let name = name.trim_start_matches(|c: char| c.is_ascii_digit());
+    if name.eq_ignore_ascii_case(residue) { name } else { name.get(..1).unwrap_or("") }
+}
+
+/// Line-at-a-time GROMACS GRO reader: title, atom count, fixed-width atom
+/// records, box line. Only the first frame of a multi-frame file is read.
+pub(crate) struct GroParser {
+    // Applied to every coordinate as it is read, after the nm to Å scale
+    frame: InputFrame,
+    atoms: Vec<RawAtom>,
+    // Å/ps, parallel to `atoms` when the records carry velocity columns
+    velocities: Vec<Vec3>,
+    expected: Option<usize>,
+    unit_cell: Option<UnitCell>,
+    lines_seen: usize,
+    // Coordinate field width, taken from the first record's decimal points
+    // (8 for the standard %8.3f)
+    width: usize,
+    warnings: LoadWarnings,
+}
+
+impl GroParser {
+    pub(crate) fn new(frame: InputFrame) -> Self {
+        GroParser {
+            frame,
+            atoms: Vec::new(),
+            velocities: Vec::new(),
+            expected: None,
+            unit_cell: None,
+            lines_seen: 0,
+            width: 8,
+            warnings: LoadWarnings::default(),
+        }
+    }
+
+    pub(crate) fn parse_line(&mut self, line: &str, line_no: usize) -> Result<(), String> {
+        self.lines_seen += 1;
+
+        // Line 1 is a free-form title
+        if self.lines_seen == 1 {
+            return Ok(());
+        }
+        let expected = match self.expected {
+            Some(expected) => expected,
+            None => {
+                let count = line.trim().parse::<usize>()
+                    .map_err(|_| format!("GRO line {}: expected atom count", line_no))?;
+                self.expected = Some(count);
+                self.atoms.reserve(count);
+                return Ok(());
+            }
+        };
+        if self.atoms.len() >= expected {
+            // Anything past the first frame's box line is ignored
+            if self.lines_seen == expected + 3 {
+                self.parse_box(line, line_no)?;
+            }
+            return Ok(());
+        }
+
+        if self.atoms.is_empty() {
+            let points: Vec<usize> = line.get(20..).unwrap_or("").match_indices('.').map(|(i, _)| i).take(2).collect();
+            if let [first, second] = points[..] {
+                self.width = second - first;
+            }
+        }
+
+        let coordinate = |k: usize| -> Result<f64, String> {
+            field(line, 20 + k * self.width, self.width)
+                .parse::<f64>()
+                .map_err(|_| format!("GRO line {}: invalid coordinate", line_no))
+        };
+        let (x, y, z) = (coordinate(0)?, coordinate(1)?, coordinate(2)?);
+        let (x, y, z) = self.frame.point(x * NM, y * NM, z * NM);
+
+        // Velocities follow in fields of the same width; the first record
+        // decides whether the file has them
+        let has_velocities = !field(line, 20 + 3 * self.width, self.width).is_empty();
+        if self.atoms.is_empty() || !self.velocities.is_empty() {
+            if has_velocities {
+                let velocity = |k: usize| -> Result<f32, String> {
+                    field(line, 20 + (3 + k) * self.width, self.width)
+                        .parse::<f64>()
+                        .map(|v| (v * NM) as f32 * self.frame.scale())
+                        .map_err(|_| format!("GRO line {}: invalid velocity", line_no))
+                };
+                self.velocities.push((velocity(0)?, velocity(1)?, velocity(2)?));
+            } else if !self.atoms.is_empty() {
+                return Err(format!("GRO line {}: missing velocity", line_no));
+            }
+        }
+
+        let element = self.warnings.element(guess_symbol(field(line, 5, 5), field(line, 10, 5)));
+        self.atoms.push(RawAtom { x, y, z, element, occupancy: 1.0 });
+        Ok(())
+    }
+
+    // Box line: `v1x v2y v3z`, optionally followed by `v1y v1z v2x v2z v3x v3y`
+    // for triclinic boxes. An all-zero box (no periodicity) sets no cell.
+    fn parse_box(&mut self, line: &str, line_no: usize) -> Result<(), String> {
+        let v: Vec<f64> = line.split_whitespace()
+            .map(|field| field.parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("GRO line {}: invalid box vectors", line_no))?;
+        if v.is_empty() {
+            return Ok(());
+        }
+        let [xx, yy, zz, xy, xz, yx, yz, zx, zy] = match v[..] {
+            [xx, yy, zz] => [xx, yy, zz, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [xx, yy, zz, xy, xz, yx, yz, zx, zy] => [xx, yy, zz, xy, xz, yx, yz, zx, zy],
+            _ => return Err(format!("GRO line {}: box line must have 3 or 9 values", line_no)),
+        };
+        let scale = NM * self.frame.scale() as f64;
+        let vector = |x: f64, y: f64, z: f64| ((x * scale) as f32, (y * scale) as f32, (z * scale) as f32);
+        let cell = UnitCell {
+            a: vector(xx, xy, xz),
+            b: vector(yx, yy, yz),
+            c: vector(zx, zy, zz),
+            origin: (0.0, 0.0, 0.0),
+        };
+        if cell.inverse().is_some() {
+            self.unit_cell = Some(cell);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<ParsedAtoms, String> {
+        let expected = self.expected.unwrap_or(0);
+        if self.atoms.len() != expected {
+            return Err(format!("GRO declares {} atoms but {} were read", expected, self.atoms.len()));
+        }
+        Ok(ParsedAtoms {
+            warnings: self.warnings.into_messages(),
+            velocities: self.velocities,
+            unit_cell: self.unit_cell,
+            precise: self.frame.into_precise(),
+            ..self.atoms.into()
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Load atoms from GROMACS GRO text, replacing the current data and
+    /// returning the atom count. Coordinates are converted from nm to Å, then
+    /// scaled by `set_input_scale` like every other format. Elements are
+    /// guessed from atom names. Velocity columns, when present, become the
+    /// per-atom velocities (Å/ps, see `get_visible_atom_speeds`), and a
+    /// non-zero box line becomes the unit cell (otherwise none is kept, as
+    /// with every loader). A declared atom count that does not match the
+    /// records is an error.
+    pub fn load_gro(&mut self, text: &str) -> Result<usize, JsValue> {
+        log!(self, LOG_INFO, "Parsing GRO ({} bytes)...", text.len());
+        let start = now_ms();
+
+        let mut parser = GroParser::new(self.input_frame());
+        for (line_no, line) in text.lines().enumerate() {
+            parser.parse_line(line, line_no + 1).map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        self.adopt_parsed_atoms(parser.finish().map_err(|e| JsValue::from_str(&e))?);
+
+        self.timings.load_ms = elapsed_ms(start);
+        log!(self, LOG_INFO, "Loaded {} atoms from GRO", self.all_atoms.len());
+        Ok(self.all_atoms.len())
+    }
+}
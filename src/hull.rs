@@ -0,0 +1,235 @@
+// Convex hull of atom centers (quickhull)
+
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+use crate::math::{add, cross, dot, normalize, scale, sub, KahanSum, Vec3};
+use crate::MolecularSystem;
+
+/// Above this many atoms the hull is built from an evenly strided sample
+/// plus the extreme atoms along `SAMPLE_DIRECTIONS`
+const HULL_MAX_POINTS: usize = 50_000;
+
+// Axes, face diagonals and body diagonals (unnormalized; only the order matters)
+const SAMPLE_DIRECTIONS: [Vec3; 13] = [
+    (1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0),
+    (1.0, 1.0, 0.0), (1.0, -1.0, 0.0), (1.0, 0.0, 1.0),
+    (1.0, 0.0, -1.0), (0.0, 1.0, 1.0), (0.0, 1.0, -1.0),
+    (1.0, 1.0, 1.0), (1.0, 1.0, -1.0), (1.0, -1.0, 1.0), (-1.0, 1.0, 1.0),
+];
+
+/// Points within this fraction of the extent of a face plane count as on it
+const HULL_EPSILON: f32 = 1e-6;
+
+struct Face {
+    vertices: [usize; 3],
+    normal: Vec3,
+    offset: f32,
+    // Points above this face not yet on the hull
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl Face {
+    // Counter-clockwise vertices seen from outside. A sliver gets a zero normal,
+    // so it keeps the mesh closed but never sees a point.
+    fn new(points: &[Vec3], vertices: [usize; 3]) -> Face {
+        let [a, b, c] = vertices.map(|v| points[v]);
+        let normal = normalize(cross(sub(b, a), sub(c, a))).unwrap_or((0.0, 0.0, 0.0));
+        Face { vertices, normal, offset: dot(normal, a), outside: Vec::new(), alive: true }
+    }
+
+    fn distance(&self, p: Vec3) -> f32 {
+        dot(self.normal, p) - self.offset
+    }
+}
+
+// Index of the point farthest along `f`
+fn farthest(points: &[Vec3], candidates: impl Iterator<Item = usize>, f: impl Fn(Vec3) -> f32) -> Option<(usize, f32)> {
+    candidates.map(|i| (i, f(points[i]))).max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+// Largest-volume starting tetrahedron from the axis extremes, or `None` when
+// the points are (nearly) coplanar
+fn initial_tetrahedron(points: &[Vec3], eps: f32) -> Option<[usize; 4]> {
+    let mut extremes = Vec::with_capacity(6);
+    for axis in [(1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)] {
+        extremes.push(farthest(points, 0..points.len(), |p| dot(p, axis))?.0);
+        extremes.push(farthest(points, 0..points.len(), |p| -dot(p, axis))?.0);
+    }
+
+    let mut best = (0, 0, 0.0);
+    for &i in &extremes {
+        for &j in &extremes {
+            let d = dot(sub(points[i], points[j]), sub(points[i], points[j]));
+            if d > best.2 {
+                best = (i, j, d);
+            }
+        }
+    }
+    let (a, b, _) = best;
+    let ab = sub(points[b], points[a]);
+    let (c, area) = farthest(points, 0..points.len(), |p| {
+        let ap = sub(p, points[a]);
+        dot(cross(ab, ap), cross(ab, ap))
+    })?;
+    if area.sqrt() <= eps * dot(ab, ab).sqrt() {
+        return None;
+    }
+    let plane = normalize(cross(ab, sub(points[c], points[a])))?;
+    let (d, height) = farthest(points, 0..points.len(), |p| dot(plane, sub(p, points[a])).abs())?;
+    if height <= eps {
+        return None;
+    }
+    Some([a, b, c, d])
+}
+
+/// Hull triangles over `points`, each counter-clockwise seen from outside.
+/// Empty for fewer than four points or a flat point set.
+fn quickhull(points: &[Vec3]) -> Vec<[usize; 3]> {
+    if points.len() < 4 {
+        return Vec::new();
+    }
+    let extent = points.iter().fold(0.0f32, |m, p| m.max(p.0.abs()).max(p.1.abs()).max(p.2.abs()));
+    let eps = HULL_EPSILON * extent.max(f32::EPSILON);
+    let Some([a, b, c, d]) = initial_tetrahedron(points, eps) else { return Vec::new() };
+
+    let mut faces = Vec::new();
+    let inside = scale(add(add(points[a], points[b]), add(points[c], points[d])), 0.25);
+    for tri in [[a, b, c], [a, b, d], [a, c, d], [b, c, d]] {
+        let face = Face::new(points, tri);
+        if face.distance(inside) > 0.0 {
+            faces.push(Face::new(points, [tri[0], tri[2], tri[1]]));
+        } else {
+            faces.push(face);
+        }
+    }
+
+    let assign = |faces: &mut [Face], first_face: usize, candidates: &[usize]| {
+        for &p in candidates {
+            if let Some(face) = faces[first_face..].iter_mut().find(|f| f.distance(points[p]) > eps) {
+                face.outside.push(p);
+            }
+        }
+    };
+    let all: Vec<usize> = (0..points.len()).filter(|&i| ![a, b, c, d].contains(&i)).collect();
+    assign(&mut faces, 0, &all);
+
+    // Directed edge -> face that owns it, for walking to neighbors
+    let mut edge_faces = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        let [u, v, w] = face.vertices;
+        edge_faces.extend([((u, v), f), ((v, w), f), ((w, u), f)]);
+    }
+
+    // Faces that may still have outside points
+    let mut pending: Vec<usize> = (0..faces.len()).collect();
+    while let Some(start) = pending.pop() {
+        let face = &faces[start];
+        if !face.alive || face.outside.is_empty() {
+            continue;
+        }
+        let (apex, _) = farthest(points, face.outside.iter().copied(), |p| face.distance(p)).unwrap();
+        let eye = points[apex];
+
+        // Flood the connected region of faces the apex sees; its boundary edges
+        // form the horizon, which gets coned to the apex
+        let mut visible = vec![start];
+        let mut seen = HashSet::from([start]);
+        let mut horizon = Vec::new();
+        let mut stack = vec![start];
+        while let Some(f) = stack.pop() {
+            let [u, v, w] = faces[f].vertices;
+            for (p, q) in [(u, v), (v, w), (w, u)] {
+                let neighbor = edge_faces[&(q, p)];
+                if seen.contains(&neighbor) {
+                    continue;
+                }
+                if faces[neighbor].distance(eye) > eps {
+                    seen.insert(neighbor);
+                    visible.push(neighbor);
+                    stack.push(neighbor);
+                } else {
+                    horizon.push((p, q));
+                }
+            }
+        }
+
+        let mut orphans = Vec::new();
+        for &f in &visible {
+            faces[f].alive = false;
+            orphans.append(&mut faces[f].outside);
+            let [u, v, w] = faces[f].vertices;
+            for edge in [(u, v), (v, w), (w, u)] {
+                edge_faces.remove(&edge);
+            }
+        }
+        let first_new = faces.len();
+        for (p, q) in horizon {
+            let f = faces.len();
+            edge_faces.extend([((p, q), f), ((q, apex), f), ((apex, p), f)]);
+            faces.push(Face::new(points, [p, q, apex]));
+        }
+        orphans.retain(|&p| p != apex);
+        assign(&mut faces, first_new, &orphans);
+        pending.extend(first_new..faces.len());
+    }
+
+    faces.into_iter().filter(|f| f.alive).map(|f| f.vertices).collect()
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Convex hull of the atom centers as a flat triangle list for direct
+    /// rendering: three vertices per triangle, counter-clockwise seen from
+    /// outside, each `[x, y, z, nx, ny, nz]` with the face normal. Empty for
+    /// fewer than four atoms or a flat structure.
+    ///
+    /// Beyond 50,000 atoms the hull is built over an evenly strided sample plus
+    /// the extreme atoms along the axes and diagonals, so it is a close envelope rather than
+    /// exact — meant as a cheap shape preview, e.g. while a large file streams in.
+    pub fn convex_hull(&self) -> Vec<f32> {
+        let n = self.all_atoms.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut sample: Vec<usize> = (0..n).step_by(n.div_ceil(HULL_MAX_POINTS)).collect();
+        if sample.len() < n {
+            let position = |i: usize| (self.all_atoms[i].x, self.all_atoms[i].y, self.all_atoms[i].z);
+            for direction in SAMPLE_DIRECTIONS {
+                for sign in [1.0, -1.0] {
+                    let along = |i: usize| sign * dot(position(i), direction);
+                    sample.extend((0..n).max_by(|&i, &j| along(i).total_cmp(&along(j))));
+                }
+            }
+            sample.sort_unstable();
+            sample.dedup();
+        }
+
+        // Work relative to the sample's centroid to keep plane offsets small
+        let mut sum = [KahanSum::default(); 3];
+        for &i in &sample {
+            let atom = &self.all_atoms[i];
+            sum[0].add(atom.x);
+            sum[1].add(atom.y);
+            sum[2].add(atom.z);
+        }
+        let [x, y, z] = sum.map(|s| s.value());
+        let center = scale((x, y, z), 1.0 / sample.len() as f32);
+        let points: Vec<Vec3> = sample
+            .iter()
+            .map(|&i| sub((self.all_atoms[i].x, self.all_atoms[i].y, self.all_atoms[i].z), center))
+            .collect();
+
+        let triangles = quickhull(&points);
+        let mut out = Vec::with_capacity(triangles.len() * 18);
+        for tri in triangles {
+            let [a, b, c] = tri.map(|v| points[v]);
+            let normal = normalize(cross(sub(b, a), sub(c, a))).unwrap_or((0.0, 0.0, 0.0));
+            for p in [a, b, c] {
+                out.extend_from_slice(&[p.0 + center.0, p.1 + center.1, p.2 + center.2, normal.0, normal.1, normal.2]);
+            }
+        }
+        out
+    }
+}
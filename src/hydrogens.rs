@@ -0,0 +1,293 @@
+// Idealized hydrogens for structures that only list heavy atoms
+
+use wasm_bindgen::prelude::*;
+
+use crate::backbone::NOT_ALPHA_CARBON;
+use crate::elements::covalent_radius;
+use crate::math::{add, cross, dot, length, normalize, perpendicular, rotate_about_axis, scale, sub, Vec3};
+use crate::{MolecularSystem, RawAtom, SecondaryStructure};
+
+const HYDROGEN: u32 = 0;
+
+/// `detect_bonds` tolerance used when no bonds exist yet
+const BOND_TOLERANCE: f32 = 1.2;
+
+/// A terminal bond shorter than these fractions of the covalent radii sum is
+/// taken as double or triple
+const DOUBLE_BOND_RATIO: f32 = 0.93;
+const TRIPLE_BOND_RATIO: f32 = 0.85;
+
+/// Mean bond angles (degrees) above which a non-terminal atom is taken as
+/// trigonal or linear rather than tetrahedral
+const TRIGONAL_ANGLE: f32 = 115.0;
+const LINEAR_ANGLE: f32 = 155.0;
+
+const TETRAHEDRAL_ANGLE: f32 = 109.47;
+
+// Bonds an element usually forms; 0 for elements never given hydrogens
+fn valence(element: u32) -> usize {
+    match element {
+        4 | 19 => 4, // C, Si
+        3 | 6 => 3,  // N, P
+        2 | 5 => 2,  // O, S
+        _ => 0,
+    }
+}
+
+// Carbon and silicon: multiple bonds show in their angles
+fn angle_reveals_order(element: u32) -> bool {
+    matches!(element, 4 | 19)
+}
+
+// Electron domains around an atom from the mean angle between its bonds:
+// 4 tetrahedral, 3 trigonal, 2 linear
+fn domains_from_angles(dirs: &[Vec3]) -> usize {
+    let mut sum = 0.0;
+    let mut pairs = 0;
+    for (i, &a) in dirs.iter().enumerate() {
+        for &b in &dirs[i + 1..] {
+            sum += dot(a, b).clamp(-1.0, 1.0).acos().to_degrees();
+            pairs += 1;
+        }
+    }
+    let mean = sum / pairs as f32;
+    if mean > LINEAR_ANGLE {
+        2
+    } else if mean > TRIGONAL_ANGLE {
+        3
+    } else {
+        4
+    }
+}
+
+// Unit directions for up to `count` hydrogens on an atom with `domains`
+// electron domains whose existing bonds point along `dirs`. `reference`, a
+// direction from the single neighbor to one of its own neighbors, orients
+// the hydrogens of terminal atoms.
+fn hydrogen_directions(dirs: &[Vec3], domains: usize, count: usize, reference: Option<Vec3>) -> Vec<Vec3> {
+    let tetrahedral_half = (TETRAHEDRAL_ANGLE * 0.5).to_radians();
+    let candidates: Vec<Vec3> = match dirs {
+        [] => {
+            let s = 1.0 / 3f32.sqrt();
+            vec![(s, s, s), (s, -s, -s), (-s, s, -s), (-s, -s, s)]
+        }
+        [n] => {
+            let angle = match domains {
+                4 => TETRAHEDRAL_ANGLE,
+                3 => 120.0,
+                _ => 180.0,
+            }
+            .to_radians();
+            // Anti to the reference, so the first hydrogen is staggered
+            let side = reference
+                .and_then(|r| normalize(sub(scale(*n, dot(r, *n)), r)))
+                .unwrap_or_else(|| perpendicular(*n));
+            let first = add(scale(*n, angle.cos()), scale(side, angle.sin()));
+            let step = std::f32::consts::TAU / (domains - 1).max(1) as f32;
+            (0..domains - 1).map(|i| rotate_about_axis(first, *n, step * i as f32)).collect()
+        }
+        [a, b] => {
+            let bisector = normalize(scale(add(*a, *b), -1.0)).unwrap_or_else(|| perpendicular(*a));
+            match domains {
+                4 => {
+                    let normal = normalize(cross(*a, *b)).unwrap_or_else(|| perpendicular(bisector));
+                    let (sin, cos) = tetrahedral_half.sin_cos();
+                    vec![
+                        add(scale(bisector, cos), scale(normal, sin)),
+                        sub(scale(bisector, cos), scale(normal, sin)),
+                    ]
+                }
+                3 => vec![bisector],
+                _ => Vec::new(),
+            }
+        }
+        [a, b, c] if domains == 4 => {
+            let away = normalize(scale(add(add(*a, *b), *c), -1.0))
+                .or_else(|| normalize(cross(*a, *b)))
+                .unwrap_or_else(|| perpendicular(*a));
+            vec![away]
+        }
+        _ => Vec::new(),
+    };
+    candidates.into_iter().take(count).collect()
+}
+
+// Copy `source`'s entry onto the end of per-atom side data, if there is any
+fn extend_from<T: Copy>(values: &mut Vec<T>, source: usize) {
+    if !values.is_empty() {
+        values.push(values[source]);
+    }
+}
+
+impl MolecularSystem {
+    // Hydrogens each heavy atom is missing, as (parent, offset from the
+    // parent) pairs
+    fn missing_hydrogens(&self) -> Vec<(usize, Vec3)> {
+        let adjacency = self.adjacency();
+        let position = |i: usize| {
+            let atom = &self.all_atoms[i];
+            (atom.x, atom.y, atom.z)
+        };
+
+        let mut added = Vec::new();
+        for (i, atom) in self.all_atoms.iter().enumerate() {
+            let valence = valence(atom.element);
+            if valence == 0 {
+                continue;
+            }
+            let neighbors = adjacency.of(i);
+            let here = position(i);
+            let dirs: Vec<Vec3> = neighbors
+                .iter()
+                .filter_map(|&j| normalize(sub(position(j as usize), here)))
+                .collect();
+            if dirs.len() != neighbors.len() {
+                // Coincident neighbor: no sensible geometry
+                continue;
+            }
+
+            // Bond order beyond single, and the electron domains it leaves
+            let (extra_order, domains) = match neighbors {
+                [] => (0, 4),
+                &[j] => {
+                    let other = &self.all_atoms[j as usize];
+                    let ratio = length(sub(position(j as usize), here))
+                        / (covalent_radius(atom.element) + covalent_radius(other.element));
+                    let order = match other.element {
+                        HYDROGEN => 0,
+                        _ if ratio < TRIPLE_BOND_RATIO => 2,
+                        _ if ratio < DOUBLE_BOND_RATIO => 1,
+                        _ => 0,
+                    };
+                    (order, 4 - order)
+                }
+                _ => {
+                    let domains = domains_from_angles(&dirs);
+                    let order = if angle_reveals_order(atom.element) { 4 - domains } else { 0 };
+                    (order, domains)
+                }
+            };
+            let count = valence
+                .saturating_sub(neighbors.len() + extra_order)
+                .min(domains.saturating_sub(neighbors.len()));
+            if count == 0 {
+                continue;
+            }
+
+            let reference = match neighbors {
+                &[j] => adjacency
+                    .of(j as usize)
+                    .iter()
+                    .find(|&&k| k as usize != i)
+                    .and_then(|&k| normalize(sub(position(k as usize), position(j as usize)))),
+                _ => None,
+            };
+            let bond_length = covalent_radius(atom.element) + covalent_radius(HYDROGEN);
+            for dir in hydrogen_directions(&dirs, domains, count, reference) {
+                added.push((i, scale(dir, bond_length)));
+            }
+        }
+        added
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Add hydrogens to C, N, O, S, P and Si atoms that have fewer bonds than
+    /// their usual valence, at idealized positions (covalent-radii bond length,
+    /// tetrahedral, trigonal or linear angles). Uses the current bonds, running
+    /// `detect_bonds` first if there are none. Returns how many hydrogens were
+    /// added; they are appended after the existing atoms and bonded to their
+    /// parents, and per-atom data (velocities, displacement parameters,
+    /// B-factors, `get_original_indices`) is copied from the parent.
+    ///
+    /// Approximate by design: multiple bonds are guessed from geometry (short
+    /// terminal bonds, near-planar or linear carbons), every group is treated
+    /// as neutral, ring nitrogens get one hydrogen whether or not they carry
+    /// one (pyridine-like nitrogens are over-protonated), and rotatable
+    /// hydrogens (OH, SH, NH3) take a staggered default rather than an
+    /// optimised orientation. Run it once: atoms already at full valence are
+    /// left alone, so a second call adds nothing.
+    pub fn add_hydrogens(&mut self) -> usize {
+        if self.bonds.is_empty() && self.all_atoms.len() > 1 {
+            self.detect_bonds(BOND_TOLERANCE);
+        }
+        let added = self.missing_hydrogens();
+        if added.is_empty() {
+            return 0;
+        }
+
+        let first = self.all_atoms.len();
+        if self.original_indices.is_empty() {
+            self.original_indices = (0..first as u32).collect();
+        }
+        for (offset, &(parent, bond)) in added.iter().enumerate() {
+            let atom = self.all_atoms[parent];
+            let (x, y, z) = add((atom.x, atom.y, atom.z), bond);
+            self.all_atoms.push(RawAtom { x, y, z, element: HYDROGEN, occupancy: atom.occupancy });
+            if !self.precise_positions.is_empty() {
+                let [px, py, pz] = self.precise_positions[parent];
+                self.precise_positions.push([px + bond.0 as f64, py + bond.1 as f64, pz + bond.2 as f64]);
+            }
+            self.bonds.push((parent as u32, (first + offset) as u32));
+            extend_from(&mut self.velocities, parent);
+            if !self.forces.is_empty() {
+                self.forces.push((0.0, 0.0, 0.0));
+            }
+            extend_from(&mut self.adps, parent);
+            extend_from(&mut self.b_factors, parent);
+            if !self.ca_chains.is_empty() {
+                self.ca_chains.push(NOT_ALPHA_CARBON);
+            }
+            if !self.selected.is_empty() {
+                self.selected.push(false);
+            }
+            if !self.secondary_structure.is_empty() {
+                self.secondary_structure.push(SecondaryStructure::Coil as u8);
+            }
+            extend_from(&mut self.importance, parent);
+            extend_from(&mut self.original_indices, parent);
+        }
+        self.bonds.sort_unstable();
+        self.total_atom_count = self.all_atoms.len();
+        self.indices_changed();
+        self.positions_changed();
+
+        log!(self, LOG_INFO, "Added {} hydrogens ({} atoms)", added.len(), self.all_atoms.len());
+        added.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    use super::HYDROGEN;
+
+    #[test]
+    fn ethane_carbons_get_three_hydrogens_each() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("2\nethane\nC 0.0 0.0 0.0\nC 1.54 0.0 0.0\n").unwrap();
+
+        assert_eq!(system.add_hydrogens(), 6);
+        assert_eq!(system.all_atoms.len(), 8);
+        for h in 2..8u32 {
+            assert_eq!(system.all_atoms[h as usize].element, HYDROGEN);
+            let &(parent, _) = system.bond_pairs().iter().find(|&&(_, b)| b == h).unwrap();
+            let (p, a) = (system.all_atoms[parent as usize], system.all_atoms[h as usize]);
+            let distance = ((a.x - p.x).powi(2) + (a.y - p.y).powi(2) + (a.z - p.z).powi(2)).sqrt();
+            assert!((0.9..1.2).contains(&distance), "C-H {} A", distance);
+        }
+        let per_carbon = |c: u32| system.bond_pairs().iter().filter(|&&(a, b)| a == c && b >= 2).count();
+        assert_eq!((per_carbon(0), per_carbon(1)), (3, 3));
+        assert_eq!(system.add_hydrogens(), 0);
+    }
+
+    #[test]
+    fn short_carbon_bond_is_read_as_double() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("2\nethylene\nC 0.0 0.0 0.0\nC 1.33 0.0 0.0\n").unwrap();
+
+        assert_eq!(system.add_hydrogens(), 4);
+    }
+}
@@ -0,0 +1,93 @@
+// Per-atom importance, which biases distance LOD towards atoms the app cares about
+
+use wasm_bindgen::prelude::*;
+
+use crate::MolecularSystem;
+
+impl MolecularSystem {
+    // Distance LOD and the caps rank the atom at: `distance / (1 + importance * k)`,
+    // further divided by its element's `set_element_lod_bias`
+    pub(crate) fn lod_distance(&self, index: usize, distance: f32) -> f32 {
+        let distance = if self.element_lod_bias.is_empty() {
+            distance
+        } else {
+            distance / self.element_lod_bias(self.all_atoms[index].element)
+        };
+        match self.importance.get(index) {
+            Some(&importance) if importance > 0.0 => distance / (1.0 + importance * self.importance_weight),
+            _ => distance,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Set each atom's importance (storage order), one finite non-negative
+    /// value per atom. Distance LOD treats an atom as if it were
+    /// `1 + importance * k` times nearer (`set_importance_weight`), so a
+    /// ligand or active site stays sharp while the bulk degrades; the same
+    /// effective distance ranks atoms for `set_visible_atom_cap` and
+    /// `set_max_high_lod_atoms`. Frustum and distance culling are unchanged.
+    /// An empty slice resets every atom to 0, which is also what loading new
+    /// atoms does.
+    pub fn set_atom_importance(&mut self, values: &[f32]) -> Result<(), JsValue> {
+        if !values.is_empty() && values.len() != self.all_atoms.len() {
+            return Err(JsValue::from_str(&format!(
+                "importance has {} values for {} atoms", values.len(), self.all_atoms.len())));
+        }
+        if let Some(bad) = values.iter().find(|v| !(v.is_finite() && **v >= 0.0)) {
+            return Err(JsValue::from_str(&format!("invalid importance {} - must be finite and >= 0", bad)));
+        }
+        self.importance = values.to_vec();
+        self.invalidate_camera_cache();
+        Ok(())
+    }
+
+    /// Each atom's importance (storage order); empty when none is set
+    pub fn get_atom_importance(&self) -> Vec<f32> {
+        self.importance.clone()
+    }
+
+    /// Give the selected atoms (`set_selection`) importance `value` and every
+    /// other atom 0. Later selection changes do not update it.
+    pub fn set_importance_from_selection(&mut self, value: f32) {
+        if !(value.is_finite() && value >= 0.0) {
+            log!(self, LOG_ERRORS, "Invalid importance {} - must be finite and >= 0", value);
+            return;
+        }
+        self.importance = if self.selected.is_empty() {
+            Vec::new()
+        } else {
+            self.selected.iter().map(|&s| if s { value } else { 0.0 }).collect()
+        };
+        self.invalidate_camera_cache();
+    }
+
+    /// Set importance from the B-factor column, scaled so the largest B-factor
+    /// gets `value`. Suits files that store a per-residue score there, such
+    /// as AlphaFold's pLDDT. Without B-factors importance is cleared.
+    pub fn set_importance_from_b_factors(&mut self, value: f32) {
+        if !(value.is_finite() && value >= 0.0) {
+            log!(self, LOG_ERRORS, "Invalid importance {} - must be finite and >= 0", value);
+            return;
+        }
+        let max = self.b_factors.iter().copied().fold(0.0f32, f32::max);
+        self.importance = if max > 0.0 {
+            self.b_factors.iter().map(|&b| b.max(0.0) / max * value).collect()
+        } else {
+            Vec::new()
+        };
+        self.invalidate_camera_cache();
+    }
+
+    /// Set `k`, how strongly importance pulls atoms nearer for LOD (see
+    /// `set_atom_importance`). Default 1; 0 turns the bias off.
+    pub fn set_importance_weight(&mut self, k: f32) {
+        if !(k.is_finite() && k >= 0.0) {
+            log!(self, LOG_ERRORS, "Invalid importance weight {} - keeping {}", k, self.importance_weight);
+            return;
+        }
+        self.importance_weight = k;
+        self.invalidate_camera_cache();
+    }
+}
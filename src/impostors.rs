@@ -0,0 +1,59 @@
+// Far-field impostors: atoms just past the distance cull are merged per grid
+// cell into one representative sphere, so distant structure fades out as a
+// coarse fog instead of popping out at the far plane
+
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::math::{add, scale, Vec3};
+use crate::MolecularSystem;
+
+// Position sum, sum of cubed radii, and atom count of one cell
+#[derive(Default)]
+pub(crate) struct ImpostorAccumulator {
+    cells: HashMap<usize, (Vec3, f32, u32)>,
+}
+
+impl ImpostorAccumulator {
+    pub(crate) fn add(&mut self, cell: usize, p: Vec3, radius: f32) {
+        let entry = self.cells.entry(cell).or_insert(((0.0, 0.0, 0.0), 0.0, 0));
+        entry.0 = add(entry.0, p);
+        entry.1 += radius * radius * radius;
+        entry.2 += 1;
+    }
+
+    // `[cx, cy, cz, radius, count]` per cell, in cell order
+    pub(crate) fn finish(self) -> Vec<f32> {
+        let mut cells: Vec<_> = self.cells.into_iter().collect();
+        cells.sort_unstable_by_key(|&(cell, _)| cell);
+        let mut out = Vec::with_capacity(cells.len() * 5);
+        for (_, (sum, cubes, count)) in cells {
+            let c = scale(sum, 1.0 / count as f32);
+            out.extend_from_slice(&[c.0, c.1, c.2, cubes.cbrt(), count as f32]);
+        }
+        out
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Keep far-away structure visible as a coarse fog: atoms in view that are
+    /// distance-culled (past `far * far_cull_fraction`) but within `d` of the
+    /// eye are merged per spatial cell (the chunked-culling grid, else 8 Å
+    /// cells) into impostor spheres, read with `get_visible_impostors`. They
+    /// still count as distance-culled and never enter the visible atom list.
+    /// `d` at or below the distance limit, or non-finite, turns this off (the
+    /// default).
+    pub fn set_impostor_distance(&mut self, d: f32) {
+        self.impostor_distance = if d.is_finite() { d.max(0.0) } else { 0.0 };
+        self.invalidate_camera_cache();
+    }
+
+    /// Impostor spheres from the last cull (`set_impostor_distance`), 5 floats
+    /// each: `[cx, cy, cz, radius, atom_count]`. The center is the centroid of
+    /// the merged atoms and the radius is that of a sphere with their combined
+    /// volume. Empty when impostors are off or none were in range.
+    pub fn get_visible_impostors(&self) -> Vec<f32> {
+        self.cached_impostors.clone()
+    }
+}
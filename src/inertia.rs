@@ -0,0 +1,194 @@
+// Mass distribution: center of mass and principal axes of inertia
+
+use wasm_bindgen::prelude::*;
+
+use crate::adp::rotate_adp;
+use crate::elements::atomic_mass;
+use crate::math::{cross, dot, scale, sub, symmetric_eigen, KahanSum, Vec3};
+use crate::MolecularSystem;
+
+impl MolecularSystem {
+    // Per-atom masses; unit masses if no atom has a known mass
+    fn atom_masses(&self) -> Vec<f32> {
+        let masses: Vec<f32> = self.all_atoms.iter().map(|a| atomic_mass(a.element)).collect();
+        if masses.iter().sum::<f32>() > 0.0 {
+            masses
+        } else {
+            vec![1.0; masses.len()]
+        }
+    }
+
+    // Center of mass, principal axes (smallest moment first, right-handed) and
+    // their moments. Requires at least one atom.
+    fn principal_frame(&self) -> (Vec3, [Vec3; 3], [f32; 3]) {
+        let masses = self.atom_masses();
+        let center = self.mass_center(&masses);
+
+        let mut tensor = [[0.0f32; 3]; 3];
+        for (atom, &m) in self.all_atoms.iter().zip(&masses) {
+            let (x, y, z) = sub((atom.x, atom.y, atom.z), center);
+            tensor[0][0] += m * (y * y + z * z);
+            tensor[1][1] += m * (x * x + z * z);
+            tensor[2][2] += m * (x * x + y * y);
+            tensor[0][1] -= m * x * y;
+            tensor[0][2] -= m * x * z;
+            tensor[1][2] -= m * y * z;
+        }
+        tensor[1][0] = tensor[0][1];
+        tensor[2][0] = tensor[0][2];
+        tensor[2][1] = tensor[1][2];
+
+        let (moments, vectors) = symmetric_eigen(tensor);
+        let mut order = [0, 1, 2];
+        order.sort_by(|&i, &j| moments[i].total_cmp(&moments[j]));
+
+        let column = |i: usize| (vectors[0][i], vectors[1][i], vectors[2][i]);
+        // Fix signs so the output is deterministic: largest component of the first
+        // two axes positive, third axis completing a right-handed frame
+        let canonical = |v: Vec3| {
+            let largest = [v.0, v.1, v.2].into_iter().fold(0.0f32, |m, c| if c.abs() > m.abs() { c } else { m });
+            if largest < 0.0 { scale(v, -1.0) } else { v }
+        };
+        let a = canonical(column(order[0]));
+        let b = canonical(column(order[1]));
+        let c = cross(a, b);
+
+        (center, [a, b, c], [moments[order[0]], moments[order[1]], moments[order[2]]])
+    }
+
+    // Compensated sums keep large systems from drifting
+    fn mass_center(&self, masses: &[f32]) -> Vec3 {
+        let mut total = KahanSum::default();
+        let mut weighted = [KahanSum::default(); 3];
+        for (a, &m) in self.all_atoms.iter().zip(masses) {
+            total.add(m);
+            weighted[0].add(a.x * m);
+            weighted[1].add(a.y * m);
+            weighted[2].add(a.z * m);
+        }
+        let [x, y, z] = weighted.map(|sum| sum.value());
+        scale((x, y, z), 1.0 / total.value())
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Mass-weighted center `[x, y, z]`, empty when there are no atoms. Atoms of
+    /// unknown element carry no mass; if no atom has a known mass, all count equally.
+    pub fn center_of_mass(&self) -> Vec<f32> {
+        if self.all_atoms.is_empty() {
+            return Vec::new();
+        }
+        let c = self.mass_center(&self.atom_masses());
+        vec![c.0, c.1, c.2]
+    }
+
+    /// Principal axes of the mass-weighted inertia tensor about the center of
+    /// mass, as three unit vectors `[ax, ay, az, bx, ..., cz]` ordered from the
+    /// smallest moment (the long axis of an elongated molecule) to the largest,
+    /// forming a right-handed frame. With `include_moments` the three moments
+    /// follow. Empty when there are no atoms.
+    ///
+    /// The tensor is 3x3 symmetric, so it is diagonalized with cyclic Jacobi
+    /// rotations, which are robust for repeated or near-zero eigenvalues.
+    pub fn principal_axes(&self, include_moments: bool) -> Vec<f32> {
+        if self.all_atoms.is_empty() {
+            return Vec::new();
+        }
+        let (_, [a, b, c], moments) = self.principal_frame();
+        let mut out = vec![a.0, a.1, a.2, b.0, b.1, b.2, c.0, c.1, c.2];
+        if include_moments {
+            out.extend_from_slice(&moments);
+        }
+        out
+    }
+
+    /// Rotate the structure into its principal frame, centered on the center of
+    /// mass. `axis_map[k]` is the world axis (0 = X, 1 = Y, 2 = Z) for principal
+    /// axis `k`, ordered as in `principal_axes` (long axis first); an empty slice
+    /// means `[0, 1, 2]`, putting the long axis on +X. When the mapping is an odd
+    /// permutation the short (third) axis is flipped so the result stays a proper
+    /// rotation rather than a mirror image. Velocities, displacement ellipsoids
+    /// and the unit cell rotate along with the atoms.
+    pub fn align_to_principal_axes(&mut self, axis_map: &[u32]) -> Result<(), JsValue> {
+        let map = match axis_map {
+            [] => [0, 1, 2],
+            &[a, b, c] if a < 3 && b < 3 && c < 3 && a != b && b != c && a != c => [a as usize, b as usize, c as usize],
+            _ => return Err(JsValue::from_str("axis map must be a permutation of [0, 1, 2]")),
+        };
+        if self.all_atoms.is_empty() {
+            return Ok(());
+        }
+
+        let (center, mut axes, _) = self.principal_frame();
+        let inversions = (map[0] > map[1]) as u32 + (map[0] > map[2]) as u32 + (map[1] > map[2]) as u32;
+        if inversions % 2 == 1 {
+            axes[2] = scale(axes[2], -1.0);
+        }
+        let rotate = |v: Vec3| {
+            let mut out = [0.0; 3];
+            for (axis, &world) in axes.iter().zip(&map) {
+                out[world] = dot(v, *axis);
+            }
+            (out[0], out[1], out[2])
+        };
+
+        // f64 positions are centered in f64, leaving small values to rotate
+        let moved = self.move_precise(|p| {
+            let centered = ((p[0] - center.0 as f64) as f32, (p[1] - center.1 as f64) as f32, (p[2] - center.2 as f64) as f32);
+            let (x, y, z) = rotate(centered);
+            [x as f64, y as f64, z as f64]
+        });
+        if !moved {
+            for atom in &mut self.all_atoms {
+                (atom.x, atom.y, atom.z) = rotate(sub((atom.x, atom.y, atom.z), center));
+            }
+        }
+        for velocity in &mut self.velocities {
+            *velocity = rotate(*velocity);
+        }
+        for force in &mut self.forces {
+            *force = rotate(*force);
+        }
+        if !self.adps.is_empty() {
+            // Same rotation as a matrix: world row `map[k]` is principal axis k
+            let mut r = [[0.0; 3]; 3];
+            for (axis, &world) in axes.iter().zip(&map) {
+                r[world] = [axis.0, axis.1, axis.2];
+            }
+            for u in &mut self.adps {
+                *u = rotate_adp(&r, u);
+            }
+        }
+        if let Some(cell) = &mut self.unit_cell {
+            cell.a = rotate(cell.a);
+            cell.b = rotate(cell.b);
+            cell.c = rotate(cell.c);
+            cell.origin = rotate(sub(cell.origin, center));
+        }
+
+        self.positions_changed();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    #[test]
+    fn center_of_mass_stays_accurate_for_a_million_distant_atoms() {
+        let n = 1_000_000;
+        let mut xyz = format!("{}\n\n", n);
+        for i in 0..n {
+            xyz += if i % 2 == 0 { "C 1000.0 -2000.0 500.0\n" } else { "C 1000.1 -2000.2 500.1\n" };
+        }
+        let mut system = MolecularSystem::new();
+        system.load_xyz(&xyz).unwrap();
+
+        let center = system.center_of_mass();
+        for (got, exact) in center.iter().zip([1000.05, -2000.1, 500.05]) {
+            assert!((got - exact).abs() < 1e-2, "center {:?}", center);
+        }
+    }
+}
@@ -1,14 +1,95 @@
+use std::cell::{Cell, OnceCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use wasm_bindgen::prelude::*;
-use web_sys::console;
 
-// Macro for console logging
+// Console logging, gated by the system's log level:
+// `log!(self, LOG_INFO, "...", args)`. The console only exists in the
+// browser, so native builds (tests) stay quiet
 macro_rules! log {
-    ( $( $t:tt )* ) => {
-        console::log_1(&format!( $( $t )* ).into());
+    ( $system:expr, $level:ident, $( $t:tt )* ) => {
+        if cfg!(target_arch = "wasm32") && $system.log_level >= $crate::$level {
+            web_sys::console::log_1(&format!( $( $t )* ).into());
+        }
     }
 }
 
+// Log levels for `set_log_level`; 0 is silent
+const LOG_ERRORS: u32 = 1;
+const LOG_INFO: u32 = 2;
+const LOG_VERBOSE: u32 = 3;
+
+mod adp;
+mod analysis;
+mod atom_buffer;
+mod backbone;
+mod bonds;
+mod batches;
+mod bounds;
+mod cavities;
+mod budget;
+mod builder;
+mod clip;
+mod crystal;
+mod dedup;
+mod depth;
+mod direct_write;
+mod edges;
+mod elements;
+mod explode;
+mod frame_cache;
+mod grid;
+mod hydrogens;
+mod gro;
+mod hull;
+mod importance;
+mod impostors;
+mod inertia;
+mod orientations;
+mod math;
+mod memory;
+mod merge;
+mod mol2;
+mod pdb;
+mod points;
+mod quantize;
+mod rings;
+mod scalars;
+mod sdf;
+mod secondary;
+mod selection;
+mod shuffle;
+mod surface;
+mod perf;
+mod precision;
+mod stream;
+mod thumbnail;
+mod velocities;
+mod volume;
+mod warnings;
+mod world;
+mod xyz;
+
+use crystal::UnitCell;
+use grid::SpatialGrid;
+pub use atom_buffer::AtomBuffer;
+pub use builder::{FillOrder, Lattice, MolecularSystemBuilder};
+pub use orientations::OrientationSource;
+pub use pdb::AltLocPolicy;
+pub use points::PointStyle;
+pub use scalars::ScalarChannel;
+pub use secondary::SecondaryStructure;
+use perf::{elapsed_ms, now_ms, Timings};
+use stream::StreamingLoad;
+use math::{add, cross, dot, length, normalize, rotate_about_axis, scale, sub, SplitMix64};
+
+/// Leading byte of `Camera::to_bytes`, bumped when the layout changes
+const CAMERA_FORMAT_VERSION: u8 = 1;
+
+/// `Camera::to_bytes` length: the version byte and 9 f32
+const CAMERA_BYTES: usize = 1 + 9 * 4;
+
 #[wasm_bindgen]
+#[derive(Clone, Copy)]
 pub struct Camera {
     pub x: f32,
     pub y: f32,
@@ -16,6 +97,9 @@ pub struct Camera {
     pub target_x: f32,
     pub target_y: f32,
     pub target_z: f32,
+    pub up_x: f32,
+    pub up_y: f32,
+    pub up_z: f32,
 }
 
 #[wasm_bindgen]
@@ -25,10 +109,226 @@ impl Camera {
         Camera {
             x: 0.0, y: 2.0, z: 5.0,
             target_x: 0.0, target_y: 0.0, target_z: 0.0,
+            up_x: 0.0, up_y: 1.0, up_z: 0.0,
+        }
+    }
+
+    /// Virtual-trackball rotation: rotate the eye (and up vector) around the target
+    /// by the arc between two pointer positions given in NDC (-1..1)
+    pub fn trackball_rotate(&mut self, from_x: f32, from_y: f32, to_x: f32, to_y: f32) {
+        let from = trackball_point(from_x, from_y);
+        let to = trackball_point(to_x, to_y);
+        let angle = dot(from, to).clamp(-1.0, 1.0).acos();
+        if angle < 1e-6 {
+            return;
+        }
+
+        let target = (self.target_x, self.target_y, self.target_z);
+        let offset = sub((self.x, self.y, self.z), target);
+        let Some((forward, right, up)) = self.view_basis() else { return };
+
+        // Trackball axis is in view space (x right, y up, z towards the viewer)
+        let axis_view = cross(from, to);
+        let axis = add(add(scale(right, axis_view.0), scale(up, axis_view.1)), scale(forward, -axis_view.2));
+        let Some(axis) = normalize(axis) else { return };
+
+        // Dragging the scene one way is orbiting the eye the other way
+        let offset = rotate_about_axis(offset, axis, -angle);
+        let up = rotate_about_axis(up, axis, -angle);
+
+        self.x = target.0 + offset.0;
+        self.y = target.1 + offset.1;
+        self.z = target.2 + offset.2;
+        self.up_x = up.0;
+        self.up_y = up.1;
+        self.up_z = up.2;
+    }
+
+    /// Whether this camera defines a view: finite coordinates, the eye apart
+    /// from the target, and an up vector that is non-zero and not parallel to
+    /// the view direction. Projection and culling still accept an invalid camera
+    /// (substituting a world up vector, or looking down -Z when eye == target),
+    /// but the result is arbitrary, so skip rendering such a frame, e.g. while
+    /// an interpolated camera passes through its target.
+    pub fn is_valid(&self) -> bool {
+        if !self.fields().iter().all(|c| c.is_finite()) {
+            return false;
+        }
+        let Some(forward) = normalize(sub((self.target_x, self.target_y, self.target_z), (self.x, self.y, self.z))) else {
+            return false;
+        };
+        normalize((self.up_x, self.up_y, self.up_z))
+            .and_then(|up| normalize(cross(forward, up)))
+            .is_some()
+    }
+
+    /// Dolly the eye along the view axis so a sphere of `atom_radius` at
+    /// `atom_xyz` appears `desired_px_radius` pixels in radius, e.g. to keep a
+    /// tracked atom the same size while orbiting. `fov` is vertical, in radians,
+    /// and `viewport_h` is the viewport height in pixels. Direction and target
+    /// are kept; does nothing if the size is unreachable without passing the
+    /// target or the inputs are degenerate.
+    pub fn track_atom_screen_size(
+        &mut self,
+        atom_xyz: &[f32],
+        desired_px_radius: f32,
+        atom_radius: f32,
+        fov: f32,
+        viewport_h: f32,
+    ) {
+        let &[ax, ay, az] = atom_xyz else { return };
+        let Some((forward, _, _)) = self.view_basis() else { return };
+        let tan_half = (fov * 0.5).tan();
+        if !(desired_px_radius > 0.0 && tan_half > 0.0) {
+            return;
+        }
+
+        // Projected radius in pixels is radius / (depth * tan(fov/2)) * viewport_h / 2
+        let depth = atom_radius * viewport_h / (2.0 * desired_px_radius * tan_half);
+        let target = (self.target_x, self.target_y, self.target_z);
+        // The atom's depth is its offset past the target plus the eye-target distance
+        let distance = depth - dot(sub((ax, ay, az), target), forward);
+        if !(distance > 0.0 && distance.is_finite()) {
+            return;
+        }
+
+        let eye = sub(target, scale(forward, distance));
+        self.x = eye.0;
+        self.y = eye.1;
+        self.z = eye.2;
+    }
+
+    /// The camera packed for sharing, e.g. base64-encoded into a view link:
+    /// a format version byte (currently 1), then eye, target and up as 9
+    /// little-endian f32, 37 bytes in all. Values round-trip exactly through
+    /// `from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(CAMERA_BYTES);
+        bytes.push(CAMERA_FORMAT_VERSION);
+        for v in self.fields() {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// A camera unpacked from `to_bytes`. An unknown version or a wrong
+    /// length is an error; the view itself is not checked, so test
+    /// `is_valid` before using a camera from an untrusted link.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Camera, JsValue> {
+        match bytes.first() {
+            Some(&CAMERA_FORMAT_VERSION) => {}
+            Some(&version) => return Err(JsValue::from_str(&format!("Unknown camera format version {}", version))),
+            None => return Err(JsValue::from_str("Camera data is empty")),
+        }
+        if bytes.len() != CAMERA_BYTES {
+            return Err(JsValue::from_str(&format!("Camera data is {} bytes, expected {}", bytes.len(), CAMERA_BYTES)));
+        }
+        let mut v = [0.0f32; 9];
+        for (value, chunk) in v.iter_mut().zip(bytes[1..].chunks_exact(4)) {
+            *value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        Ok(Camera {
+            x: v[0], y: v[1], z: v[2],
+            target_x: v[3], target_y: v[4], target_z: v[5],
+            up_x: v[6], up_y: v[7], up_z: v[8],
+        })
+    }
+}
+
+impl Camera {
+    // Eye, target and up in `to_bytes` order
+    fn fields(&self) -> [f32; 9] {
+        [
+            self.x, self.y, self.z,
+            self.target_x, self.target_y, self.target_z,
+            self.up_x, self.up_y, self.up_z,
+        ]
+    }
+
+    // Orthonormal (forward, right, up) frame, None if eye == target or a coordinate
+    // is non-finite. A zero or view-parallel up vector is replaced by world +Y,
+    // or +Z when looking along Y.
+    fn view_basis(&self) -> Option<(math::Vec3, math::Vec3, math::Vec3)> {
+        let forward = normalize(sub((self.target_x, self.target_y, self.target_z), (self.x, self.y, self.z)))?;
+        let up = normalize((self.up_x, self.up_y, self.up_z)).unwrap_or((0.0, 1.0, 0.0));
+        let right = normalize(cross(forward, up))
+            .or_else(|| normalize(cross(forward, (0.0, 1.0, 0.0))))
+            .or_else(|| normalize(cross(forward, (0.0, 0.0, 1.0))))?;
+        Some((forward, right, cross(right, forward)))
+    }
+
+    // Perspective projection of a world point to `(ndc_x, ndc_y, view_depth)`,
+    // None if the point is not in front of the eye. `fov` is vertical, in radians.
+    fn project_ndc(&self, p: math::Vec3, fov: f32, aspect: f32) -> Option<(f32, f32, f32)> {
+        let (forward, right, up) = self.view_basis()?;
+        let rel = sub(p, (self.x, self.y, self.z));
+        let depth = dot(rel, forward);
+        if depth <= 0.0 {
+            return None;
         }
+        let tan_half = (fov * 0.5).tan();
+        Some((
+            dot(rel, right) / (depth * tan_half * aspect),
+            dot(rel, up) / (depth * tan_half),
+            depth,
+        ))
+    }
+}
+
+// Project an NDC point onto the trackball: a unit sphere near the centre,
+// blending into a hyperbolic sheet further out so edge drags stay smooth
+fn trackball_point(x: f32, y: f32) -> (f32, f32, f32) {
+    let d2 = x * x + y * y;
+    let z = if d2 <= 0.5 { (1.0 - d2).sqrt() } else { 0.5 / d2.sqrt() };
+    normalize((x, y, z)).unwrap_or((0.0, 0.0, 1.0))
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Perspective projection parameters used for culling (fov in radians)
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Projection {
+    pub fov: f32,
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+#[wasm_bindgen]
+impl Projection {
+    #[wasm_bindgen(constructor)]
+    pub fn new(fov: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Projection { fov, aspect, near, far }
+    }
+}
+
+impl Projection {
+    // Mix the projection part of the camera-cache key into `hash`
+    fn hash_into(&self, hash: u64, fov_scale: f32) -> u64 {
+        [self.fov * fov_scale, self.aspect * 100.0, self.near * 1000.0, self.far * 10.0]
+            .into_iter()
+            .fold(hash, |hash, v| fnv_mix(hash, quantize_for_hash(v)))
     }
 }
 
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// One FNV-1a step over a whole word
+fn fnv_mix(hash: u64, word: u64) -> u64 {
+    (hash ^ word).wrapping_mul(FNV_PRIME)
+}
+
+// Nearest whole step, sign kept, as hash input
+fn quantize_for_hash(v: f32) -> u64 {
+    v.round() as i64 as u64
+}
+
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct AtomData {
@@ -40,6 +340,18 @@ pub struct AtomData {
     pub lod_level: u32,
 }
 
+/// Floats per atom in the packed render buffers (`get_render_payload`,
+/// `write_visible_atoms_to`)
+const PACKED_ATOM_FLOATS: usize = 6;
+
+impl AtomData {
+    // `[x, y, z, element, radius, lod_level]`, the layout of every packed
+    // render buffer
+    fn packed(&self) -> [f32; PACKED_ATOM_FLOATS] {
+        [self.x, self.y, self.z, self.element as f32, self.radius, self.lod_level as f32]
+    }
+}
+
 // Raw atom storage - simulates file data
 #[derive(Clone, Copy)]
 struct RawAtom {
@@ -47,20 +359,460 @@ struct RawAtom {
     y: f32,
     z: f32,
     element: u32,
+    occupancy: f32,
+}
+
+// Default `set_camera_hash_precision` scales
+const DEFAULT_CAMERA_HASH_POSITION_SCALE: f32 = 1000.0;
+const DEFAULT_CAMERA_HASH_FOV_SCALE: f32 = 100.0;
+
+// Default `set_frustum_margin`: the view cone's half-angle is 1.2 times half
+// the vertical FOV
+const DEFAULT_FRUSTUM_MARGIN: f32 = 0.2;
+
+// Most LOD levels `set_lod_level_count` accepts
+const MAX_LOD_LEVELS: u32 = 16;
+const DEFAULT_LOD_LEVELS: u32 = 4;
+
+// Widest band `set_lod_hysteresis` accepts, as a fraction of each threshold
+const MAX_LOD_HYSTERESIS: f32 = 0.5;
+
+// Unscaled threshold ladder: the point cutoff, the midpoint and the high-poly
+// cutoff. Four levels use exactly these; other counts interpolate along them.
+const LOD_LADDER: [f32; 3] = [50.0, 20.0, 10.0];
+
+// Distances beyond which atoms and bonds drop to the next-coarser LOD. Level 0
+// is a point (very cheap to render), higher levels are increasingly detailed
+// meshes, and the top level is reserved for the few atoms closest to the eye.
+#[derive(Clone, Copy)]
+struct LodThresholds {
+    // Descending; beyond `cutoffs[k]` (and within `cutoffs[k - 1]`) is LOD k,
+    // so LOD k covers `(cutoffs[k], cutoffs[k - 1]]` and an atom exactly on a
+    // cutoff takes the finer level
+    cutoffs: [f32; MAX_LOD_LEVELS as usize - 1],
+    count: usize,
+}
+
+impl LodThresholds {
+    fn new(levels: u32, scale: f32) -> Self {
+        let count = levels.clamp(1, MAX_LOD_LEVELS) as usize - 1;
+        let mut cutoffs = [0.0; MAX_LOD_LEVELS as usize - 1];
+        for (k, cutoff) in cutoffs[..count].iter_mut().enumerate() {
+            // Position along the ladder, 0 at the point cutoff and 1 at the high-poly one
+            let u = if count > 1 { k as f32 / (count - 1) as f32 } else { 0.0 };
+            let (from, to, t) = if u <= 0.5 {
+                (LOD_LADDER[0], LOD_LADDER[1], u * 2.0)
+            } else {
+                (LOD_LADDER[1], LOD_LADDER[2], u * 2.0 - 1.0)
+            };
+            *cutoff = (from + (to - from) * t) * scale;
+        }
+        LodThresholds { cutoffs, count }
+    }
+
+    fn level(&self, distance: f32) -> u32 {
+        self.cutoffs().iter().position(|&cutoff| distance > cutoff).unwrap_or(self.count) as u32
+    }
+
+    // `level`, except that an atom keeps its `previous` level while still
+    // within that level's range widened by `band` (a fraction of each cutoff)
+    fn sticky_level(&self, distance: f32, previous: u8, band: f32) -> u32 {
+        let level = self.level(distance);
+        let previous = previous as u32;
+        // LOD_HIDDEN and levels from a longer ladder have no range here
+        if band <= 0.0 || previous == level || previous > self.top() {
+            return level;
+        }
+        let cutoffs = self.cutoffs();
+        let near_enough = previous == 0 || distance <= cutoffs[previous as usize - 1] * (1.0 + band);
+        let far_enough = previous == self.top() || distance > cutoffs[previous as usize] * (1.0 - band);
+        if near_enough && far_enough { previous } else { level }
+    }
+
+    fn top(&self) -> u32 {
+        self.count as u32
+    }
+
+    fn cutoffs(&self) -> &[f32] {
+        &self.cutoffs[..self.count]
+    }
+}
+
+// Relative slack on chunk bounding spheres and cone angles, so rounding can't
+// make a chunk test reject an atom the per-atom test would keep
+const CHUNK_CULL_MARGIN: f32 = 1e-3;
+
+// Cell size (Å) of the grid built for estimates and spatial queries when
+// chunked culling has not already built one
+const COARSE_CELL_SIZE: f32 = 8.0;
+
+/// Which API surface a system exposes.
+///
+/// - `Small`: the legacy diatomic getters (`get_atom_count`, `get_atom_data`,
+///   `get_bond_data`) are live whatever the atom count, and atoms 0 and 1 get
+///   the implicit H-F bond when no bonds are set.
+/// - `Large`: those legacy getters report no atoms or bonds and there is no
+///   implicit bond; use `get_atom` and the culling APIs instead.
+/// - `Auto` (default): `Small` with at most 2 atoms, `Large` otherwise.
+///
+/// `get_bond_count` and `get_bond` report the real bonds in every mode, as
+/// do the culling, analysis and loading APIs.
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SystemMode {
+    Auto,
+    Small,
+    Large,
+}
+
+/// Axis convention of the files being loaded, for `set_coordinate_handedness`
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Handedness {
+    RightHanded,
+    LeftHanded,
+}
+
+// Default fraction of the far plane beyond which atoms are distance-culled
+const DEFAULT_FAR_CULL_FRACTION: f32 = 0.8;
+
+/// Default bound on the animation speed magnitude; see `set_max_animation_speed`
+const DEFAULT_MAX_ANIMATION_SPEED: f32 = 100.0;
+
+// Per-atom LOD marker for atoms outside the visible set
+const LOD_HIDDEN: u8 = u8::MAX;
+
+// Which of the atoms at `distances` are among the `cap` nearest (ties broken
+// arbitrarily), by partial selection rather than a full sort
+fn nearest_mask(cap: usize, distances: &[f32]) -> Vec<bool> {
+    let mut keep = vec![true; distances.len()];
+    if distances.len() <= cap {
+        return keep;
+    }
+    let mut ranked: Vec<usize> = (0..distances.len()).collect();
+    ranked.select_nth_unstable_by(cap, |&a, &b| distances[a].total_cmp(&distances[b]));
+    for &i in &ranked[cap..] {
+        keep[i] = false;
+    }
+    keep
+}
+
+// Cap LODs by distance rank: the `budget` closest atoms keep up to the `top`
+// LOD, the next `budget` up to one below, and so on down to LOD 1, with the
+// rest dropping to points. Uses partial selection per tier rather than a full sort.
+fn apply_lod_budget(budget: usize, top: u32, distances: &[f32], atoms: &mut [AtomData]) {
+    if atoms.len() <= budget {
+        return;
+    }
+    let mut order: Vec<usize> = (0..atoms.len()).collect();
+    let mut start = 0;
+    for cap in (0..=top).rev() {
+        let rest = &mut order[start..];
+        if cap > 0 && rest.len() > budget {
+            rest.select_nth_unstable_by(budget, |&a, &b| distances[a].total_cmp(&distances[b]));
+        }
+        let end = if cap > 0 { (start + budget).min(order.len()) } else { order.len() };
+        for &i in &order[start..end] {
+            atoms[i].lod_level = atoms[i].lod_level.min(cap);
+        }
+        start = end;
+        if start == order.len() {
+            break;
+        }
+    }
+}
+
+// Camera-derived parameters of one cull, shared by every per-atom test
+struct CullFrame {
+    cam_pos: math::Vec3,
+    view_dir: math::Vec3,
+    max_distance: f32,
+    // Atoms past `max_distance` but within this feed far-field impostors
+    impostor_distance: f32,
+    fov_threshold: f32,
+    frustum_culling: bool,
+    clip_planes: Vec<clip::ClipPlane>,
+    // Along-view and lateral weights on the LOD distance, None when isotropic
+    lod_weights: Option<(f32, f32)>,
+    lod: LodThresholds,
+}
+
+enum AtomCull {
+    // Kept, at this LOD distance from the camera
+    Visible(f32),
+    // Distance-culled, but in view within the impostor distance
+    Impostor,
+    Distance,
+    Frustum,
+}
+
+impl CullFrame {
+    fn test(&self, p: math::Vec3) -> AtomCull {
+        self.test_offset(p, sub(p, self.cam_pos))
+    }
+
+    // `test` given the camera-to-atom vector, for callers that take it in f64
+    fn test_offset(&self, p: math::Vec3, (dx, dy, dz): math::Vec3) -> AtomCull {
+        if self.clip_planes.iter().any(|plane| plane.hides(p)) {
+            return AtomCull::Frustum;
+        }
+        let distance = (dx*dx + dy*dy + dz*dz).sqrt();
+
+        // Natural distance culling based on camera far plane
+        let beyond = distance > self.max_distance;
+        if beyond && distance > self.impostor_distance {
+            return AtomCull::Distance;
+        }
+
+        // Natural frustum culling; an atom at the camera position has no
+        // direction and is kept
+        let to_atom_length = distance;
+        if self.frustum_culling && to_atom_length > f32::EPSILON {
+            let to_atom_normalized = (dx/to_atom_length, dy/to_atom_length, dz/to_atom_length);
+            let dot_product = self.view_dir.0 * to_atom_normalized.0 +
+                            self.view_dir.1 * to_atom_normalized.1 +
+                            self.view_dir.2 * to_atom_normalized.2;
+
+            // Cull atoms outside expanded view frustum
+            if dot_product < self.fov_threshold {
+                return if beyond { AtomCull::Distance } else { AtomCull::Frustum };
+            }
+        }
+
+        if beyond { AtomCull::Impostor } else { AtomCull::Visible(self.lod_distance((dx, dy, dz), distance)) }
+    }
+
+    // Camera distance for LOD decisions, with its along-view and lateral
+    // parts weighted by `set_lod_distance_weights`
+    fn lod_distance(&self, to_atom: math::Vec3, distance: f32) -> f32 {
+        let Some((along_weight, lateral_weight)) = self.lod_weights else { return distance };
+        let along = dot(to_atom, self.view_dir);
+        let lateral = (distance * distance - along * along).max(0.0).sqrt();
+        ((along_weight * along).powi(2) + (lateral_weight * lateral).powi(2)).sqrt()
+    }
+
+    fn chunk_bounds(&self, cell_size: f32) -> ChunkBounds {
+        let half = cell_size * 0.5;
+        ChunkBounds {
+            half,
+            radius: half * 3f32.sqrt() * (1.0 + CHUNK_CULL_MARGIN),
+            half_angle: self.fov_threshold.clamp(-1.0, 1.0).acos(),
+        }
+    }
+
+    // Whole-chunk test against the chunk's bounding sphere: `Distance` or
+    // `Frustum` when no point of the chunk can pass that test, `None` when its
+    // atoms need testing. Conservative, so it never drops a visible atom.
+    fn test_chunk(&self, bounds: &ChunkBounds, corner: math::Vec3) -> Option<AtomCull> {
+        let half = bounds.half;
+        let center = add(corner, (half, half, half));
+        let to_center = sub(center, self.cam_pos);
+        let center_distance = length(to_center);
+        if center_distance - bounds.radius > self.max_distance.max(self.impostor_distance) {
+            return Some(AtomCull::Distance);
+        }
+        if self.clip_planes.iter().any(|plane| plane.hides_sphere(center, bounds.radius)) {
+            return Some(AtomCull::Frustum);
+        }
+        if self.frustum_culling && center_distance > bounds.radius {
+            let angle = (dot(to_center, self.view_dir) / center_distance).clamp(-1.0, 1.0).acos();
+            let spread = (bounds.radius / center_distance).asin();
+            if angle - spread > bounds.half_angle + CHUNK_CULL_MARGIN {
+                return Some(AtomCull::Frustum);
+            }
+        }
+        None
+    }
+}
+
+// Cube chunks of one grid as seen by a `CullFrame`
+struct ChunkBounds {
+    half: f32,
+    // Bounding-sphere radius, padded by the cull margin
+    radius: f32,
+    // View cone half-angle
+    half_angle: f32,
+}
+
+// Counters from the most recent cull pass
+#[derive(Clone, Copy, Default)]
+struct CullStats {
+    total: usize,
+    tested: usize,
+    frustum_culled: usize,
+    distance_culled: usize,
+    visible: usize,
+    // Of the distance-culled, those dropped only to fit `set_cache_memory_budget`
+    memory_culled: usize,
 }
 
 #[wasm_bindgen]
 pub struct MolecularSystem {
     // ALL atoms stored here - simulates loaded file data
     all_atoms: Vec<RawAtom>,
+    // Whether the atoms came from a parsed file rather than the generator
+    loaded_from_file: bool,
     total_atom_count: usize,
     grid_size: f32,
     time: f32,
+    // Signed; negative runs `time` backward. Magnitude at most `max_animation_speed`
     animation_speed: f32,
+    max_animation_speed: f32,
+
+    // Synthetic generator settings
+    generator_lattice: Lattice,
+    generator_fill_order: FillOrder,
+    generator_seed: u64,
+    // Cumulative element weights; empty means the legacy H/F/O/N set, cycled
+    // as `i % 4` unless `generator_random_elements` draws them from the PRNG
+    generator_element_cdf: Vec<f32>,
+    generator_random_elements: bool,
 
     // Camera-dependent data - recalculated on every camera change
     current_camera_hash: u64,
+    // Camera-hash quantization steps per unit of position and per radian of
+    // field of view; see `set_camera_hash_precision`
+    camera_hash_position_scale: f32,
+    camera_hash_fov_scale: f32,
+    // Whether the last visibility query was answered from the cache
+    last_query_cached: bool,
     cached_visible_atoms: Vec<AtomData>,
+    // Storage index of each entry in `cached_visible_atoms`
+    cached_visible_indices: Vec<u32>,
+    // `get_visible_impostors` records of the last cull
+    cached_impostors: Vec<f32>,
+    preserve_order: bool,
+    // Visible list front-to-back from the eye; see `set_opaque_sort`
+    opaque_sort: bool,
+    frustum_culling: bool,
+    // See `set_frustum_margin`
+    frustum_margin: f32,
+    // Half-spaces every visible atom must lie in; see `add_clip_plane`
+    clip_planes: Vec<clip::ClipPlane>,
+    // Atoms farther than `far * far_cull_fraction` are distance-culled
+    far_cull_fraction: f32,
+    // Distance-culled atoms nearer than this become impostors; 0 is off
+    impostor_distance: f32,
+    // Chunk size when chunked culling is on, and the chunk grid built for the
+    // current positions
+    chunked_culling: Option<f32>,
+    chunk_grid: Option<SpatialGrid>,
+    // Grid for `estimate_visible_count` and `atoms_within` without chunked
+    // culling, built on first use
+    coarse_grid: OnceCell<SpatialGrid>,
+    // See `set_explode`; 0 for the normal view
+    explode_factor: f32,
+    // Per-atom exploded-view offsets at factor 1, found on first use per bond set
+    explode_directions: OnceCell<Vec<math::Vec3>>,
+    // At most this many atoms get full LOD; see `set_max_high_lod_atoms`
+    max_high_lod_atoms: Option<usize>,
+    // At most this many atoms are visible, the nearest; see `set_visible_atom_cap`
+    visible_atom_cap: Option<usize>,
+    // Bytes the visible set and remembered frames may hold; see `set_cache_memory_budget`
+    cache_memory_budget: Option<usize>,
+    // Multiplier on the LOD thresholds, driven by `frame_budget` when set
+    lod_quality: f32,
+    lod_levels: u32,
+    // See `set_lod_distance_weights`; None for plain distance
+    lod_distance_weights: Option<(f32, f32)>,
+    // See `set_lod_hysteresis`; 0 for none
+    lod_hysteresis: f32,
+    // How `get_visible_point_sizes` sizes LOD-0 atoms
+    point_style: PointStyle,
+    point_pixel_size: f32,
+    // See `set_edge_softness`; 0 for hard edges
+    edge_softness: f32,
+    frame_budget: Option<budget::FrameBudget>,
+    // `bounding_sphere` results, box-based then exact, until atoms move
+    bounding_spheres: [Cell<Option<[f32; 4]>>; 2],
+    cull_stats: CullStats,
+    timings: Timings,
+    // Projection the cached visible set was built with
+    last_cull_params: Option<Projection>,
+    // Eye position of that cull, and `next_visible_batch` progress through it
+    last_cull_eye: Option<math::Vec3>,
+    visible_batches: Option<batches::VisibleBatches>,
+    frame_cache: frame_cache::FrameCache,
+    cached_visible_bonds: Vec<BondData>,
+    // Atom indices of each entry in `cached_visible_bonds`
+    cached_visible_bond_pairs: Vec<(u32, u32)>,
+    // LOD of every atom in the last cull (LOD_HIDDEN if culled), the visible
+    // atoms whose LOD differs from the cull before it, and the atoms that
+    // appeared or disappeared (`get_visibility_delta` encoding)
+    atom_lods: Vec<u8>,
+    lod_changes: Vec<u32>,
+    visibility_delta: Vec<i32>,
+
+    // Bonds as atom index pairs
+    bonds: Vec<(u32, u32)>,
+    // Whether `bonds` came from the file or `set_bonds` rather than `detect_bonds`
+    explicit_bonds: bool,
+    // Per element pair (ascending) bond length cutoffs for `detect_bonds`
+    bond_cutoff_overrides: HashMap<(u32, u32), f32>,
+    // Per-atom velocities, empty when none were provided
+    velocities: Vec<math::Vec3>,
+    // Per-atom forces, empty when none were provided
+    forces: Vec<math::Vec3>,
+    // Per-atom anisotropic displacement parameters, empty when none were provided
+    adps: Vec<adp::Adp>,
+    // Per-atom B-factors (Å²), empty unless loaded from a PDB file with them
+    b_factors: Vec<f32>,
+    // Chain code of each protein alpha carbon, `NOT_ALPHA_CARBON` for other
+    // atoms; empty unless loaded from a PDB file with a backbone
+    ca_chains: Vec<u32>,
+    // Whether each atom is in the selection; empty when nothing is selected
+    selected: Vec<bool>,
+    // Keep selected atoms visible at top LOD; see `set_force_selection_high_lod`
+    force_selection_high_lod: bool,
+    // `SecondaryStructure` code of each alpha carbon, coil for other atoms;
+    // empty until `assign_secondary_structure`
+    secondary_structure: Vec<u8>,
+    // Per-atom LOD importance, empty when every atom is 0; see `set_atom_importance`
+    importance: Vec<f32>,
+    // `k` in `set_importance_weight`
+    importance_weight: f32,
+    // Index of each atom in the dataset it was cropped from (`extract_visible`,
+    // `deduplicate`); empty means atoms are still in loaded order
+    original_indices: Vec<u32>,
+
+    // Crystal lattice, if any
+    unit_cell: Option<UnitCell>,
+    use_pbc: bool,
+
+    // Storage indices of each element's atoms, rebuilt whenever indices change;
+    // culling walks only the lists of elements not in `hidden_elements`
+    element_atoms: BTreeMap<u32, Vec<u32>>,
+    hidden_elements: HashSet<u32>,
+
+    // Per-element display overrides layered over the element table
+    element_radius_overrides: HashMap<u32, f32>,
+    element_color_overrides: HashMap<u32, [f32; 3]>,
+    // Element ids folded into others for display; see `set_element_remap`
+    element_remap: HashMap<u32, u32>,
+    // LOD threshold multipliers by display element; see `set_element_lod_bias`
+    element_lod_bias: HashMap<u32, f32>,
+
+    // Model matrix the camera is moved out of before culling (`set_world_transform`)
+    world_transform: Option<world::WorldTransform>,
+
+    mode: SystemMode,
+
+    // Console verbosity, one of the LOG_* levels
+    log_level: u32,
+
+    // File loading options
+    altloc_policy: AltLocPolicy,
+    input_scale: f32,
+    // Keep parsed coordinates as f64; see `set_high_precision`
+    high_precision: bool,
+    // f64 position of each atom, empty unless loaded in high precision
+    precise_positions: Vec<[f64; 3]>,
+    // `take_load_warnings` summaries from the last file load
+    load_warnings: Vec<String>,
+    input_handedness: Handedness,
+    // Chains PDB loads keep; empty keeps all
+    chain_filter: Vec<char>,
+    streaming_load: Option<StreamingLoad>,
 }
 
 #[wasm_bindgen]
@@ -69,34 +821,213 @@ impl MolecularSystem {
     pub fn new() -> Self {
         MolecularSystem {
             all_atoms: Vec::new(),
+            loaded_from_file: false,
             total_atom_count: 0,
             grid_size: 1.0,
             time: 0.0,
             animation_speed: 1.0,
+            max_animation_speed: DEFAULT_MAX_ANIMATION_SPEED,
+            generator_lattice: Lattice::SimpleCubic,
+            generator_fill_order: FillOrder::Xyz,
+            generator_seed: 0,
+            generator_element_cdf: Vec::new(),
+            generator_random_elements: false,
             current_camera_hash: 0,
+            camera_hash_position_scale: DEFAULT_CAMERA_HASH_POSITION_SCALE,
+            camera_hash_fov_scale: DEFAULT_CAMERA_HASH_FOV_SCALE,
+            last_query_cached: false,
             cached_visible_atoms: Vec::new(),
+            cached_visible_indices: Vec::new(),
+            cached_impostors: Vec::new(),
+            preserve_order: false,
+            opaque_sort: false,
+            frustum_culling: true,
+            frustum_margin: DEFAULT_FRUSTUM_MARGIN,
+            clip_planes: Vec::new(),
+            far_cull_fraction: DEFAULT_FAR_CULL_FRACTION,
+            impostor_distance: 0.0,
+            chunked_culling: None,
+            chunk_grid: None,
+            coarse_grid: OnceCell::new(),
+            explode_factor: 0.0,
+            explode_directions: OnceCell::new(),
+            max_high_lod_atoms: None,
+            visible_atom_cap: None,
+            cache_memory_budget: None,
+            lod_quality: budget::MAX_LOD_QUALITY,
+            lod_levels: DEFAULT_LOD_LEVELS,
+            lod_distance_weights: None,
+            lod_hysteresis: 0.0,
+            point_style: PointStyle::ConstantPixel,
+            point_pixel_size: 2.0,
+            edge_softness: 0.0,
+            frame_budget: None,
+            bounding_spheres: Default::default(),
+            cull_stats: CullStats::default(),
+            timings: Timings::default(),
+            last_cull_params: None,
+            last_cull_eye: None,
+            visible_batches: None,
+            frame_cache: Default::default(),
+            cached_visible_bonds: Vec::new(),
+            cached_visible_bond_pairs: Vec::new(),
+            atom_lods: Vec::new(),
+            lod_changes: Vec::new(),
+            visibility_delta: Vec::new(),
+            bonds: Vec::new(),
+            explicit_bonds: false,
+            bond_cutoff_overrides: HashMap::new(),
+            velocities: Vec::new(),
+            forces: Vec::new(),
+            adps: Vec::new(),
+            b_factors: Vec::new(),
+            ca_chains: Vec::new(),
+            selected: Vec::new(),
+            secondary_structure: Vec::new(),
+            importance: Vec::new(),
+            importance_weight: 1.0,
+            force_selection_high_lod: false,
+            original_indices: Vec::new(),
+            unit_cell: None,
+            use_pbc: false,
+            element_atoms: BTreeMap::new(),
+            hidden_elements: HashSet::new(),
+            element_radius_overrides: HashMap::new(),
+            element_color_overrides: HashMap::new(),
+            element_remap: HashMap::new(),
+            element_lod_bias: HashMap::new(),
+            world_transform: None,
+            mode: SystemMode::Auto,
+            log_level: LOG_ERRORS,
+            altloc_policy: AltLocPolicy::HighestOccupancy,
+            input_scale: 1.0,
+            high_precision: false,
+            precise_positions: Vec::new(),
+            load_warnings: Vec::new(),
+            input_handedness: Handedness::RightHanded,
+            chain_filter: Vec::new(),
+            streaming_load: None,
         }
     }
 
     /// Simulate loading atoms from file - READ ALL ATOMS FIRST
     pub fn load_atoms_from_file(&mut self, count: usize) {
-        log!("Loading {} atoms from file (simulated)...", count);
+        log!(self, LOG_INFO, "Loading {} atoms from file (simulated)...", count);
+        let start = now_ms();
 
         self.total_atom_count = count;
         self.all_atoms.clear();
+        self.loaded_from_file = false;
+        self.unit_cell = None;
+        self.precise_positions.clear();
+        self.bonds.clear();
+        self.explicit_bonds = false;
+        self.velocities.clear();
+        self.forces.clear();
+        self.adps.clear();
+        self.b_factors.clear();
+        self.ca_chains.clear();
+        self.selected.clear();
+        self.secondary_structure.clear();
+        self.importance.clear();
+        self.original_indices.clear();
         self.all_atoms.reserve(count);
 
         // Step 1: READ ALL ATOMS (simulate file reading)
         // Generate simple coordinates - treat this as unknown data source
         self.read_all_atoms_from_source(count);
+        self.indices_changed();
 
         // Step 2: Analyze the complete dataset
         self.analyze_complete_dataset();
 
         // Clear any cached camera-dependent data
+        self.geometry_changed();
+        self.invalidate_camera_cache();
+
+        self.timings.load_ms = elapsed_ms(start);
+        log!(self, LOG_INFO, "Loaded {} atoms from file", self.all_atoms.len());
+    }
+
+    /// Return to the empty state so one instance can be reused across files:
+    /// drops atoms, bonds, velocities, forces, displacement parameters, the unit cell,
+    /// any unfinished streaming load and every camera-dependent cache. Display
+    /// and loading settings (element styles and visibility, LOD and culling
+    /// options, clip planes, mode, log level, alt-loc policy, input scale,
+    /// handedness and precision) are kept.
+    pub fn clear(&mut self) {
+        self.all_atoms.clear();
+        self.loaded_from_file = false;
+        self.unit_cell = None;
+        self.streaming_load = None;
+        self.cull_stats = CullStats::default();
+        self.finish_load();
+        log!(self, LOG_INFO, "Cleared molecular system");
+    }
+
+    // Bring freshly parsed atoms into the right-handed frame
+    fn convert_input_handedness(&self, atoms: &mut [RawAtom]) {
+        if self.input_handedness == Handedness::LeftHanded {
+            for atom in atoms {
+                atom.z = -atom.z;
+            }
+        }
+    }
+
+    /// Common bookkeeping after `all_atoms` has been replaced or rebuilt
+    fn finish_load(&mut self) {
+        self.total_atom_count = self.all_atoms.len();
+        self.precise_positions.clear();
+        self.bonds.clear();
+        self.explicit_bonds = false;
+        self.velocities.clear();
+        self.forces.clear();
+        self.adps.clear();
+        self.b_factors.clear();
+        self.ca_chains.clear();
+        self.selected.clear();
+        self.secondary_structure.clear();
+        self.importance.clear();
+        self.original_indices.clear();
+        self.indices_changed();
+        self.positions_changed();
+    }
+
+    /// Bookkeeping after atoms moved in place (indices and bonds still valid)
+    fn positions_changed(&mut self) {
+        self.analyze_complete_dataset();
+        self.geometry_changed();
         self.invalidate_camera_cache();
+    }
+
+    // Rebuild per-index bookkeeping after atoms were added, removed or reordered
+    fn indices_changed(&mut self) {
+        self.atom_lods.clear();
+        self.element_atoms.clear();
+        for (index, atom) in self.all_atoms.iter().enumerate() {
+            self.element_atoms.entry(atom.element).or_default().push(index as u32);
+        }
+    }
+
+    fn element_hidden(&self, element: u32) -> bool {
+        !self.hidden_elements.is_empty() && self.hidden_elements.contains(&self.display_element(element))
+    }
+
+    // The chunked-culling grid when there is one, else an 8 Å grid built on
+    // first use after atoms move
+    fn coarse_grid(&self) -> &SpatialGrid {
+        match self.chunk_grid.as_ref().filter(|_| self.chunked_culling.is_some()) {
+            Some(grid) => grid,
+            None => self.coarse_grid.get_or_init(|| SpatialGrid::build(&self.all_atoms, COARSE_CELL_SIZE)),
+        }
+    }
 
-        log!("Loaded {} atoms from file", self.all_atoms.len());
+    // Drop caches derived from atom positions
+    fn geometry_changed(&mut self) {
+        self.chunk_grid = None;
+        self.coarse_grid = OnceCell::new();
+        self.explode_directions = OnceCell::new();
+        self.bounding_spheres = Default::default();
     }
 
     /// Simulate reading from an actual file source
@@ -106,44 +1037,88 @@ impl MolecularSystem {
 
         if count <= 2 {
             // Special case: HF molecule
-            self.all_atoms.push(RawAtom { x: 0.0, y: 0.0, z: 0.0, element: 0 }); // H
+            self.all_atoms.push(RawAtom { x: 0.0, y: 0.0, z: 0.0, element: 0, occupancy: 1.0 }); // H
             if count > 1 {
-                self.all_atoms.push(RawAtom { x: 0.92, y: 0.0, z: 0.0, element: 1 }); // F
+                self.all_atoms.push(RawAtom { x: 0.92, y: 0.0, z: 0.0, element: 1, occupancy: 1.0 }); // F
             }
             return;
         }
 
         // For larger counts, generate coordinates (simulating file data)
-        let atoms_per_axis = ((count as f32).powf(1.0/3.0).ceil() as usize).max(1);
+        let basis = self.generator_lattice.basis();
+        let cells = count.div_ceil(basis.len());
+        let atoms_per_axis = ((cells as f32).powf(1.0/3.0).ceil() as usize).max(1);
         let spacing = self.grid_size;
         let offset = -(atoms_per_axis as f32 - 1.0) * spacing * 0.5;
 
-        log!("Reading atoms in {}x{}x{} grid from file...", atoms_per_axis, atoms_per_axis, atoms_per_axis);
+        log!(self, LOG_VERBOSE, "Reading atoms in {}x{}x{} grid from file...", atoms_per_axis, atoms_per_axis, atoms_per_axis);
+
+        let mut rng = SplitMix64::new(self.generator_seed);
 
         // Read ALL atoms - this would be a file read in real implementation
+        let mut cells = self.generator_fill_order.cells(atoms_per_axis);
+        let mut cell = [0; 3];
         for i in 0..count {
-            let x_idx = i % atoms_per_axis;
-            let y_idx = (i / atoms_per_axis) % atoms_per_axis;
-            let z_idx = i / (atoms_per_axis * atoms_per_axis);
-
-            let x = offset + x_idx as f32 * spacing;
-            let y = offset + y_idx as f32 * spacing;
-            let z = offset + z_idx as f32 * spacing;
-
-            let element = match i % 4 {
-                0 => 0, // H
-                1 => 1, // F
-                2 => 2, // O
-                _ => 3, // N
+            if i % basis.len() == 0 {
+                cell = cells.next().unwrap_or(cell);
+            }
+            let site = basis[i % basis.len()];
+            let [x_idx, y_idx, z_idx] = cell;
+
+            let x = offset + (x_idx as f32 + site.0) * spacing;
+            let y = offset + (y_idx as f32 + site.1) * spacing;
+            let z = offset + (z_idx as f32 + site.2) * spacing;
+
+            let element = if !self.generator_element_cdf.is_empty() {
+                self.sample_generator_element(&mut rng)
+            } else if self.generator_random_elements {
+                (rng.next_u64() % 4) as u32
+            } else {
+                match i % 4 {
+                    0 => 0, // H
+                    1 => 1, // F
+                    2 => 2, // O
+                    _ => 3, // N
+                }
             };
 
-            self.all_atoms.push(RawAtom { x, y, z, element });
+            self.all_atoms.push(RawAtom { x, y, z, element, occupancy: 1.0 });
+        }
+
+        // The offset above centers a full cube; a partial last layer (count not a
+        // perfect cube) or an off-corner lattice site leaves the atoms off-center,
+        // so recenter on their actual bounding box
+        let (min, max) = self.atom_extent();
+        let center = scale(add(min, max), 0.5);
+        for atom in &mut self.all_atoms {
+            (atom.x, atom.y, atom.z) = sub((atom.x, atom.y, atom.z), center);
+        }
+    }
+
+    fn sample_generator_element(&self, rng: &mut SplitMix64) -> u32 {
+        let cdf = &self.generator_element_cdf;
+        let total = cdf[cdf.len() - 1];
+        let pick = rng.next_f32() * total;
+        cdf.partition_point(|&c| c <= pick).min(cdf.len() - 1) as u32
+    }
+
+    // Axis-aligned min/max corners of all atoms (zeros when empty)
+    fn atom_extent(&self) -> (math::Vec3, math::Vec3) {
+        if self.all_atoms.is_empty() {
+            return ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+        }
+        let mut min = (f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for atom in &self.all_atoms {
+            min = (min.0.min(atom.x), min.1.min(atom.y), min.2.min(atom.z));
+            max = (max.0.max(atom.x), max.1.max(atom.y), max.2.max(atom.z));
         }
+        (min, max)
     }
 
     /// Analyze the complete dataset to understand atom distribution
     fn analyze_complete_dataset(&self) {
-        log!("Analyzing complete dataset of {} atoms...", self.all_atoms.len());
+        log!(self, LOG_VERBOSE, "Analyzing complete dataset of {} atoms...", self.all_atoms.len());
 
         if self.all_atoms.is_empty() {
             return;
@@ -171,170 +1146,1120 @@ impl MolecularSystem {
         let bounds_y = max_y - min_y;
         let bounds_z = max_z - min_z;
 
-        log!("Dataset bounds: X:{:.2}-{:.2}, Y:{:.2}-{:.2}, Z:{:.2}-{:.2}",
+        log!(self, LOG_VERBOSE, "Dataset bounds: X:{:.2}-{:.2}, Y:{:.2}-{:.2}, Z:{:.2}-{:.2}",
              min_x, max_x, min_y, max_y, min_z, max_z);
-        log!("Dataset size: {:.2} x {:.2} x {:.2}", bounds_x, bounds_y, bounds_z);
+        log!(self, LOG_VERBOSE, "Dataset size: {:.2} x {:.2} x {:.2}", bounds_x, bounds_y, bounds_z);
     }
 
     /// Get atoms visible from current camera - RECALCULATE ON EVERY CAMERA CHANGE
-    pub fn get_visible_atoms_for_camera(&mut self, camera: &Camera, fov: f32, aspect: f32, near: f32, far: f32) -> Vec<AtomData> {
-        // Calculate camera hash to detect changes
-        let camera_hash = self.calculate_camera_hash(camera, fov, aspect, near, far);
+    pub fn get_visible_atoms(&mut self, camera: &Camera, proj: &Projection) -> Vec<AtomData> {
+        self.refresh_visibility(camera, proj);
+        self.cached_visible_atoms.clone()
+    }
 
-        // If camera changed, MUST recalculate everything
-        if camera_hash != self.current_camera_hash {
-            log!("Camera changed - recalculating LOD for ALL {} atoms", self.all_atoms.len());
+    /// Get bonds with at least one endpoint visible from the current camera.
+    /// Shares the camera cache with `get_visible_atoms`.
+    pub fn get_visible_bonds(&mut self, camera: &Camera, proj: &Projection) -> Vec<BondData> {
+        self.refresh_visibility(camera, proj);
+        self.cached_visible_bonds.clone()
+    }
 
-            self.current_camera_hash = camera_hash;
-            self.cached_visible_atoms.clear();
+    /// Scalar-parameter form of `get_visible_atoms`
+    pub fn get_visible_atoms_for_camera(&mut self, camera: &Camera, fov: f32, aspect: f32, near: f32, far: f32) -> Vec<AtomData> {
+        self.get_visible_atoms(camera, &Projection::new(fov, aspect, near, far))
+    }
 
-            // Recalculate visibility and LOD for ALL atoms from new camera position
-            self.recalculate_visibility_for_camera(camera, fov, aspect, near, far);
+    /// Cull only the atoms at `indices` (e.g. a ligand or selection drawn in its
+    /// own pass), returning survivors in the order given. Uses the same tests and
+    /// LOD rules as `get_visible_atoms`, including pinned selections
+    /// (`set_force_selection_high_lod`), LOD hysteresis against the last full
+    /// cull and the high-LOD budget applied within the subset. Hidden elements
+    /// and out-of-range indices are skipped.
+    ///
+    /// This bypasses the camera cache: it neither reads nor populates the cached
+    /// visible set, so `get_visible_atoms` and the per-visible-atom accessors are
+    /// unaffected.
+    pub fn get_visible_atoms_for_indices(
+        &self,
+        camera: &Camera,
+        fov: f32,
+        aspect: f32,
+        near: f32,
+        far: f32,
+        indices: &[u32],
+    ) -> Vec<AtomData> {
+        let mut visible = Vec::new();
+        self.cull_indices_into(camera, &Projection::new(fov, aspect, near, far), indices, &mut visible);
+        visible
+    }
+
+    /// Quick upper bound on how many atoms `get_visible_atoms` would return,
+    /// for choosing LOD settings before the real cull: the atom count of every
+    /// grid cell that passes the chunk-level distance and view-cone test, with
+    /// no per-atom tests and nothing cached. It over-counts the atoms of
+    /// partly visible cells at the frustum and distance boundaries, and counts
+    /// hidden elements. Uses the chunked-culling grid when there is one,
+    /// otherwise an 8 Å grid built on the first call after atoms move.
+    pub fn estimate_visible_count(&self, camera: &Camera, fov: f32, aspect: f32, near: f32, far: f32) -> usize {
+        if self.all_atoms.is_empty() {
+            return 0;
         }
+        let mut frame = self.cull_frame(&self.local_camera(camera), &Projection::new(fov, aspect, near, far));
+        // Impostors are not atoms, so cells only in the impostor band don't count
+        frame.impostor_distance = 0.0;
+        let grid = self.coarse_grid();
+        let bounds = frame.chunk_bounds(grid.cell_size());
+        let mut count = 0;
+        grid.for_each_cell(|corner, entries| {
+            if frame.test_chunk(&bounds, corner).is_none() {
+                count += entries.len();
+            }
+        });
+        count
+    }
 
-        self.cached_visible_atoms.clone()
+    /// Scalar-parameter form of `get_visible_bonds`
+    pub fn get_visible_bonds_for_camera(&mut self, camera: &Camera, fov: f32, aspect: f32, near: f32, far: f32) -> Vec<BondData> {
+        self.get_visible_bonds(camera, &Projection::new(fov, aspect, near, far))
     }
 
-    /// Recalculate atom visibility and LOD from current camera position
-    fn recalculate_visibility_for_camera(&mut self, camera: &Camera, fov: f32, _aspect: f32, _near: f32, far: f32) {
-        let cam_pos = (camera.x, camera.y, camera.z);
-        let cam_target = (camera.target_x, camera.target_y, camera.target_z);
+    /// Visible atoms and bonds for one frame in a single buffer, sharing the
+    /// camera cache with `get_visible_atoms` and `get_visible_bonds`:
+    ///
+    /// - `[0]` visible atom count `A`, `[1]` visible bond count `B`
+    /// - from offset 2, `A` atom records of 6 floats:
+    ///   `[x, y, z, element, radius, lod_level]`
+    /// - from offset `2 + 6 * A`, `B` bond records of 7 floats:
+    ///   `[start_x, start_y, start_z, end_x, end_y, end_z, lod_level]`
+    ///
+    /// Counts, elements and LODs are stored as floats, exact below 2^24.
+    pub fn get_render_payload(&mut self, camera: &Camera, fov: f32, aspect: f32, near: f32, far: f32) -> Vec<f32> {
+        self.refresh_visibility(camera, &Projection::new(fov, aspect, near, far));
+
+        let atoms = &self.cached_visible_atoms;
+        let bonds = &self.cached_visible_bonds;
+        let mut payload = Vec::with_capacity(2 + atoms.len() * PACKED_ATOM_FLOATS + bonds.len() * 7);
+        payload.extend_from_slice(&[atoms.len() as f32, bonds.len() as f32]);
+        for atom in atoms {
+            payload.extend_from_slice(&atom.packed());
+        }
+        for bond in bonds {
+            payload.extend_from_slice(&[
+                bond.start_x, bond.start_y, bond.start_z,
+                bond.end_x, bond.end_y, bond.end_z,
+                bond.lod_level as f32,
+            ]);
+        }
+        payload
+    }
 
-        // Calculate aggressive distance thresholds based on total atom count
-        let aggression = self.calculate_aggression_factor();
-        let max_distance = far * 0.8;
-        let point_threshold = 50.0 * aggression;
-        let low_poly_threshold = 20.0 * aggression;
-        let medium_poly_threshold = 10.0 * aggression;
-
-        // Calculate view direction for frustum culling
-        let view_dir = (
-            cam_target.0 - cam_pos.0,
-            cam_target.1 - cam_pos.1,
-            cam_target.2 - cam_pos.2,
-        );
-        let view_length = (view_dir.0*view_dir.0 + view_dir.1*view_dir.1 + view_dir.2*view_dir.2).sqrt();
-        let view_normalized = if view_length > 0.0 {
-            (view_dir.0/view_length, view_dir.1/view_length, view_dir.2/view_length)
-        } else {
-            (0.0, 0.0, -1.0)
-        };
+    /// The parts of the cached visible set that only change on a new cull, 5
+    /// floats per atom in visible-list order: `[x, y, z, element, lod_level]`.
+    /// Upload once per camera change and pair with `get_visible_dynamic_radii`.
+    pub fn get_visible_static(&self) -> Vec<f32> {
+        let mut out = Vec::with_capacity(self.cached_visible_atoms.len() * 5);
+        for atom in &self.cached_visible_atoms {
+            out.extend_from_slice(&[atom.x, atom.y, atom.z, atom.element as f32, atom.lod_level as f32]);
+        }
+        out
+    }
 
-        let mut visible_atoms = Vec::new();
+    /// Animated radius of each atom of the cached visible set, in visible-list
+    /// order. `update` refreshes these in place without culling again, so while
+    /// the camera holds still only this buffer needs re-uploading per frame.
+    pub fn get_visible_dynamic_radii(&self) -> Vec<f32> {
+        self.cached_visible_atoms.iter().map(|atom| atom.radius).collect()
+    }
 
-        // ITERATE THROUGH ALL ATOMS - essential for rotation handling
-        for atom in &self.all_atoms {
-            let dx = atom.x - cam_pos.0;
-            let dy = atom.y - cam_pos.1;
-            let dz = atom.z - cam_pos.2;
-            let distance = (dx*dx + dy*dy + dz*dz).sqrt();
-
-            // Natural distance culling based on camera far plane
-            if distance > max_distance {
-                continue;
-            }
+    fn refresh_visibility(&mut self, camera: &Camera, proj: &Projection) {
+        let camera = &self.local_camera(camera);
 
-            // Natural frustum culling
-            let to_atom_length = distance;
-            if to_atom_length > 0.0 {
-                let to_atom_normalized = (dx/to_atom_length, dy/to_atom_length, dz/to_atom_length);
-                let dot_product = view_normalized.0 * to_atom_normalized.0 +
-                                view_normalized.1 * to_atom_normalized.1 +
-                                view_normalized.2 * to_atom_normalized.2;
-
-                // Cull atoms outside expanded view frustum
-                let fov_threshold = (fov * 0.6).cos(); // Slightly wider than actual FOV
-                if dot_product < fov_threshold {
-                    continue;
-                }
-            }
+        // Calculate camera hash to detect changes
+        let camera_hash = self.calculate_camera_hash(camera, proj);
 
-            // Calculate LOD based on distance and aggression - this is the MAIN performance control
-            let lod_level = if distance > point_threshold {
-                0 // Point representation - very cheap to render
-            } else if distance > low_poly_threshold {
-                1 // Low-poly sphere - moderate cost
-            } else if distance > medium_poly_threshold {
-                2 // Medium-poly sphere - higher cost
-            } else {
-                3 // High-poly sphere - expensive, but few atoms will be this close
-            };
+        // If camera changed, MUST recalculate everything
+        self.last_query_cached = camera_hash == self.current_camera_hash;
+        if !self.last_query_cached {
+            log!(self, LOG_VERBOSE, "Camera changed - recalculating LOD for ALL {} atoms", self.all_atoms.len());
 
-            // Element-specific radius
-            let base_radius = match atom.element {
-                0 => 0.25, // H
-                1 => 0.35, // F
-                2 => 0.3,  // O
-                _ => 0.28, // N
-            };
+            self.current_camera_hash = camera_hash;
+            self.cached_visible_atoms.clear();
 
-            // Animate radius slightly
-            let animated_radius = base_radius + 0.02 * (self.time + atom.x + atom.y + atom.z).sin();
+            // A camera seen recently needs no cull
+            if self.restore_cached_frame(camera_hash, camera, proj) {
+                self.last_query_cached = true;
+                return;
+            }
 
-            visible_atoms.push(AtomData {
-                x: atom.x,
-                y: atom.y,
-                z: atom.z,
-                element: atom.element,
-                radius: animated_radius,
-                lod_level,
-            });
+            // Recalculate visibility and LOD for ALL atoms from new camera position
+            self.recalculate_visibility_for_camera(camera, proj);
+            self.remember_frame(camera_hash);
         }
+    }
 
-        self.cached_visible_atoms = visible_atoms;
+    fn cull_frame(&self, camera: &Camera, proj: &Projection) -> CullFrame {
+        let cam_pos = (camera.x, camera.y, camera.z);
+        let cam_target = (camera.target_x, camera.target_y, camera.target_z);
 
-        log!("Selected {} visible atoms from {} total (aggression: {:.1}x) - LOD naturally applied",
-             self.cached_visible_atoms.len(), self.all_atoms.len(), aggression);
+        // Calculate view direction for frustum culling; a camera sitting on its
+        // target (or with non-finite coordinates) looks down -Z
+        let view_dir = normalize(sub(cam_target, cam_pos))
+            .filter(|v| v.0.is_finite() && v.1.is_finite() && v.2.is_finite())
+            .unwrap_or((0.0, 0.0, -1.0));
+
+        CullFrame {
+            cam_pos,
+            view_dir,
+            max_distance: proj.far * self.far_cull_fraction,
+            impostor_distance: self.impostor_distance,
+            fov_threshold: (proj.fov * 0.5 * (1.0 + self.frustum_margin)).min(std::f32::consts::PI).cos(),
+            frustum_culling: self.frustum_culling,
+            clip_planes: self.clip_planes.clone(),
+            lod_weights: self.lod_distance_weights,
+            lod: self.lod_thresholds(),
+        }
     }
 
-    fn calculate_aggression_factor(&self) -> f32 {
-        // More aggressive culling for larger atom counts
-        match self.total_atom_count {
-            0..=1_000 => 1.0,
-            1_001..=10_000 => 2.0,
-            10_001..=100_000 => 4.0,
-            100_001..=1_000_000 => 8.0,
-            1_000_001..=10_000_000 => 16.0,
-            _ => 32.0,
+    // `frame.test` for atom `index` at its exploded position `p`; with an f64
+    // copy (`set_high_precision`) the camera offset is taken in f64
+    fn cull_atom(&self, frame: &CullFrame, index: usize, p: math::Vec3) -> AtomCull {
+        let Some(precise) = self.precise_positions.get(index) else { return frame.test(p) };
+        let (ox, oy, oz) = self.explode_offset(index);
+        let (cx, cy, cz) = frame.cam_pos;
+        let to_atom = (
+            (precise[0] - cx as f64) as f32 + ox,
+            (precise[1] - cy as f64) as f32 + oy,
+            (precise[2] - cz as f64) as f32 + oz,
+        );
+        frame.test_offset(p, to_atom)
+    }
+
+    // Render record for visible atom `index`, where the exploded view puts it
+    fn visible_atom_data(&self, index: usize, lod_level: u32) -> AtomData {
+        let atom = &self.all_atoms[index];
+        let (x, y, z) = self.exploded_position(index);
+        AtomData {
+            x,
+            y,
+            z,
+            element: self.display_element(atom.element),
+            radius: self.animated_radius(atom),
+            lod_level,
         }
     }
 
-    fn calculate_camera_hash(&self, camera: &Camera, fov: f32, _aspect: f32, _near: f32, _far: f32) -> u64 {
-        // Simple hash to detect camera changes
-        let mut hash = 0u64;
-        hash ^= (camera.x * 1000.0) as u64;
-        hash ^= ((camera.y * 1000.0) as u64) << 10;
-        hash ^= ((camera.z * 1000.0) as u64) << 20;
-        hash ^= ((camera.target_x * 1000.0) as u64) << 30;
-        hash ^= ((camera.target_y * 1000.0) as u64) << 40;
-        hash ^= ((camera.target_z * 1000.0) as u64) << 50;
-        hash ^= (fov * 100.0) as u64;
-        hash
+    // Element-specific radius, animated slightly with `time`
+    fn animated_radius(&self, atom: &RawAtom) -> f32 {
+        self.element_radius(self.display_element(atom.element)) + 0.02 * (self.time + atom.x + atom.y + atom.z).sin()
     }
 
-    fn invalidate_camera_cache(&mut self) {
-        self.current_camera_hash = 0;
-        self.cached_visible_atoms.clear();
+    // Re-derive the cached visible set's radii for the current `time`
+    fn refresh_animated_radii(&mut self) {
+        let radii: Vec<f32> = self.cached_visible_indices
+            .iter()
+            .map(|&index| self.animated_radius(&self.all_atoms[index as usize]))
+            .collect();
+        for (atom, radius) in self.cached_visible_atoms.iter_mut().zip(radii) {
+            atom.radius = radius;
+        }
+        self.visible_batches = None;
     }
 
-    /// Get raw atom data for WebGPU compute shaders
-    pub fn get_all_atom_positions(&self) -> Vec<f32> {
-        let mut positions = Vec::new();
-        positions.reserve(self.all_atoms.len() * 4); // x, y, z, element
+    /// Recalculate atom visibility and LOD from current camera position
+    fn recalculate_visibility_for_camera(&mut self, camera: &Camera, proj: &Projection) {
+        let start = now_ms();
+        self.last_cull_params = Some(*proj);
+        self.last_cull_eye = Some((camera.x, camera.y, camera.z));
+        self.visible_batches = None;
 
-        for atom in &self.all_atoms {
-            positions.push(atom.x);
-            positions.push(atom.y);
-            positions.push(atom.z);
-            positions.push(atom.element as f32);
+        if self.all_atoms.is_empty() {
+            self.cull_stats = CullStats::default();
+            self.cached_visible_atoms.clear();
+            self.cached_visible_indices.clear();
+            self.cached_visible_bonds.clear();
+            self.cached_visible_bond_pairs.clear();
+            self.cached_impostors.clear();
+            self.atom_lods.clear();
+            self.lod_changes.clear();
+            self.visibility_delta.clear();
+            self.timings.cull_ms = elapsed_ms(start);
+            return;
+        }
+
+        // Calculate aggressive distance thresholds based on total atom count
+        let aggression = self.calculate_aggression_factor();
+        let frame = self.cull_frame(camera, proj);
+
+        if let Some(chunk_size) = self.chunked_culling {
+            if self.chunk_grid.is_none() {
+                self.chunk_grid = Some(SpatialGrid::build(&self.all_atoms, chunk_size));
+            }
+        }
+
+        let mut visible_atoms = Vec::new();
+        let mut visible_indices = Vec::new();
+        let mut stats = CullStats { total: self.all_atoms.len(), ..CullStats::default() };
+        let mut atom_lods = vec![LOD_HIDDEN; self.all_atoms.len()];
+        let mut visible_distances = Vec::new();
+        let impostor_grid = (frame.impostor_distance > frame.max_distance).then(|| self.coarse_grid());
+        let mut impostors = impostors::ImpostorAccumulator::default();
+
+        let mut test_atom = |index: usize| {
+            let atom = &self.all_atoms[index];
+            stats.tested += 1;
+            let pinned = self.pinned(index);
+            let p = self.exploded_position(index);
+            let distance = match self.cull_atom(&frame, index, p) {
+                AtomCull::Visible(distance) => distance,
+                _ if pinned => 0.0,
+                AtomCull::Impostor => {
+                    stats.distance_culled += 1;
+                    if let Some(grid) = impostor_grid {
+                        impostors.add(grid.cell_id(p), p, self.element_radius(self.display_element(atom.element)));
+                    }
+                    return;
+                }
+                AtomCull::Distance => {
+                    stats.distance_culled += 1;
+                    return;
+                }
+                AtomCull::Frustum => {
+                    stats.frustum_culled += 1;
+                    return;
+                }
+            };
+
+            // Calculate LOD based on distance and aggression - this is the MAIN performance control
+            // Pinned atoms rank nearest so the cap and LOD budget keep them;
+            // important atoms rank as if nearer
+            let distance = self.lod_distance(index, distance);
+            let (lod_level, distance) = match self.atom_lods.get(index) {
+                _ if pinned => (frame.lod.top(), 0.0),
+                Some(&previous) => (frame.lod.sticky_level(distance, previous, self.lod_hysteresis), distance),
+                None => (frame.lod.level(distance), distance),
+            };
+
+            atom_lods[index] = lod_level as u8;
+            visible_distances.push(distance);
+            visible_indices.push(index as u32);
+            visible_atoms.push(self.visible_atom_data(index, lod_level));
+        };
+
+        let mut chunk_distance_culled = 0;
+        let mut chunk_frustum_culled = 0;
+        // Chunks bound the stored positions, not exploded ones
+        let chunk_grid = self.chunk_grid.as_ref().filter(|_| self.chunked_culling.is_some() && self.explode_factor == 0.0);
+        match chunk_grid {
+            // Coarse pass: skip whole chunks whose bounding sphere lies entirely
+            // beyond the distance limit or outside the view cone, then run the
+            // per-atom test on the rest. The chunk tests are conservative, so the
+            // visible set matches the brute-force path.
+            Some(grid) => {
+                let bounds = frame.chunk_bounds(grid.cell_size());
+                let pin_selection = self.force_selection_high_lod && !self.selected.is_empty();
+                grid.for_each_cell(|corner, entries| {
+                    let shown = |&&index: &&u32| !self.element_hidden(self.all_atoms[index as usize].element);
+                    let Some(cull) = frame.test_chunk(&bounds, corner) else {
+                        entries.iter().filter(shown).for_each(|&index| test_atom(index as usize));
+                        return;
+                    };
+                    // Selected atoms in a rejected chunk are still kept
+                    let mut culled = entries.len();
+                    if pin_selection {
+                        for &index in entries.iter().filter(shown).filter(|&&index| self.selected[index as usize]) {
+                            test_atom(index as usize);
+                            culled -= 1;
+                        }
+                    }
+                    match cull {
+                        AtomCull::Distance => chunk_distance_culled += culled,
+                        _ => chunk_frustum_culled += culled,
+                    }
+                });
+            }
+            // ITERATE THROUGH ALL ATOMS - essential for rotation handling
+            None if self.hidden_elements.is_empty() => (0..self.all_atoms.len()).for_each(&mut test_atom),
+            // Only the shown elements' atoms, one element at a time
+            None => {
+                for (element, atoms) in &self.element_atoms {
+                    if !self.element_hidden(*element) {
+                        atoms.iter().for_each(|&index| test_atom(index as usize));
+                    }
+                }
+            }
+        }
+        stats.distance_culled += chunk_distance_culled;
+        stats.frustum_culled += chunk_frustum_culled;
+
+        // Over the cap, the farthest visible atoms go, whatever order they were found in
+        let memory_cap = self.memory_atom_cap();
+        let cap = match (self.visible_atom_cap, memory_cap) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(cap) = cap.filter(|&cap| visible_atoms.len() > cap) {
+            stats.distance_culled += visible_atoms.len() - cap;
+            stats.memory_culled = visible_atoms.len().min(self.visible_atom_cap.unwrap_or(usize::MAX)) - cap;
+            // Compact the survivors to the front, keeping their order
+            let mut kept = 0;
+            for (i, keep) in nearest_mask(cap, &visible_distances).into_iter().enumerate() {
+                if keep {
+                    visible_atoms.swap(kept, i);
+                    visible_indices.swap(kept, i);
+                    visible_distances.swap(kept, i);
+                    kept += 1;
+                } else {
+                    atom_lods[visible_indices[i] as usize] = LOD_HIDDEN;
+                }
+            }
+            visible_atoms.truncate(kept);
+            visible_indices.truncate(kept);
+            visible_distances.truncate(kept);
+        }
+
+        if let Some(budget) = self.max_high_lod_atoms {
+            apply_lod_budget(budget, frame.lod.top(), &visible_distances, &mut visible_atoms);
+            for (atom, &index) in visible_atoms.iter().zip(&visible_indices) {
+                atom_lods[index as usize] = atom.lod_level as u8;
+            }
+        }
+
+        stats.visible = visible_atoms.len();
+        self.cull_stats = stats;
+        self.cached_impostors = impostors.finish();
+        self.cached_visible_atoms = visible_atoms;
+        self.cached_visible_indices = visible_indices;
+        if self.preserve_order {
+            self.restore_storage_order();
+        } else if self.opaque_sort {
+            self.sort_front_to_back(frame.cam_pos);
+        }
+
+        self.adopt_atom_lods(atom_lods);
+        let atom_lods = &self.atom_lods;
+
+        // Bonds survive if either end survived the atom cull
+        self.cached_visible_bond_pairs = self.bond_pairs()
+            .iter()
+            .copied()
+            .filter(|&(a, b)| atom_lods[a as usize] != LOD_HIDDEN || atom_lods[b as usize] != LOD_HIDDEN)
+            // A bond to a hidden element would dangle
+            .filter(|&(a, b)| {
+                !self.element_hidden(self.all_atoms[a as usize].element)
+                    && !self.element_hidden(self.all_atoms[b as usize].element)
+            })
+            .collect();
+        self.cached_visible_bonds = self.cached_visible_bond_pairs
+            .iter()
+            .map(|&(a, b)| {
+                let mut bond = self.bond_between(a as usize, b as usize);
+                // Both ends are in one bonded group, so they move together
+                let (dx, dy, dz) = self.explode_offset(a as usize);
+                bond.start_x += dx;
+                bond.start_y += dy;
+                bond.start_z += dz;
+                bond.end_x += dx;
+                bond.end_y += dy;
+                bond.end_z += dz;
+                // Bond LOD mirrors atom LOD, measured at the midpoint
+                let mid = (
+                    (bond.start_x + bond.end_x) * 0.5,
+                    (bond.start_y + bond.end_y) * 0.5,
+                    (bond.start_z + bond.end_z) * 0.5,
+                );
+                bond.lod_level = frame.lod.level(length(sub(mid, frame.cam_pos)));
+                bond
+            })
+            .collect();
+
+        self.timings.cull_ms = elapsed_ms(start);
+        log!(self, LOG_VERBOSE, "Selected {} visible atoms from {} total (aggression: {:.1}x) - LOD naturally applied",
+             self.cached_visible_atoms.len(), self.all_atoms.len(), aggression);
+    }
+
+    /// Call `callback(x, y, z, element, radius, lod_level, index)` for each atom of
+    /// the cached visible set, in visible-list order, stopping at the first throw.
+    ///
+    /// Avoids cloning the whole `Vec<AtomData>` but pays one JS boundary crossing
+    /// per atom, so it suits small visible sets or consumers that can't take a
+    /// typed array. For large scenes prefer the bulk `Vec` accessors, which cross
+    /// the boundary once.
+    pub fn for_each_visible_atom(&self, callback: &js_sys::Function) -> Result<(), JsValue> {
+        let this = JsValue::NULL;
+        for (atom, &index) in self.cached_visible_atoms.iter().zip(&self.cached_visible_indices) {
+            callback.call7(
+                &this,
+                &JsValue::from(atom.x),
+                &JsValue::from(atom.y),
+                &JsValue::from(atom.z),
+                &JsValue::from(atom.element),
+                &JsValue::from(atom.radius),
+                &JsValue::from(atom.lod_level),
+                &JsValue::from(index),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Pixel position `[x, y, depth]` of atom `index` (storage order), for
+    /// anchoring HTML labels. Origin is the viewport's top-left corner with y
+    /// pointing down, as in CSS; `depth` is the distance in front of the eye
+    /// along the view axis. Same projection as `get_nearest_atoms_to_point`,
+    /// with `fov` vertical in radians. Points outside the viewport are still
+    /// returned; `None` if the atom is behind the camera or the index is invalid.
+    pub fn project_atom(
+        &self,
+        index: usize,
+        camera: &Camera,
+        fov: f32,
+        aspect: f32,
+        viewport_w: f32,
+        viewport_h: f32,
+    ) -> Option<Vec<f32>> {
+        let atom = self.all_atoms.get(index)?;
+        let camera = self.local_camera(camera);
+        let (ndc_x, ndc_y, depth) = camera.project_ndc((atom.x, atom.y, atom.z), fov, aspect)?;
+        Some(vec![
+            (ndc_x + 1.0) * 0.5 * viewport_w,
+            (1.0 - ndc_y) * 0.5 * viewport_h,
+            depth,
+        ])
+    }
+
+    /// Storage indices of up to `k` cached visible atoms whose screen projections are
+    /// nearest the NDC point `(ndc_x, ndc_y)`, closest first. For hover labels.
+    pub fn get_nearest_atoms_to_point(&self, camera: &Camera, fov: f32, aspect: f32, ndc_x: f32, ndc_y: f32, k: usize) -> Vec<u32> {
+        let camera = self.local_camera(camera);
+        let mut candidates: Vec<(f32, u32)> = self.cached_visible_atoms.iter()
+            .zip(&self.cached_visible_indices)
+            .filter_map(|(atom, &index)| {
+                let (sx, sy, _) = camera.project_ndc((atom.x, atom.y, atom.z), fov, aspect)?;
+                Some(((sx - ndc_x).powi(2) + (sy - ndc_y).powi(2), index))
+            })
+            .collect();
+
+        let k = k.min(candidates.len());
+        if k == 0 {
+            return Vec::new();
+        }
+        // Partial sort: only the k nearest need ordering
+        candidates.select_nth_unstable_by(k - 1, |a, b| a.0.total_cmp(&b.0));
+        candidates.truncate(k);
+        candidates.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        candidates.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Index of each atom in the originally loaded dataset, in storage order:
+    /// after `extract_visible`, `deduplicate`, `shuffle_storage` or
+    /// `apply_symmetry_operators` (including chains of them) entry `i` is the
+    /// loaded atom that atom `i` came from - for a symmetry copy, its source
+    /// atom - for looking up metadata kept outside this system. Identity until
+    /// atoms are cropped, copied or reordered; loading or generating atoms
+    /// starts a new dataset.
+    pub fn get_original_indices(&self) -> Vec<u32> {
+        (0..self.all_atoms.len()).map(|i| self.original_index(i)).collect()
+    }
+
+    /// Fingerprint of the atom data - every position and element, in storage
+    /// order - for skipping GPU re-uploads when a reload produced identical
+    /// atoms, or spotting unintended changes. Bit-exact (any coordinate change
+    /// changes it, `-0.0` differs from `0.0`) and ignores view state, styling
+    /// and bonds. 64-bit FNV-1a, so the value is stable across runs, builds and
+    /// platforms; a BigInt in JS.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        for atom in &self.all_atoms {
+            for word in [atom.x.to_bits(), atom.y.to_bits(), atom.z.to_bits(), atom.element] {
+                for byte in word.to_le_bytes() {
+                    hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+                }
+            }
+        }
+        hash
+    }
+
+    fn original_index(&self, i: usize) -> u32 {
+        self.original_indices.get(i).copied().unwrap_or(i as u32)
+    }
+
+    /// Freeze the cached visible set into a standalone system holding just those
+    /// atoms (storage order kept), bonds between them, and the element styling:
+    /// radii, colors, remaps, visibility and LOD biases, so the extracted
+    /// system's own culls rank its atoms by the same `lod_distance` rules.
+    /// Atoms of hidden elements are left out.
+    pub fn extract_visible(&self) -> MolecularSystem {
+        let mut extracted = MolecularSystem::new();
+        extracted.log_level = self.log_level;
+        extracted.mode = self.mode;
+        extracted.loaded_from_file = self.loaded_from_file;
+        let mut remap = vec![u32::MAX; self.all_atoms.len()];
+        let mut indices: Vec<u32> = self.cached_visible_indices
+            .iter()
+            .copied()
+            .filter(|&i| !self.element_hidden(self.all_atoms[i as usize].element))
+            .collect();
+        indices.sort_unstable();
+
+        for (new_index, &index) in indices.iter().enumerate() {
+            remap[index as usize] = new_index as u32;
+            extracted.all_atoms.push(self.all_atoms[index as usize]);
+        }
+        extracted.finish_load();
+
+        extracted.bonds = self.bonds.iter()
+            .map(|&(a, b)| (remap[a as usize], remap[b as usize]))
+            .filter(|&(a, b)| a != u32::MAX && b != u32::MAX)
+            .collect();
+        extracted.explicit_bonds = self.explicit_bonds;
+        if !self.precise_positions.is_empty() {
+            extracted.precise_positions = indices.iter().map(|&i| self.precise_positions[i as usize]).collect();
+        }
+        if !self.velocities.is_empty() {
+            extracted.velocities = indices.iter().map(|&i| self.velocities[i as usize]).collect();
+        }
+        if !self.forces.is_empty() {
+            extracted.forces = indices.iter().map(|&i| self.forces[i as usize]).collect();
+        }
+        if !self.adps.is_empty() {
+            extracted.adps = indices.iter().map(|&i| self.adps[i as usize]).collect();
+        }
+        if !self.b_factors.is_empty() {
+            extracted.b_factors = indices.iter().map(|&i| self.b_factors[i as usize]).collect();
+        }
+        if !self.ca_chains.is_empty() {
+            extracted.ca_chains = indices.iter().map(|&i| self.ca_chains[i as usize]).collect();
+        }
+        if !self.selected.is_empty() {
+            extracted.selected = indices.iter().map(|&i| self.selected[i as usize]).collect();
+        }
+        if !self.secondary_structure.is_empty() {
+            extracted.secondary_structure = indices.iter().map(|&i| self.secondary_structure[i as usize]).collect();
+        }
+        if !self.importance.is_empty() {
+            extracted.importance = indices.iter().map(|&i| self.importance[i as usize]).collect();
+        }
+        extracted.importance_weight = self.importance_weight;
+        extracted.original_indices = indices.iter().map(|&i| self.original_index(i as usize)).collect();
+        extracted.bond_cutoff_overrides = self.bond_cutoff_overrides.clone();
+        extracted.element_radius_overrides = self.element_radius_overrides.clone();
+        extracted.element_color_overrides = self.element_color_overrides.clone();
+        extracted.element_remap = self.element_remap.clone();
+        extracted.element_lod_bias = self.element_lod_bias.clone();
+        extracted.hidden_elements = self.hidden_elements.clone();
+
+        log!(self, LOG_INFO, "Extracted {} visible atoms into a new system", extracted.all_atoms.len());
+        extracted
+    }
+
+    /// Storage indices of visible atoms whose `lod_level` differs from the
+    /// previous cull, in visible-list order. Atoms that just became visible count
+    /// as changed, and after a load every visible atom does. Lets a renderer
+    /// patch only those instances.
+    pub fn get_lod_changes(&self) -> Vec<u32> {
+        self.lod_changes.clone()
+    }
+
+    /// Atoms that entered or left the visible set in the last cull, compared
+    /// with the cull before it, in ascending storage order: a newly visible
+    /// atom `i` is encoded as `i`, a newly hidden one as `-(i + 1)` (so atom 0
+    /// hiding is `-1`). Applying it to the previous set gives the current one,
+    /// for streaming visibility to a remote renderer. After a load every
+    /// visible atom is new; empty when there is no current visible set.
+    pub fn get_visibility_delta(&self) -> Vec<i32> {
+        self.visibility_delta.clone()
+    }
+
+    /// Whether the atom at storage `index` is in the cached visible set, e.g. to
+    /// decide if a picked atom needs framing. False when there is no current
+    /// visible set (nothing culled yet, or a setting or the atoms changed since)
+    /// and for out-of-range indices.
+    pub fn is_atom_visible(&self, index: usize) -> bool {
+        self.last_cull_params.is_some() && self.atom_lods.get(index).is_some_and(|&lod| lod != LOD_HIDDEN)
+    }
+
+    /// `[fov, aspect, near, far]` used to build the current visible set, empty if there is none
+    pub fn get_last_cull_params(&self) -> Vec<f32> {
+        self.last_cull_params.map_or_else(Vec::new, |p| vec![p.fov, p.aspect, p.near, p.far])
+    }
+
+    /// Camera-facing quads for impostor spheres, one record per atom of the cached
+    /// visible set (same order), 11 floats each:
+    /// `[cx, cy, cz, radius, half_size, rx, ry, rz, ux, uy, uz]`.
+    ///
+    /// `right`/`up` are unit vectors spanning a quad that faces the eye from the
+    /// atom center; `half_size` exceeds `radius` just enough to cover the sphere's
+    /// perspective silhouette.
+    pub fn get_visible_atom_billboards(&self, camera: &Camera) -> Vec<f32> {
+        let camera = self.local_camera(camera);
+        let eye = (camera.x, camera.y, camera.z);
+        let (cam_right, cam_up) = match camera.view_basis() {
+            Some((_, right, up)) => (right, up),
+            None => ((1.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+        };
+
+        let mut billboards = Vec::with_capacity(self.cached_visible_atoms.len() * 11);
+        for atom in &self.cached_visible_atoms {
+            let center = (atom.x, atom.y, atom.z);
+            let to_eye = sub(eye, center);
+            let distance = dot(to_eye, to_eye).sqrt();
+
+            let (right, up) = match normalize(to_eye) {
+                Some(view) => match normalize(cross(cam_up, view)) {
+                    Some(right) => (right, cross(view, right)),
+                    None => (cam_right, cam_up),
+                },
+                None => (cam_right, cam_up),
+            };
+
+            // Tangent cone from the eye: the silhouette radius grows as the eye approaches
+            let half_size = if distance > atom.radius {
+                atom.radius * distance / (distance * distance - atom.radius * atom.radius).sqrt()
+            } else {
+                atom.radius
+            };
+
+            billboards.extend_from_slice(&[
+                atom.x, atom.y, atom.z, atom.radius, half_size,
+                right.0, right.1, right.2,
+                up.0, up.1, up.2,
+            ]);
+        }
+        billboards
+    }
+
+    /// Milliseconds taken by the last `[load, bond detection, cull]`. Only measured
+    /// when built with the `perf` feature; otherwise all zeros.
+    pub fn get_timings(&self) -> Vec<f32> {
+        vec![self.timings.load_ms, self.timings.bonds_ms, self.timings.cull_ms]
+    }
+
+    /// Whether the most recent `get_visible_atoms` / `get_visible_bonds` call (or
+    /// a scalar-parameter form) reused the cached visible set, or a remembered
+    /// camera (`set_frame_cache_size`), instead of culling
+    pub fn last_query_was_cached(&self) -> bool {
+        self.last_query_cached
+    }
+
+    /// How finely the camera cache tells cameras apart: positions and targets
+    /// are quantized to `1 / position_scale` units and the field of view to
+    /// `1 / fov_scale` radians, and cameras that round the same reuse the
+    /// cached visible set. Defaults 1000 and 100, steps of 0.001 Å and 0.01
+    /// rad, suit structures some tens of Å across. Raise `position_scale`
+    /// for tightly zoomed views (moves below a step leave the view stale) and
+    /// lower it for overviews of large assemblies (jitter re-culls needlessly);
+    /// around `10000 / r`, with `r` the `bounding_sphere` radius, keeps the
+    /// step at 1/10000 of the structure. Non-positive or non-finite scales are
+    /// ignored. With `set_high_precision` camera positions are compared
+    /// exactly and only `fov_scale` applies.
+    pub fn set_camera_hash_precision(&mut self, position_scale: f32, fov_scale: f32) {
+        let valid = position_scale > 0.0 && position_scale.is_finite() && fov_scale > 0.0 && fov_scale.is_finite();
+        if !valid {
+            log!(self, LOG_ERRORS, "Invalid camera hash precision ({}, {}) - keeping ({}, {})",
+                 position_scale, fov_scale, self.camera_hash_position_scale, self.camera_hash_fov_scale);
+            return;
+        }
+        self.camera_hash_position_scale = position_scale;
+        self.camera_hash_fov_scale = fov_scale;
+        self.invalidate_camera_cache();
+    }
+
+    /// Counters from the last cull:
+    /// `[total, tested, frustum_culled, distance_culled, visible, memory_culled]`,
+    /// where `memory_culled` is how many of the distance-culled atoms were
+    /// dropped only to fit `set_cache_memory_budget` (non-zero means the
+    /// budget degraded the view)
+    pub fn get_cull_stats(&self) -> Vec<f32> {
+        let stats = &self.cull_stats;
+        vec![
+            stats.total as f32,
+            stats.tested as f32,
+            stats.frustum_culled as f32,
+            stats.distance_culled as f32,
+            stats.visible as f32,
+            stats.memory_culled as f32,
+        ]
+    }
+
+    /// Number of bonds in the cached visible set, without copying it
+    pub fn get_visible_bond_count(&self) -> usize {
+        self.cached_visible_bonds.len()
+    }
+
+    /// Cached visible bonds per LOD level, one count per level (`set_lod_level_count`),
+    /// for sizing per-LOD bond instance buffers
+    pub fn get_bond_lod_counts(&self) -> Vec<u32> {
+        let mut counts = vec![0u32; self.lod_levels as usize];
+        for bond in &self.cached_visible_bonds {
+            if let Some(count) = counts.get_mut(bond.lod_level as usize) {
+                *count += 1;
+            }
+        }
+        counts
+    }
+
+    /// Keep the visible list in original atom index order even when an accelerated
+    /// traversal visits atoms out of order. Costs an O(n log n) sort per recompute
+    /// whenever the traversal did reorder; the brute-force path is already ordered
+    /// unless elements are hidden.
+    /// Turns `set_opaque_sort` off when enabled.
+    pub fn set_preserve_order(&mut self, enabled: bool) {
+        self.preserve_order = enabled;
+        self.opaque_sort &= !enabled;
+        self.invalidate_camera_cache();
+    }
+
+    /// Order the visible list nearest-first by distance from the eye, so
+    /// opaque spheres drawn in list order let early-z reject the fragments
+    /// they hide. Costs an O(n log n) sort per recompute, which only pays
+    /// off in overdraw-heavy opaque scenes (dense close-ups of large
+    /// systems); sparse or distant views are usually faster without it.
+    /// Visible indices follow the same order. Off by default; turns
+    /// `set_preserve_order` off when enabled.
+    pub fn set_opaque_sort(&mut self, enabled: bool) {
+        self.opaque_sort = enabled;
+        self.preserve_order &= !enabled;
+        self.invalidate_camera_cache();
+    }
+
+    /// Turn the view-direction (frustum) test on or off. With it off, every atom
+    /// within the far-distance limit is kept regardless of direction, and LOD
+    /// still applies ("show all within range").
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.frustum_culling = enabled;
+        self.invalidate_camera_cache();
+    }
+
+    /// Widen the view-direction test: atoms are kept inside a cone around the
+    /// view direction whose half-angle is `(1 + margin)` times half the
+    /// vertical FOV. The default 0.2 gives some slack against pop-in at the
+    /// edges while turning. The cone is round and ignores the aspect ratio, so
+    /// wide viewports may need a larger margin to keep their left and right
+    /// edges; larger margins keep more atoms off screen. Negative or
+    /// non-finite values are ignored.
+    pub fn set_frustum_margin(&mut self, margin: f32) {
+        if !(margin.is_finite() && margin >= 0.0) {
+            log!(self, LOG_ERRORS, "Invalid frustum margin {} - keeping {}", margin, self.frustum_margin);
+            return;
+        }
+        self.frustum_margin = margin;
+        self.invalidate_camera_cache();
+    }
+
+    /// Distance-cull atoms beyond `f * far` instead of the default `0.8 * far`.
+    /// Use `1.0` to keep atoms right up to the far plane. Values above 1 are
+    /// clamped to 1; non-positive or non-finite values are ignored.
+    pub fn set_far_cull_fraction(&mut self, f: f32) {
+        if !(f > 0.0 && f.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid far cull fraction {} - keeping {}", f, self.far_cull_fraction);
+            return;
+        }
+        self.far_cull_fraction = f.min(1.0);
+        self.invalidate_camera_cache();
+    }
+
+    /// Multiply coordinates by `scale` as subsequent files are parsed (PDB, XYZ,
+    /// GRO, streaming), so sources in different units share one world. The
+    /// default 1.0 keeps file values as-is, which for PDB and XYZ means Å (GRO
+    /// is converted from nm first); use 0.529177 for Bohr. Everything distance
+    /// based (bond detection against covalent radii, element display radii)
+    /// assumes Å. Atoms already loaded are not rescaled. Non-positive or
+    /// non-finite values are ignored.
+    pub fn set_input_scale(&mut self, scale: f32) {
+        if !(scale > 0.0 && scale.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid input scale {} - keeping {}", scale, self.input_scale);
+            return;
+        }
+        self.input_scale = scale;
+    }
+
+    /// Axis convention of subsequently parsed files (PDB, XYZ, GRO,
+    /// streaming). Everything here, including the camera and frustum math, is
+    /// right-handed, so `LeftHanded` sources have z negated as they are read
+    /// (and their displacement tensors mirrored to match) instead of appearing
+    /// mirrored. Like `set_input_scale`, this converts at load time: atoms
+    /// already loaded are not flipped, so no cached view goes stale. Default
+    /// `RightHanded`, keeping file values as-is.
+    pub fn set_coordinate_handedness(&mut self, handedness: Handedness) {
+        self.input_handedness = handedness;
+    }
+
+    /// Show or hide every atom of one element (e.g. hydrogens). Culling then skips
+    /// hidden elements' atoms entirely, along with their bonds.
+    pub fn set_element_visible(&mut self, element: u32, visible: bool) {
+        let changed = if visible {
+            self.hidden_elements.remove(&element)
+        } else {
+            self.hidden_elements.insert(element)
+        };
+        if changed {
+            self.invalidate_camera_cache();
+        }
+    }
+
+    /// Bound geometry cost in close-up views: only the `n` visible atoms nearest
+    /// the camera may use the top LOD (3 by default), the next `n` at most one
+    /// below, and so on down to LOD 1; all others are points. Distance LOD still
+    /// applies, so atoms are never promoted. `0` removes the budget.
+    pub fn set_max_high_lod_atoms(&mut self, n: usize) {
+        self.max_high_lod_atoms = if n == 0 { None } else { Some(n) };
+        self.invalidate_camera_cache();
+    }
+
+    /// Hard cap on the visible set: when more than `n` atoms pass culling, only
+    /// the `n` nearest the camera are kept, independent of storage layout and
+    /// of chunked traversal order. Survivors keep their usual order, and the
+    /// dropped atoms count as distance-culled in `get_cull_stats`. Applied
+    /// before `set_max_high_lod_atoms`. `0` removes the cap.
+    pub fn set_visible_atom_cap(&mut self, n: usize) {
+        self.visible_atom_cap = if n == 0 { None } else { Some(n) };
+        self.invalidate_camera_cache();
+    }
+
+    /// Cull in two levels: first test chunks of roughly `chunk_size` per side
+    /// against the distance limit and view cone, then test only the atoms in
+    /// surviving chunks. Gives the same visible set as the brute-force path, but
+    /// in chunk order unless `set_preserve_order` is on. Sparse scenes may get
+    /// coarser chunks than requested. A non-positive `chunk_size` keeps the
+    /// brute-force path.
+    pub fn set_chunked_culling(&mut self, enabled: bool, chunk_size: f32) {
+        self.chunked_culling = if !enabled {
+            None
+        } else if chunk_size > 0.0 && chunk_size.is_finite() {
+            Some(chunk_size)
+        } else {
+            log!(self, LOG_ERRORS, "Invalid chunk size {} - chunked culling stays off", chunk_size);
+            None
+        };
+        self.chunk_grid = None;
+        self.invalidate_camera_cache();
+    }
+
+    /// Rebuild the spatial index (the chunked-culling grid, or the coarse grid
+    /// behind `atoms_within` and `estimate_visible_count`) from the current
+    /// atoms now, and drop every cached view. Operations that move or reorder
+    /// atoms already discard a stale index and the next query rebuilds it
+    /// lazily, so this is never needed for correctness; call it after a batch
+    /// of edits to pay the build cost at a chosen moment instead of in the
+    /// next frame.
+    pub fn rebuild_index(&mut self) {
+        self.geometry_changed();
+        if let Some(chunk_size) = self.chunked_culling {
+            self.chunk_grid = Some(SpatialGrid::build(&self.all_atoms, chunk_size));
+        }
+        self.coarse_grid();
+        self.invalidate_camera_cache();
+    }
+
+    // Sort the visible list nearest-first from `eye`
+    fn sort_front_to_back(&mut self, eye: math::Vec3) {
+        let distances: Vec<f32> = self.cached_visible_atoms.iter()
+            .map(|atom| {
+                let offset = sub((atom.x, atom.y, atom.z), eye);
+                dot(offset, offset)
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..distances.len()).collect();
+        order.sort_unstable_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+        self.cached_visible_atoms = order.iter().map(|&i| self.cached_visible_atoms[i].clone()).collect();
+        self.cached_visible_indices = order.iter().map(|&i| self.cached_visible_indices[i]).collect();
+    }
+
+    // Sort the visible list back into storage order (no-op if already sorted)
+    fn restore_storage_order(&mut self) {
+        if self.cached_visible_indices.is_sorted() {
+            return;
+        }
+        let mut order: Vec<usize> = (0..self.cached_visible_indices.len()).collect();
+        order.sort_unstable_by_key(|&i| self.cached_visible_indices[i]);
+        self.cached_visible_atoms = order.iter().map(|&i| self.cached_visible_atoms[i].clone()).collect();
+        self.cached_visible_indices = order.iter().map(|&i| self.cached_visible_indices[i]).collect();
+    }
+
+    /// Current LOD distance thresholds in descending order, one fewer than the
+    /// LOD level count and shared by atoms and bonds: beyond `t[0]` is LOD 0,
+    /// beyond `t[k]` LOD k, and closer than the last the top LOD. Edges are
+    /// exclusive on the far side: an atom exactly at `t[k]` gets LOD k + 1,
+    /// the finer level, every time. With the default four levels this is
+    /// `[point, low_poly, medium_poly]`.
+    pub fn get_lod_thresholds(&self) -> Vec<f32> {
+        self.lod_thresholds().cutoffs().to_vec()
+    }
+
+    /// Stop atoms hovering at a threshold from flickering between two LODs:
+    /// an atom keeps its LOD from the previous cull until it is more than
+    /// `fraction` of the threshold past it (0.05 allows 5%). Atoms entering
+    /// view, and bonds, get the plain thresholds. `0` (the default) turns it
+    /// off; negative or non-finite values are ignored, and values above 0.5
+    /// are clamped to 0.5.
+    pub fn set_lod_hysteresis(&mut self, fraction: f32) {
+        if !(fraction >= 0.0 && fraction.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid LOD hysteresis {} - keeping {}", fraction, self.lod_hysteresis);
+            return;
+        }
+        self.lod_hysteresis = fraction.min(MAX_LOD_HYSTERESIS);
+        self.invalidate_camera_cache();
+    }
+
+    /// Number of LOD levels, so `lod_level` ranges over `0..n` (default 4). The
+    /// thresholds keep the same point and high-poly cutoffs and are interpolated
+    /// between them, so finer mesh ladders just get more tiers in between.
+    /// Values outside 1..=16 are ignored.
+    pub fn set_lod_level_count(&mut self, n: u32) {
+        if !(1..=MAX_LOD_LEVELS).contains(&n) {
+            log!(self, LOG_ERRORS, "Invalid LOD level count {} - keeping {}", n, self.lod_levels);
+            return;
+        }
+        self.lod_levels = n;
+        self.invalidate_camera_cache();
+    }
+
+    /// Weight the along-view and lateral parts of the camera-to-atom vector
+    /// when measuring distance for LOD: the LOD distance is
+    /// `sqrt((along * a)² + (lateral * l)²)`. A lateral weight above the along
+    /// weight keeps detail near the view axis, for a flattened viewing volume.
+    /// `set_visible_atom_cap` and `set_max_high_lod_atoms` rank atoms by the
+    /// same distance; the far-plane cull still uses the plain one. `(1, 1)`,
+    /// the default, is isotropic; non-positive or non-finite weights are
+    /// ignored.
+    pub fn set_lod_distance_weights(&mut self, along: f32, lateral: f32) {
+        if !(along > 0.0 && along.is_finite() && lateral > 0.0 && lateral.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid LOD distance weights ({}, {}) - must be positive", along, lateral);
+            return;
+        }
+        self.lod_distance_weights = (along != 1.0 || lateral != 1.0).then_some((along, lateral));
+        self.invalidate_camera_cache();
+    }
+
+    fn lod_thresholds(&self) -> LodThresholds {
+        let aggression = self.calculate_aggression_factor() * self.lod_quality;
+        LodThresholds::new(self.lod_levels, aggression)
+    }
+
+    fn calculate_aggression_factor(&self) -> f32 {
+        // More aggressive culling for larger atom counts
+        match self.total_atom_count {
+            0..=1_000 => 1.0,
+            1_001..=10_000 => 2.0,
+            10_001..=100_000 => 4.0,
+            100_001..=1_000_000 => 8.0,
+            1_000_001..=10_000_000 => 16.0,
+            _ => 32.0,
+        }
+    }
+
+    fn calculate_camera_hash(&self, camera: &Camera, proj: &Projection) -> u64 {
+        let coordinates = [camera.x, camera.y, camera.z, camera.target_x, camera.target_y, camera.target_z];
+        let step = self.camera_hash_position_scale;
+        let hash = coordinates.into_iter().fold(FNV_OFFSET, |hash, c| {
+            // Exact in high precision, so no camera move is too small to re-cull
+            let word = if self.high_precision { c.to_bits() as u64 } else { quantize_for_hash(c * step) };
+            fnv_mix(hash, word)
+        });
+        proj.hash_into(hash, self.camera_hash_fov_scale)
+    }
+
+    // Replace the per-atom LODs, recording which visible atoms changed LOD and
+    // which atoms appeared or disappeared. After a load there are no previous
+    // LODs to compare against.
+    fn adopt_atom_lods(&mut self, atom_lods: Vec<u8>) {
+        let previous_lods = std::mem::replace(&mut self.atom_lods, atom_lods);
+        let was_visible = |i: usize| previous_lods.get(i).is_some_and(|&lod| lod != LOD_HIDDEN);
+        self.visibility_delta = self.atom_lods
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &lod)| match (was_visible(i), lod != LOD_HIDDEN) {
+                (false, true) => Some(i as i32),
+                (true, false) => Some(-(i as i32) - 1),
+                _ => None,
+            })
+            .collect();
+        self.lod_changes = self.cached_visible_indices
+            .iter()
+            .copied()
+            .filter(|&i| previous_lods.get(i as usize) != Some(&self.atom_lods[i as usize]))
+            .collect();
+    }
+
+    fn invalidate_camera_cache(&mut self) {
+        self.frame_cache.clear();
+        self.current_camera_hash = 0;
+        self.last_cull_params = None;
+        self.last_cull_eye = None;
+        self.visible_batches = None;
+        self.cached_visible_atoms.clear();
+        self.cached_visible_indices.clear();
+        self.cached_visible_bonds.clear();
+        self.cached_visible_bond_pairs.clear();
+        self.cached_impostors.clear();
+        // Keep `atom_lods` so the next cull can still report changes
+        self.lod_changes.clear();
+        self.visibility_delta.clear();
+    }
+
+    // Whether the legacy diatomic behavior is active
+    fn small_mode(&self) -> bool {
+        match self.mode {
+            SystemMode::Auto => self.total_atom_count <= 2,
+            SystemMode::Small => true,
+            SystemMode::Large => false,
+        }
+    }
+
+    // Bond list, falling back to the implicit H-F bond of the diatomic case
+    fn bond_pairs(&self) -> &[(u32, u32)] {
+        const DIATOMIC_BOND: [(u32, u32); 1] = [(0, 1)];
+        if self.bonds.is_empty() && self.small_mode() && self.all_atoms.len() == 2 {
+            &DIATOMIC_BOND
+        } else {
+            &self.bonds
+        }
+    }
+
+    fn bond_between(&self, a: usize, b: usize) -> BondData {
+        let (start, end) = (self.all_atoms[a], self.all_atoms[b]);
+        BondData {
+            start_x: start.x, start_y: start.y, start_z: start.z,
+            end_x: end.x, end_y: end.y, end_z: end.z,
+            lod_level: self.lod_levels - 1,
+        }
+    }
+
+    /// Get raw atom data for WebGPU compute shaders
+    pub fn get_all_atom_positions(&self) -> Vec<f32> {
+        let mut positions = Vec::with_capacity(self.all_atoms.len() * 4); // x, y, z, element
+
+        for atom in &self.all_atoms {
+            positions.push(atom.x);
+            positions.push(atom.y);
+            positions.push(atom.z);
+            positions.push(atom.element as f32);
         }
 
         positions
     }
 
-    /// Get coarse spatial chunks for WebGPU processing
+    /// Occupied cells of the grid chunked culling actually uses, as
+    /// `[min_x, min_y, min_z, max_x, max_y, max_z]` per cell, for visualizing
+    /// the partitioning while tuning `set_chunked_culling`. Cell size may be
+    /// coarser than requested in sparse scenes. Empty until chunked culling is
+    /// on and a cull has built the grid (and again after atoms move).
+    pub fn get_grid_cell_boxes(&self) -> Vec<f32> {
+        let Some(grid) = self.chunk_grid.as_ref().filter(|_| self.chunked_culling.is_some()) else {
+            return Vec::new();
+        };
+        let size = grid.cell_size();
+        let mut boxes = Vec::new();
+        grid.for_each_cell(|min, _| {
+            boxes.extend_from_slice(&[min.0, min.1, min.2, min.0 + size, min.1 + size, min.2 + size]);
+        });
+        boxes
+    }
+
+    /// Get coarse spatial chunks for WebGPU processing: 8 floats per occupied
+    /// chunk, `[cx, cy, cz, size, atom_count, 0, 0, 0]`. Every atom is in
+    /// exactly one chunk, so the counts sum to the atom count.
     pub fn get_spatial_chunks(&self, chunk_size: f32) -> Vec<f32> {
-        if self.all_atoms.is_empty() {
+        if self.all_atoms.is_empty() || !(chunk_size > 0.0 && chunk_size.is_finite()) {
             return Vec::new();
         }
 
@@ -351,70 +2276,153 @@ impl MolecularSystem {
             max_pos.2 = max_pos.2.max(atom.z);
         }
 
-        // Create spatial chunks
-        let chunks_per_axis = ((max_pos.0 - min_pos.0) / chunk_size).ceil().max(1.0) as usize;
-        let mut chunks = Vec::new();
-
-        for x in 0..chunks_per_axis {
-            for y in 0..chunks_per_axis {
-                for z in 0..chunks_per_axis {
-                    let center_x = min_pos.0 + (x as f32 + 0.5) * chunk_size;
-                    let center_y = min_pos.1 + (y as f32 + 0.5) * chunk_size;
-                    let center_z = min_pos.2 + (z as f32 + 0.5) * chunk_size;
-
-                    // Count atoms in this chunk
-                    let mut atom_count = 0;
-                    for atom in &self.all_atoms {
-                        let dx = (atom.x - center_x).abs();
-                        let dy = (atom.y - center_y).abs();
-                        let dz = (atom.z - center_z).abs();
-
-                        if dx <= chunk_size * 0.5 && dy <= chunk_size * 0.5 && dz <= chunk_size * 0.5 {
-                            atom_count += 1;
-                        }
-                    }
+        // Create spatial chunks, enough per axis to cover the widest extent so flat
+        // or coincident datasets still land in at least one chunk
+        let extent = (max_pos.0 - min_pos.0)
+            .max(max_pos.1 - min_pos.1)
+            .max(max_pos.2 - min_pos.2);
+        let chunks_per_axis = (extent / chunk_size).ceil().max(1.0) as usize;
+
+        // Each atom counts in exactly one chunk: cells are half-open,
+        // `[min, min + chunk_size)` per axis, except that the last one also
+        // takes atoms on the far bound. Keyed by (x, y, z) to emit in that order.
+        let cell = |v: f32, min: f32| (((v - min) / chunk_size).floor().max(0.0) as usize).min(chunks_per_axis - 1);
+        let mut counts: BTreeMap<(usize, usize, usize), u32> = BTreeMap::new();
+        for atom in &self.all_atoms {
+            let key = (cell(atom.x, min_pos.0), cell(atom.y, min_pos.1), cell(atom.z, min_pos.2));
+            *counts.entry(key).or_default() += 1;
+        }
 
-                    if atom_count > 0 {
-                        chunks.push(center_x);      // chunk center x
-                        chunks.push(center_y);      // chunk center y
-                        chunks.push(center_z);      // chunk center z
-                        chunks.push(chunk_size);    // chunk size
-                        chunks.push(atom_count as f32); // atom count
-                        chunks.push(0.0);           // reserved
-                        chunks.push(0.0);           // reserved
-                        chunks.push(0.0);           // reserved
-                    }
-                }
-            }
+        let mut chunks = Vec::with_capacity(counts.len() * 8);
+        for ((x, y, z), atom_count) in counts {
+            chunks.push(min_pos.0 + (x as f32 + 0.5) * chunk_size); // chunk center x
+            chunks.push(min_pos.1 + (y as f32 + 0.5) * chunk_size); // chunk center y
+            chunks.push(min_pos.2 + (z as f32 + 0.5) * chunk_size); // chunk center z
+            chunks.push(chunk_size);        // chunk size
+            chunks.push(atom_count as f32); // atom count
+            chunks.push(0.0);               // reserved
+            chunks.push(0.0);               // reserved
+            chunks.push(0.0);               // reserved
         }
 
         chunks
     }
 
     pub fn update(&mut self, delta_time: f32) {
-        self.time += delta_time * self.animation_speed;
-
-        // Animation changes require camera cache invalidation
-        self.invalidate_camera_cache();
+        let time = self.time + delta_time * self.animation_speed;
+
+        // Culling doesn't depend on time, so a real change only re-derives the
+        // animated radii of the cached visible set
+        if time != self.time {
+            self.time = time;
+            self.refresh_animated_radii();
+            self.lod_changes.clear();
+            self.visibility_delta.clear();
+        }
     }
 
     pub fn get_total_atom_count(&self) -> usize {
         self.total_atom_count
     }
 
+    /// Seed for the synthetic generator's PRNG
+    pub fn set_generator_seed(&mut self, seed: u64) {
+        self.generator_seed = seed;
+    }
+
+    /// Draw the synthetic generator's H/F/O/N elements uniformly from the seeded
+    /// PRNG instead of the fixed `i % 4` cycle, whose repeating pattern puts
+    /// same-element atoms on regular planes and skews element-filter and color
+    /// tests. `false` (the default) keeps the cycle. An element distribution, when
+    /// set, takes precedence either way. Takes effect on the next generation.
+    pub fn set_generator_seed_elements(&mut self, enabled: bool) {
+        self.generator_random_elements = enabled;
+    }
+
+    /// Make the synthetic generator sample elements with these relative weights
+    /// (indexed by element id) instead of cycling H/F/O/N. An empty slice restores
+    /// the cycle. Takes effect on the next generation.
+    pub fn set_generator_element_distribution(&mut self, weights: &[f32]) -> Result<(), JsValue> {
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(JsValue::from_str("element weights must be finite and non-negative"));
+        }
+        if !weights.is_empty() && weights.iter().sum::<f32>() <= 0.0 {
+            return Err(JsValue::from_str("element weights must not all be zero"));
+        }
+
+        let mut total = 0.0;
+        self.generator_element_cdf = weights.iter().map(|w| { total += w; total }).collect();
+        Ok(())
+    }
+
+    /// Console verbosity: 0 silent, 1 errors and warnings (default), 2 info such
+    /// as load summaries, 3 verbose per-cull and dataset diagnostics
+    pub fn set_log_level(&mut self, level: u32) {
+        self.log_level = level;
+    }
+
+    /// Multiplier from `update`'s `delta_time` to animation time. Negative
+    /// speeds run the animation backward and `0` pauses it. Clamped to
+    /// `±get_max_animation_speed()`; non-finite speeds are ignored.
     pub fn set_animation_speed(&mut self, speed: f32) {
-        self.animation_speed = speed;
+        if !speed.is_finite() {
+            log!(self, LOG_ERRORS, "Invalid animation speed {} - keeping {}", speed, self.animation_speed);
+            return;
+        }
+        self.animation_speed = speed.clamp(-self.max_animation_speed, self.max_animation_speed);
+    }
+
+    /// Current animation speed, after clamping
+    pub fn get_animation_speed(&self) -> f32 {
+        self.animation_speed
     }
 
+    /// Largest animation speed magnitude `set_animation_speed` accepts (default
+    /// 100), so one long frame can't jump the animation far ahead. The current
+    /// speed is clamped to the new bound. Non-positive or NaN values are ignored;
+    /// infinity removes the bound.
+    pub fn set_max_animation_speed(&mut self, max: f32) {
+        if max.is_nan() || max <= 0.0 {
+            log!(self, LOG_ERRORS, "Invalid max animation speed {} - keeping {}", max, self.max_animation_speed);
+            return;
+        }
+        self.max_animation_speed = max;
+        self.animation_speed = self.animation_speed.clamp(-max, max);
+    }
+
+    pub fn get_max_animation_speed(&self) -> f32 {
+        self.max_animation_speed
+    }
+
+    /// Generator cell edge length. Takes effect on the next generation; use
+    /// `MolecularSystemBuilder` to configure and generate in one step.
     pub fn set_grid_size(&mut self, size: f32) {
         self.grid_size = size;
-        // Regenerate atoms with new spacing
-        let count = self.total_atom_count;
-        self.load_atoms_from_file(count);
     }
 
-    // Legacy compatibility
+    /// Generator lattice. Takes effect on the next generation.
+    pub fn set_generator_lattice(&mut self, lattice: Lattice) {
+        self.generator_lattice = lattice;
+    }
+
+    /// Order the generator fills its grid of cells in, and so the storage
+    /// order of generated atoms. `Morton` keeps spatial neighbors close in
+    /// storage. When the atoms do not fill the grid, the order also decides
+    /// which cells are used. Default `Xyz`. Takes effect on the next
+    /// generation.
+    pub fn set_generator_fill_order(&mut self, order: FillOrder) {
+        self.generator_fill_order = order;
+    }
+
+    /// Legacy alias of `load_atoms_from_file`, except that it refuses to
+    /// replace atoms parsed from a file (logging an error instead), so an old
+    /// call site cannot silently wipe a loaded structure. Call
+    /// `load_atoms_from_file`, or `clear` first, to regenerate deliberately.
     pub fn generate_atoms(&mut self, count: usize) {
+        if self.loaded_from_file {
+            log!(self, LOG_ERRORS, "generate_atoms ignored: {} atoms were loaded from a file - use load_atoms_from_file to replace them", self.all_atoms.len());
+            return;
+        }
         self.load_atoms_from_file(count);
     }
 
@@ -422,42 +2430,67 @@ impl MolecularSystem {
         self.get_visible_atoms_for_camera(camera, fov, aspect, near, far)
     }
 
-    // Legacy methods for small molecules
+    /// Stored atom at `index` with its element radius and full-detail LOD, for
+    /// any dataset size. Unlike `get_atom_data` this is not animated.
+    pub fn get_atom(&self, index: usize) -> Option<AtomData> {
+        let atom = self.all_atoms.get(index)?;
+        Some(AtomData {
+            x: atom.x,
+            y: atom.y,
+            z: atom.z,
+            element: atom.element,
+            radius: self.element_radius(atom.element),
+            lod_level: self.lod_levels - 1,
+        })
+    }
+
+    /// Choose the legacy small-molecule or general API surface explicitly
+    /// instead of by atom count; see `SystemMode`
+    pub fn set_mode(&mut self, mode: SystemMode) {
+        self.mode = mode;
+        // The implicit diatomic bond may have appeared or gone
+        self.invalidate_camera_cache();
+    }
+
+    // Legacy methods for small molecules (`get_atom_count`, `get_atom_data`
+    // and `get_bond_data` are active in `SystemMode::Small` only)
     pub fn get_atom_count(&self) -> usize {
-        if self.total_atom_count <= 2 { self.total_atom_count } else { 0 }
+        if self.small_mode() { self.total_atom_count } else { 0 }
     }
 
     pub fn get_atom_data(&self, index: usize) -> Option<AtomData> {
-        if self.total_atom_count <= 2 && index < self.all_atoms.len() {
-            let atom = self.all_atoms[index];
-            let animated_offset = if index == 1 {
-                0.18 * (self.time.sin() + 1.0) * 0.5
-            } else { 0.0 };
-
-            Some(AtomData {
-                x: atom.x + animated_offset,
-                y: atom.y,
-                z: atom.z,
-                element: atom.element,
-                radius: if atom.element == 0 { 0.25 } else { 0.35 },
-                lod_level: 3,
-            })
-        } else {
-            None
+        if !self.small_mode() {
+            return None;
         }
+        let mut atom = self.get_atom(index)?;
+        if index == 1 {
+            atom.x += 0.18 * (self.time.sin() + 1.0) * 0.5;
+        }
+        Some(atom)
     }
 
+    /// Bonds of any dataset size, explicit or from `detect_bonds`; before
+    /// either, the implicit H-F bond of the diatomic case counts as one
     pub fn get_bond_count(&self) -> usize {
-        if self.total_atom_count <= 2 { 1 } else { 0 }
+        self.bond_pairs().len()
+    }
+
+    /// Bond at `index` (ascending atom-pair order, as `get_bond_count`
+    /// counts them) between its atoms' stored positions, at full-detail LOD.
+    /// Unlike `get_bond_data` this is not animated.
+    pub fn get_bond(&self, index: usize) -> Option<BondData> {
+        let &(a, b) = self.bond_pairs().get(index)?;
+        Some(self.bond_between(a as usize, b as usize))
     }
 
     pub fn get_bond_data(&self, index: usize) -> Option<BondData> {
-        if self.total_atom_count <= 2 && index == 0 {
+        if self.small_mode() && index == 0 {
             let h_atom = self.get_atom_data(0)?;
             let f_atom = self.get_atom_data(1)?;
             Some(BondData {
                 start_x: h_atom.x, start_y: h_atom.y, start_z: h_atom.z,
                 end_x: f_atom.x, end_y: f_atom.y, end_z: f_atom.z,
+                lod_level: self.lod_levels - 1,
             })
         } else {
             None
@@ -465,7 +2498,14 @@ impl MolecularSystem {
     }
 }
 
+impl Default for MolecularSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct BondData {
     pub start_x: f32,
     pub start_y: f32,
@@ -473,12 +2513,270 @@ pub struct BondData {
     pub end_x: f32,
     pub end_y: f32,
     pub end_z: f32,
+    /// 0 = line, higher = increasingly detailed cylinder (same tiers as atom LOD)
+    pub lod_level: u32,
 }
 
 #[wasm_bindgen(start)]
 pub fn main() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
+}
 
-    log!("Enhanced molecular visualization WASM initialized - with file reading simulation and rotation-aware LOD");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn culling_an_empty_system_returns_nothing() {
+        let mut system = MolecularSystem::new();
+        let camera = Camera::new();
+        let visible = system.get_visible_atoms(&camera, &Projection::new(1.0, 1.0, 0.1, 100.0));
+        assert!(visible.is_empty());
+        assert!(system.get_spatial_chunks(10.0).is_empty());
+    }
+
+    #[test]
+    fn culling_a_single_atom_keeps_it() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("1\n\nC 1 2 3\n").unwrap();
+        let mut camera = Camera::new();
+        (camera.x, camera.y, camera.z) = (1.0, 2.0, 23.0);
+        (camera.target_x, camera.target_y, camera.target_z) = (1.0, 2.0, 3.0);
+
+        let visible = system.get_visible_atoms(&camera, &Projection::new(1.0, 1.0, 0.1, 100.0));
+        assert_eq!(visible.len(), 1);
+        assert_eq!((visible[0].x, visible[0].y, visible[0].z), (1.0, 2.0, 3.0));
+        assert!(system.get_spatial_chunks(10.0).iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn culling_coincident_atoms_from_their_position_keeps_all() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("3\n\nC 5 5 5\nO 5 5 5\nN 5 5 5\n").unwrap();
+        // Eye on the atoms and on its own target: no view or atom direction
+        let mut camera = Camera::new();
+        (camera.x, camera.y, camera.z) = (5.0, 5.0, 5.0);
+        (camera.target_x, camera.target_y, camera.target_z) = (5.0, 5.0, 5.0);
+
+        let visible = system.get_visible_atoms(&camera, &Projection::new(1.0, 1.0, 0.1, 100.0));
+        assert_eq!(visible.len(), 3);
+        assert!(visible.iter().all(|a| a.x.is_finite() && a.radius.is_finite()));
+        let chunks = system.get_spatial_chunks(10.0);
+        assert!(chunks.iter().all(|v| v.is_finite()));
+        assert_eq!(chunks[4], 3.0);
+    }
+
+    #[test]
+    fn update_without_time_change_keeps_cached_visible_set() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("3\n\nC 0 0 0\nO 1 0 0\nN 0 1 0\n").unwrap();
+        let camera = Camera::new();
+        let before = system.get_visible_atoms_for_camera(&camera, 1.0, 1.0, 0.1, 100.0);
+        let hash = system.current_camera_hash;
+        assert_ne!(hash, 0);
+
+        // Paused, and a zero step while playing: neither moves time
+        system.set_animation_speed(0.0);
+        system.update(0.016);
+        system.set_animation_speed(1.0);
+        system.update(0.0);
+
+        // An invalidated cache would have reset the hash
+        assert_eq!(system.current_camera_hash, hash);
+        let after = system.get_visible_atoms_for_camera(&camera, 1.0, 1.0, 0.1, 100.0);
+        let radii = |atoms: &[AtomData]| atoms.iter().map(|a| a.radius).collect::<Vec<_>>();
+        assert_eq!(radii(&after), radii(&before));
+        assert_eq!(system.time, 0.0);
+    }
+
+    #[test]
+    fn chunked_culling_matches_brute_force() {
+        let mut system = MolecularSystem::new();
+        system.load_atoms_from_file(4000);
+        let projection = Projection::new(0.9, 1.5, 0.1, 60.0);
+        let mut cameras = Vec::new();
+        for (x, y, z) in [(0.0, 2.0, 5.0), (30.0, -10.0, 25.0), (-3.0, 4.0, -40.0), (0.5, 0.5, 0.5)] {
+            let mut camera = Camera::new();
+            (camera.x, camera.y, camera.z) = (x, y, z);
+            cameras.push(camera);
+        }
+
+        for camera in &cameras {
+            system.set_chunked_culling(false, 0.0);
+            system.get_visible_atoms(camera, &projection);
+            let mut brute: Vec<_> = system.cached_visible_indices.iter()
+                .zip(&system.cached_visible_atoms)
+                .map(|(&i, a)| (i, a.lod_level))
+                .collect();
+
+            system.set_chunked_culling(true, 4.0);
+            system.get_visible_atoms(camera, &projection);
+            let mut chunked: Vec<_> = system.cached_visible_indices.iter()
+                .zip(&system.cached_visible_atoms)
+                .map(|(&i, a)| (i, a.lod_level))
+                .collect();
+
+            brute.sort_unstable();
+            chunked.sort_unstable();
+            assert!(!brute.is_empty());
+            assert_eq!(chunked, brute);
+        }
+    }
+
+    #[test]
+    fn generated_partial_lattice_is_centered_on_its_bounds() {
+        for count in [1000, 1024, 1001] {
+            let mut system = MolecularSystem::new();
+            system.set_log_level(0);
+            system.load_atoms_from_file(count);
+            assert_eq!(system.all_atoms.len(), count);
+
+            let (min, max) = system.atom_extent();
+            for (lo, hi) in [(min.0, max.0), (min.1, max.1), (min.2, max.2)] {
+                assert!((lo + hi).abs() < 1e-3, "{} atoms span {}..{}", count, lo, hi);
+                assert!(hi > lo);
+            }
+            // Camera framing reads the same center
+            let sphere = system.bounding_sphere(false);
+            assert!(sphere[..3].iter().all(|c| c.abs() < 1e-3), "{} atoms: sphere {:?}", count, sphere);
+        }
+    }
+
+    #[test]
+    fn camera_on_its_target_is_invalid_but_culls_without_nans() {
+        let mut camera = Camera::new();
+        assert!(camera.is_valid());
+        (camera.x, camera.y, camera.z) = (1.0, 1.0, 1.0);
+        (camera.target_x, camera.target_y, camera.target_z) = (1.0, 1.0, 1.0);
+        assert!(!camera.is_valid());
+        assert!(camera.view_basis().is_none());
+
+        let mut system = MolecularSystem::new();
+        system.load_xyz("2\n\nC 1 1 -4\nO 1 1 6\n").unwrap();
+        let visible = system.get_visible_atoms(&camera, &Projection::new(1.0, 1.0, 0.1, 100.0));
+        // Looks down -Z: the atom below is in view, the one above is not
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].z, -4.0);
+        assert!(visible.iter().all(|a| a.x.is_finite() && a.radius.is_finite()));
+    }
+
+    #[test]
+    fn degenerate_up_vector_falls_back_to_world_up() {
+        let mut camera = Camera::new();
+        (camera.up_x, camera.up_y, camera.up_z) = (0.0, 0.0, 0.0);
+        assert!(!camera.is_valid());
+        let (forward, right, up) = camera.view_basis().unwrap();
+        for v in [forward, right, up] {
+            assert!((length(v) - 1.0).abs() < 1e-5);
+        }
+        assert!(dot(forward, up).abs() < 1e-5 && dot(right, up).abs() < 1e-5);
+        assert!(up.1 > 0.0);
+    }
+
+    #[test]
+    fn generate_atoms_keeps_file_loaded_atoms() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("2\n\nC 0 0 0\nO 1.2 0 0\n").unwrap();
+        let positions = system.get_all_atom_positions();
+
+        system.generate_atoms(500);
+        assert_eq!(system.get_total_atom_count(), 2);
+        assert_eq!(system.get_all_atom_positions(), positions);
+
+        // After `clear` the legacy call generates again
+        system.clear();
+        system.generate_atoms(500);
+        assert_eq!(system.get_total_atom_count(), 500);
+    }
+
+    #[test]
+    fn animation_speed_runs_forward_backward_or_not_at_all() {
+        let mut system = MolecularSystem::new();
+
+        system.set_animation_speed(2.0);
+        system.update(0.5);
+        assert_eq!(system.time, 1.0);
+
+        system.set_animation_speed(-4.0);
+        assert_eq!(system.get_animation_speed(), -4.0);
+        system.update(0.125);
+        assert_eq!(system.time, 0.5);
+
+        system.set_animation_speed(0.0);
+        system.update(10.0);
+        assert_eq!(system.time, 0.5);
+    }
+
+    #[test]
+    fn animation_speed_is_clamped_to_its_maximum() {
+        let mut system = MolecularSystem::new();
+        system.set_max_animation_speed(3.0);
+        system.set_animation_speed(-50.0);
+        assert_eq!(system.get_animation_speed(), -3.0);
+        system.update(1.0);
+        assert_eq!(system.time, -3.0);
+
+        system.set_max_animation_speed(1.0);
+        assert_eq!(system.get_animation_speed(), -1.0);
+        system.set_animation_speed(f32::NAN);
+        assert_eq!(system.get_animation_speed(), -1.0);
+    }
+
+    #[test]
+    fn atom_exactly_at_a_threshold_takes_the_finer_lod_every_cull() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("1\n\nC 0 0 0\n").unwrap();
+        let low_poly = system.get_lod_thresholds()[1];
+        let mut camera = Camera::new();
+        (camera.x, camera.y, camera.z) = (0.0, 0.0, low_poly);
+        let projection = Projection::new(1.0, 1.0, 0.1, low_poly * 10.0);
+
+        for _ in 0..5 {
+            let visible = system.get_visible_atoms(&camera, &projection);
+            assert_eq!(visible[0].lod_level, 2);
+            // Force the next query to cull again
+            system.set_frustum_culling(true);
+        }
+    }
+
+    #[test]
+    fn lod_hysteresis_holds_the_previous_lod_near_a_threshold() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("1\n\nC 0 0 0\n").unwrap();
+        system.set_lod_hysteresis(0.05);
+        let low_poly = system.get_lod_thresholds()[1];
+        let projection = Projection::new(1.0, 1.0, 0.1, low_poly * 10.0);
+        let mut lod_at = |distance: f32| {
+            let mut camera = Camera::new();
+            (camera.x, camera.y, camera.z) = (0.0, 0.0, distance);
+            system.get_visible_atoms(&camera, &projection)[0].lod_level
+        };
+
+        assert_eq!(lod_at(low_poly * 0.9), 2);
+        // Past the threshold but within 5% of it: still the finer LOD
+        assert_eq!(lod_at(low_poly * 1.03), 2);
+        assert_eq!(lod_at(low_poly * 1.06), 1);
+        // And back inside the band from the coarser side
+        assert_eq!(lod_at(low_poly * 0.97), 1);
+        assert_eq!(lod_at(low_poly * 0.9), 2);
+    }
+
+    #[test]
+    fn spatial_chunk_counts_sum_to_atom_count() {
+        // A 5x5x5 integer lattice: with whole-number chunk sizes many atoms
+        // sit exactly on chunk faces
+        let mut xyz = String::from("125\n\n");
+        for i in 0..125 {
+            xyz += &format!("C {} {} {}\n", i % 5, i / 5 % 5, i / 25);
+        }
+        let mut system = MolecularSystem::new();
+        system.load_xyz(&xyz).unwrap();
+
+        for chunk_size in [1.0, 2.0, 0.5, 3.7, 100.0] {
+            let chunks = system.get_spatial_chunks(chunk_size);
+            let total: f32 = chunks.chunks_exact(8).map(|chunk| chunk[4]).sum();
+            assert_eq!(total as usize, system.all_atoms.len(), "chunk size {}", chunk_size);
+        }
+    }
 }
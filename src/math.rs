@@ -0,0 +1,166 @@
+// Small vector helpers on the (x, y, z) tuples used throughout the crate
+
+pub(crate) type Vec3 = (f32, f32, f32);
+
+pub(crate) fn add(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+pub(crate) fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+pub(crate) fn scale(a: Vec3, s: f32) -> Vec3 {
+    (a.0 * s, a.1 * s, a.2 * s)
+}
+
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f32 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    (
+        a.1 * b.2 - a.2 * b.1,
+        a.2 * b.0 - a.0 * b.2,
+        a.0 * b.1 - a.1 * b.0,
+    )
+}
+
+pub(crate) fn length(a: Vec3) -> f32 {
+    dot(a, a).sqrt()
+}
+
+/// Unit vector, or `None` for a (near) zero-length input
+pub(crate) fn normalize(a: Vec3) -> Option<Vec3> {
+    let len = length(a);
+    if len > f32::EPSILON {
+        Some(scale(a, 1.0 / len))
+    } else {
+        None
+    }
+}
+
+/// Any unit vector perpendicular to `v`
+pub(crate) fn perpendicular(v: Vec3) -> Vec3 {
+    let axis = if v.0.abs() < 0.9 { (1.0, 0.0, 0.0) } else { (0.0, 1.0, 0.0) };
+    normalize(cross(v, axis)).unwrap_or((0.0, 0.0, 1.0))
+}
+
+/// Rotate `v` by `angle` radians around the unit vector `axis` (Rodrigues)
+pub(crate) fn rotate_about_axis(v: Vec3, axis: Vec3, angle: f32) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    add(
+        add(scale(v, cos), scale(cross(axis, v), sin)),
+        scale(axis, dot(axis, v) * (1.0 - cos)),
+    )
+}
+
+pub(crate) type Matrix3 = [[f32; 3]; 3];
+
+/// Jacobi sweeps are quadratically convergent; a handful always suffices in 3x3
+const MAX_JACOBI_SWEEPS: usize = 16;
+
+// Eigen-decomposition of a symmetric 3x3 matrix by cyclic Jacobi rotations.
+// Returns the eigenvalues and the matching eigenvectors as matrix columns.
+pub(crate) fn symmetric_eigen(mut a: Matrix3) -> ([f32; 3], Matrix3) {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..MAX_JACOBI_SWEEPS {
+        let off = a[0][1].abs() + a[0][2].abs() + a[1][2].abs();
+        let diag = a[0][0].abs() + a[1][1].abs() + a[2][2].abs();
+        if off <= f32::EPSILON * diag || off == 0.0 {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0.0 {
+                continue;
+            }
+            // Rotation angle that zeroes a[p][q]
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            // A <- J^T A J: columns p, q, then rows p, q
+            for row in a.iter_mut() {
+                let (akp, akq) = (row[p], row[q]);
+                row[p] = c * akp - s * akq;
+                row[q] = s * akp + c * akq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+            for row in v.iter_mut() {
+                let (vkp, vkq) = (row[p], row[q]);
+                row[p] = c * vkp - s * vkq;
+                row[q] = s * vkp + c * vkq;
+            }
+        }
+    }
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+/// Kahan (compensated) running sum: feeds the low-order bits each addition
+/// rounds away back into the next one, so centroids of millions of f32
+/// coordinates far from the origin stay accurate where a naive sum drifts
+#[derive(Clone, Copy, Default)]
+pub(crate) struct KahanSum {
+    sum: f32,
+    compensation: f32,
+}
+
+impl KahanSum {
+    pub(crate) fn add(&mut self, value: f32) {
+        let y = value - self.compensation;
+        let t = self.sum + y;
+        // What of `y` the rounding of `t` dropped, negated
+        self.compensation = (t - self.sum) - y;
+        self.sum = t;
+    }
+
+    pub(crate) fn value(&self) -> f32 {
+        self.sum
+    }
+}
+
+/// SplitMix64: tiny deterministic PRNG for the synthetic generator
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KahanSum;
+
+    #[test]
+    fn kahan_sum_holds_where_a_naive_sum_drifts() {
+        let n = 2_000_000;
+        let mut naive = 0.0f32;
+        let mut kahan = KahanSum::default();
+        for i in 0..n {
+            let value = if i % 2 == 0 { 1000.0 } else { 1000.1 };
+            naive += value;
+            kahan.add(value);
+        }
+
+        let exact = 1000.05;
+        assert!((kahan.value() / n as f32 - exact).abs() < 1e-3, "kahan mean {}", kahan.value() / n as f32);
+        // Past 2^24 an f32 sum rounds away whole units per addition
+        assert!((naive / n as f32 - exact).abs() > 1.0, "naive mean {}", naive / n as f32);
+    }
+}
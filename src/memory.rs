@@ -0,0 +1,59 @@
+// Memory budget for the cached visible set and remembered frames
+
+use std::mem::size_of;
+use wasm_bindgen::prelude::*;
+
+use crate::{AtomData, BondData, MolecularSystem};
+
+/// Cache bytes per visible atom: its `AtomData` and storage index
+pub(crate) const VISIBLE_ATOM_BYTES: usize = size_of::<AtomData>() + size_of::<u32>();
+
+/// Cache bytes per visible bond: its `BondData` and atom pair
+pub(crate) const VISIBLE_BOND_BYTES: usize = size_of::<BondData>() + size_of::<(u32, u32)>();
+
+impl MolecularSystem {
+    // Most visible atoms the budget holds, with the bonds a typical atom of
+    // this system brings along
+    pub(crate) fn memory_atom_cap(&self) -> Option<usize> {
+        let budget = self.cache_memory_budget?;
+        let bonds_per_atom = self.bonds.len() as f64 / self.all_atoms.len().max(1) as f64;
+        let per_atom = VISIBLE_ATOM_BYTES as f64 + bonds_per_atom * VISIBLE_BOND_BYTES as f64;
+        Some((budget as f64 / per_atom) as usize)
+    }
+
+    pub(crate) fn visible_set_bytes(&self) -> usize {
+        self.cached_visible_atoms.len() * VISIBLE_ATOM_BYTES
+            + self.cached_visible_bonds.len() * VISIBLE_BOND_BYTES
+            + self.cached_impostors.len() * size_of::<f32>()
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Cap the memory of the cached visible set plus remembered frames
+    /// (`set_frame_cache_size`) at about `bytes`, for constrained devices
+    /// where a close-up of a huge structure could otherwise make millions of
+    /// atoms visible. Degrades gracefully rather than failing:
+    ///
+    /// 1. Remembered frames are evicted, least recently used first, so they
+    ///    only use what the current visible set leaves free.
+    /// 2. If the visible set alone would not fit, only the nearest atoms that
+    ///    fit are kept, as with `set_visible_atom_cap` (pinned selections
+    ///    stay). LOD does not change an atom's size in the cache, so lowering
+    ///    it would not help; the view loses its far atoms instead.
+    ///
+    /// Atoms dropped for the budget count as distance-culled and are also
+    /// reported as `memory_culled` in `get_cull_stats`. The estimate covers the
+    /// visible atoms, bonds and impostors, not the atoms themselves. `0`
+    /// removes the budget.
+    pub fn set_cache_memory_budget(&mut self, bytes: usize) {
+        self.cache_memory_budget = (bytes > 0).then_some(bytes);
+        self.invalidate_camera_cache();
+    }
+
+    /// Bytes currently held by the cached visible set and remembered frames,
+    /// as counted against `set_cache_memory_budget`
+    pub fn get_cache_memory_bytes(&self) -> usize {
+        self.visible_set_bytes() + self.frame_cache_bytes()
+    }
+}
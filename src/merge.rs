@@ -0,0 +1,115 @@
+// Combining two systems into one dataset
+
+use wasm_bindgen::prelude::*;
+
+use crate::adp::rotate_adp;
+use crate::backbone::NOT_ALPHA_CARBON;
+use crate::math::{add, scale, Vec3};
+use crate::world::affine_parts;
+use crate::{MolecularSystem, RawAtom, SecondaryStructure};
+
+const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 1.0, 0.0,
+    0.0, 0.0, 0.0, 1.0,
+];
+
+// Append `theirs` to a per-atom vector, padding whichever side is empty with
+// `fill`; stays empty when both are
+fn append_parallel<T: Copy>(ours: &mut Vec<T>, our_len: usize, theirs: &[T], their_len: usize, fill: T) {
+    if ours.is_empty() && theirs.is_empty() {
+        return;
+    }
+    ours.resize(our_len, fill);
+    if theirs.is_empty() {
+        ours.resize(our_len + their_len, fill);
+    } else {
+        ours.extend_from_slice(theirs);
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Append `other`'s atoms and bonds to this system, placing them with a
+    /// column-major 4x4 affine `transform` (as `set_world_transform`; empty
+    /// means identity), e.g. a docked ligand pose next to its receptor.
+    /// Returns the combined atom count.
+    ///
+    /// Atoms are placed in f64, keeping f64 positions if either side has
+    /// them (`set_high_precision`). Velocities, forces and displacement
+    /// tensors are carried along by the matrix's linear part; other per-atom
+    /// data (B-factors, selection, importance, secondary structure, backbone)
+    /// is copied, and a side without it gets zeros or coil. Bonds keep their atoms, with `other`'s
+    /// indices offset by this system's atom count, and stay explicit only if
+    /// both sides' were. `other`'s element styling, unit cell and settings are
+    /// not copied. A matrix that is not 16 finite values forming an invertible
+    /// affine map is an error.
+    pub fn merge(&mut self, other: &MolecularSystem, transform: &[f32]) -> Result<usize, JsValue> {
+        let matrix = if transform.is_empty() { &IDENTITY[..] } else { transform };
+        let Some((columns, translation)) = affine_parts(matrix) else {
+            return Err(JsValue::from_str("merge transform must be an invertible affine 4x4 matrix (16 floats)"));
+        };
+        let linear = |v: Vec3| add(add(scale(columns[0], v.0), scale(columns[1], v.1)), scale(columns[2], v.2));
+        let (n, m) = (self.all_atoms.len(), other.all_atoms.len());
+
+        // Placed in f64, from `other`'s f64 positions when it has them
+        // (`set_high_precision`), which the result then keeps too
+        let c = columns.map(|c| [c.0 as f64, c.1 as f64, c.2 as f64]);
+        let t = [translation.0 as f64, translation.1 as f64, translation.2 as f64];
+        let placed: Vec<[f64; 3]> = (0..m).map(|j| {
+            let p = other.precise_position(j);
+            std::array::from_fn(|k| c[0][k] * p[0] + c[1][k] * p[1] + c[2][k] * p[2] + t[k])
+        }).collect();
+        self.all_atoms.extend(other.all_atoms.iter().zip(&placed).map(|(atom, p)| {
+            RawAtom { x: p[0] as f32, y: p[1] as f32, z: p[2] as f32, ..*atom }
+        }));
+        if !self.precise_positions.is_empty() || !other.precise_positions.is_empty() {
+            let ours: Vec<[f64; 3]> = (0..n).map(|i| self.precise_position(i)).collect();
+            self.precise_positions = ours.into_iter().chain(placed).collect();
+        }
+
+        self.bonds.extend(other.bonds.iter().map(|&(a, b)| (a + n as u32, b + n as u32)));
+        self.explicit_bonds = match (n, m) {
+            (0, _) => other.explicit_bonds,
+            (_, 0) => self.explicit_bonds,
+            _ => self.explicit_bonds && other.explicit_bonds,
+        };
+
+        let velocities: Vec<Vec3> = other.velocities.iter().map(|&v| linear(v)).collect();
+        append_parallel(&mut self.velocities, n, &velocities, m, (0.0, 0.0, 0.0));
+        let forces: Vec<Vec3> = other.forces.iter().map(|&f| linear(f)).collect();
+        append_parallel(&mut self.forces, n, &forces, m, (0.0, 0.0, 0.0));
+        let [a, b, c] = columns;
+        let rows = [[a.0, b.0, c.0], [a.1, b.1, c.1], [a.2, b.2, c.2]];
+        let adps: Vec<_> = other.adps.iter().map(|u| rotate_adp(&rows, u)).collect();
+        append_parallel(&mut self.adps, n, &adps, m, [0.0; 6]);
+        append_parallel(&mut self.b_factors, n, &other.b_factors, m, 0.0);
+
+        // Chain codes only separate backbone traces, so `other`'s are shifted
+        // past ours to keep its chains apart from ours
+        let chain_offset = self.ca_chains.iter().filter(|&&c| c != NOT_ALPHA_CARBON).max().map_or(0, |&c| c + 1);
+        let ca_chains: Vec<u32> = other.ca_chains.iter()
+            .map(|&c| if c == NOT_ALPHA_CARBON { c } else { c + chain_offset })
+            .collect();
+        append_parallel(&mut self.ca_chains, n, &ca_chains, m, NOT_ALPHA_CARBON);
+        append_parallel(&mut self.selected, n, &other.selected, m, false);
+        append_parallel(&mut self.secondary_structure, n, &other.secondary_structure, m, SecondaryStructure::Coil as u8);
+        append_parallel(&mut self.importance, n, &other.importance, m, 0.0);
+
+        // File order: ours, then `other`'s after our highest original index
+        if !self.original_indices.is_empty() || !other.original_indices.is_empty() {
+            let offset = (0..n).map(|i| self.original_index(i) + 1).max().unwrap_or(0);
+            let originals: Vec<u32> = (0..m).map(|j| offset + other.original_index(j)).collect();
+            self.original_indices = (0..n).map(|i| self.original_index(i)).chain(originals).collect();
+        }
+
+        self.bonds.sort_unstable();
+        self.total_atom_count = self.all_atoms.len();
+        self.indices_changed();
+        self.positions_changed();
+
+        log!(self, LOG_INFO, "Merged {} atoms and {} bonds: {} atoms total", m, other.bonds.len(), self.all_atoms.len());
+        Ok(self.all_atoms.len())
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::bonds::canonical_bonds;
+use crate::pdb::ParsedAtoms;
+use crate::perf::{elapsed_ms, now_ms};
+use crate::precision::InputFrame;
+use crate::warnings::LoadWarnings;
+use crate::{MolecularSystem, RawAtom};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Other,
+    Atoms,
+    Bonds,
+}
+
+/// Line-at-a-time Tripos MOL2 reader: the `@<TRIPOS>ATOM` and
+/// `@<TRIPOS>BOND` records of the first molecule
+pub(crate) struct Mol2Parser {
+    // Applied to every coordinate as it is read
+    frame: InputFrame,
+    atoms: Vec<RawAtom>,
+    // Atom id column to index in `atoms`; bonds refer to atoms by id
+    ids: HashMap<u32, u32>,
+    bonds: Vec<(u32, u32)>,
+    section: Section,
+    molecules: usize,
+    warnings: LoadWarnings,
+}
+
+impl Mol2Parser {
+    pub(crate) fn new(frame: InputFrame) -> Self {
+        Mol2Parser {
+            frame,
+            atoms: Vec::new(),
+            ids: HashMap::new(),
+            bonds: Vec::new(),
+            section: Section::Other,
+            molecules: 0,
+            warnings: LoadWarnings::default(),
+        }
+    }
+
+    pub(crate) fn parse_line(&mut self, line: &str, line_no: usize) -> Result<(), String> {
+        let line = line.trim();
+        if let Some(tag) = line.strip_prefix("@<TRIPOS>") {
+            self.section = match tag {
+                "ATOM" => Section::Atoms,
+                "BOND" => Section::Bonds,
+                "MOLECULE" => {
+                    self.molecules += 1;
+                    Section::Other
+                }
+                _ => Section::Other,
+            };
+            return Ok(());
+        }
+        // Anything past the first molecule is ignored
+        if self.molecules > 1 || line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+
+        let mut fields = line.split_whitespace();
+        let mut index = |what: &str| -> Result<u32, String> {
+            fields.next()
+                .and_then(|field| field.parse::<u32>().ok())
+                .ok_or_else(|| format!("MOL2 line {}: invalid {}", line_no, what))
+        };
+        match self.section {
+            Section::Atoms => {
+                let id = index("atom id")?;
+                let mut fields = fields.skip(1);
+                let mut coordinate = || -> Result<f64, String> {
+                    fields.next()
+                        .and_then(|field| field.parse::<f64>().ok())
+                        .ok_or_else(|| format!("MOL2 line {}: invalid coordinate", line_no))
+                };
+                let (x, y, z) = (coordinate()?, coordinate()?, coordinate()?);
+                let (x, y, z) = self.frame.point(x, y, z);
+                // SYBYL types are the element, then an optional hybridization: C.3, N.ar
+                let atom_type = fields.next()
+                    .ok_or_else(|| format!("MOL2 line {}: missing atom type", line_no))?;
+                let element = self.warnings.element(atom_type.split('.').next().unwrap_or(""));
+
+                if self.ids.insert(id, self.atoms.len() as u32).is_some() {
+                    return Err(format!("MOL2 line {}: duplicate atom id {}", line_no, id));
+                }
+                self.atoms.push(RawAtom { x, y, z, element, occupancy: 1.0 });
+            }
+            Section::Bonds => {
+                index("bond id")?;
+                let (a, b) = (index("bond atom")?, index("bond atom")?);
+                let atom = |id: u32| {
+                    self.ids.get(&id).copied()
+                        .ok_or_else(|| format!("MOL2 line {}: bond to unknown atom {}", line_no, id))
+                };
+                let (a, b) = (atom(a)?, atom(b)?);
+                if a == b {
+                    return Err(format!("MOL2 line {}: bond joins an atom to itself", line_no));
+                }
+                self.bonds.push((a, b));
+            }
+            Section::Other => {}
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<ParsedAtoms, String> {
+        if self.atoms.is_empty() {
+            return Err("MOL2 has no @<TRIPOS>ATOM records".to_string());
+        }
+        Ok(ParsedAtoms {
+            warnings: self.warnings.into_messages(),
+            bonds: canonical_bonds(self.bonds),
+            precise: self.frame.into_precise(),
+            ..self.atoms.into()
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Load atoms and bonds from Tripos MOL2 text, replacing the current data.
+    /// Elements come from the SYBYL atom types, and the listed bonds become
+    /// explicit bonds (`has_explicit_bonds`). Only the first molecule is read.
+    pub fn load_mol2(&mut self, text: &str) -> Result<usize, JsValue> {
+        log!(self, LOG_INFO, "Parsing MOL2 ({} bytes)...", text.len());
+        let start = now_ms();
+
+        let mut parser = Mol2Parser::new(self.input_frame());
+        for (line_no, line) in text.lines().enumerate() {
+            parser.parse_line(line, line_no + 1).map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        self.adopt_parsed_atoms(parser.finish().map_err(|e| JsValue::from_str(&e))?);
+
+        self.timings.load_ms = elapsed_ms(start);
+        log!(self, LOG_INFO, "Loaded {} atoms and {} bonds from MOL2", self.all_atoms.len(), self.bonds.len());
+        Ok(self.all_atoms.len())
+    }
+}
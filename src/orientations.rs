@@ -0,0 +1,45 @@
+use wasm_bindgen::prelude::*;
+
+use crate::adp::principal_axes;
+use crate::math::{normalize, Vec3};
+use crate::MolecularSystem;
+
+/// Per-atom direction for arrow and other oriented glyphs, for
+/// `get_visible_atom_orientations`
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrientationSource {
+    /// From `set_velocities`
+    Velocity,
+    /// From `set_forces`
+    Force,
+    /// Longest axis of the thermal ellipsoid (displacement tensor)
+    PrincipalAxis,
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Unit direction `[x, y, z]` per atom of the cached visible set, in
+    /// visible-list order, for drawing vector-field glyphs over the atoms.
+    /// Atoms whose source data is absent or zero get `[0, 0, 0]`. A principal
+    /// axis has no sign, so either direction along it may be returned.
+    pub fn get_visible_atom_orientations(&self, source: OrientationSource) -> Vec<f32> {
+        let direction = |i: usize| -> Option<Vec3> {
+            match source {
+                OrientationSource::Velocity => normalize(*self.velocities.get(i)?),
+                OrientationSource::Force => normalize(*self.forces.get(i)?),
+                OrientationSource::PrincipalAxis => {
+                    let (axis, value) = principal_axes(self.adps.get(i)?)[0];
+                    (value > 0.0).then_some(axis)
+                }
+            }
+        };
+
+        let mut out = Vec::with_capacity(self.cached_visible_indices.len() * 3);
+        for &i in &self.cached_visible_indices {
+            let d = direction(i as usize).unwrap_or((0.0, 0.0, 0.0));
+            out.extend_from_slice(&[d.0, d.1, d.2]);
+        }
+        out
+    }
+}
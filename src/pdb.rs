@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::elements::element_info;
+use crate::perf::{elapsed_ms, now_ms};
+use crate::adp::{rotate_adp, Adp};
+use crate::backbone::NOT_ALPHA_CARBON;
+use crate::crystal::UnitCell;
+use crate::math::Vec3;
+use crate::precision::InputFrame;
+use crate::warnings::LoadWarnings;
+use crate::{Handedness, MolecularSystem, RawAtom};
+
+/// How `load_pdb` treats alternate conformations (the alt-loc column)
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AltLocPolicy {
+    /// Keep only the highest-occupancy alternate of each atom
+    HighestOccupancy,
+    /// Keep every alternate location as its own atom
+    KeepAll,
+}
+
+// Identity of an atom across its alternate locations:
+// atom name, residue name, chain, residue number, insertion code
+type AtomKey = (String, String, String, String, String);
+
+// Fixed-width PDB column, 1-based inclusive like the format spec
+fn column(line: &str, start: usize, end: usize) -> &str {
+    let end = end.min(line.len());
+    if start > end {
+        return "";
+    }
+    line.get(start - 1..end).unwrap_or("").trim()
+}
+
+fn parse_coordinate(line: &str, start: usize, end: usize, line_no: usize) -> Result<f64, String> {
+    column(line, start, end)
+        .parse::<f64>()
+        .map_err(|_| format!("PDB line {}: invalid coordinate in columns {}-{}", line_no, start, end))
+}
+
+fn element_symbol(line: &str) -> &str {
+    // Element symbol lives in cols 77-78; older files only have it in the atom name
+    let symbol = column(line, 77, 78);
+    if symbol.is_empty() {
+        column(line, 13, 14).trim_start_matches(|c: char| c.is_ascii_digit())
+    } else {
+        symbol
+    }
+}
+
+// One fixed-width ATOM record. Atoms carry no residue data, so every atom is
+// named after its element in residue MOL 1 of chain A; serials wrap at 99999.
+fn write_atom_record(out: &mut String, serial: usize, atom: &RawAtom, [x, y, z]: [f64; 3], b_factor: f32) {
+    let symbol = element_info(atom.element).map_or("X", |e| e.symbol).to_ascii_uppercase();
+    // Single-letter elements sit in column 14 of the atom name by convention
+    let name = if symbol.len() == 1 { format!(" {}", symbol) } else { symbol.clone() };
+    out.push_str(&format!(
+        "ATOM  {:>5} {:<4} MOL A   1    {:>8.3}{:>8.3}{:>8.3}{:>6.2}{:>6.2}          {:>2}\n",
+        serial % 100_000, name, x, y, z, atom.occupancy, b_factor, symbol
+    ));
+}
+
+// ANISOU cols 29-70: U11 U22 U33 U12 U13 U23 as 7-wide integers in units of 1e-4 Å²
+fn parse_anisou(line: &str, line_no: usize) -> Result<Adp, String> {
+    let mut u = [0.0; 6];
+    for (k, value) in u.iter_mut().enumerate() {
+        let start = 29 + 7 * k;
+        *value = column(line, start, start + 6)
+            .parse::<i32>()
+            .map_err(|_| format!("PDB line {}: invalid ANISOU value in columns {}-{}", line_no, start, start + 6))?
+            as f32 * 1e-4;
+    }
+    Ok(u)
+}
+
+/// Atoms from a parsed file with their optional per-atom extras, each either
+/// empty or parallel to `atoms`
+#[derive(Default)]
+pub(crate) struct ParsedAtoms {
+    pub(crate) atoms: Vec<RawAtom>,
+    pub(crate) adps: Vec<Adp>,
+    pub(crate) b_factors: Vec<f32>,
+    pub(crate) ca_chains: Vec<u32>,
+    // f64 position of each atom in high-precision mode, else empty; see
+    // `InputFrame`
+    pub(crate) precise: Vec<[f64; 3]>,
+    // Summaries of recoverable problems; see `take_load_warnings`
+    pub(crate) warnings: Vec<String>,
+    // Connectivity listed in the file, ascending index pairs; empty if none
+    pub(crate) bonds: Vec<(u32, u32)>,
+    pub(crate) velocities: Vec<Vec3>,
+    // Periodic box from the file, in stored coordinates
+    pub(crate) unit_cell: Option<UnitCell>,
+}
+
+impl From<Vec<RawAtom>> for ParsedAtoms {
+    fn from(atoms: Vec<RawAtom>) -> Self {
+        ParsedAtoms { atoms, ..Default::default() }
+    }
+}
+
+/// Line-at-a-time PDB reader, shared by `load_pdb` and the streaming loader
+pub(crate) struct PdbParser {
+    policy: AltLocPolicy,
+    // Applied to every coordinate as it is read
+    frame: InputFrame,
+    // Chain IDs to keep; empty keeps every chain
+    chains: Vec<char>,
+    atoms: Vec<RawAtom>,
+    // Parallel to `atoms`; zero for atoms without an ANISOU record
+    adps: Vec<Adp>,
+    has_adps: bool,
+    // Parallel to `atoms`; zero where the temperature factor column is blank
+    b_factors: Vec<f32>,
+    has_b_factors: bool,
+    // Parallel to `atoms`: the chain code of alpha carbons, NOT_ALPHA_CARBON otherwise
+    ca_chains: Vec<u32>,
+    has_ca: bool,
+    // Slot the most recent ATOM/HETATM went to, `None` if it was discarded
+    last_slot: Option<usize>,
+    // Alt-loc atoms already kept, so later alternates can replace them
+    altloc_slots: HashMap<AtomKey, usize>,
+    warnings: LoadWarnings,
+}
+
+impl PdbParser {
+    pub(crate) fn new(policy: AltLocPolicy, frame: InputFrame, chains: &[char]) -> Self {
+        PdbParser {
+            policy,
+            frame,
+            chains: chains.to_vec(),
+            atoms: Vec::new(),
+            adps: Vec::new(),
+            has_adps: false,
+            b_factors: Vec::new(),
+            has_b_factors: false,
+            ca_chains: Vec::new(),
+            has_ca: false,
+            last_slot: None,
+            altloc_slots: HashMap::new(),
+            warnings: LoadWarnings::default(),
+        }
+    }
+
+    fn push_atom(&mut self, atom: RawAtom, b_factor: f32, ca_chain: u32) {
+        self.last_slot = Some(self.atoms.len());
+        self.atoms.push(atom);
+        self.adps.push([0.0; 6]);
+        self.b_factors.push(b_factor);
+        self.ca_chains.push(ca_chain);
+    }
+
+    pub(crate) fn parse_line(&mut self, line: &str, line_no: usize) -> Result<(), String> {
+        let record = column(line, 1, 6);
+        if record == "ANISOU" {
+            // Applies to the ATOM record just before it
+            if let Some(slot) = self.last_slot {
+                // Displacements are squared lengths
+                let scale_sq = self.frame.scale() * self.frame.scale();
+                self.adps[slot] = parse_anisou(line, line_no)?.map(|u| u * scale_sq);
+                self.has_adps = true;
+            }
+            return Ok(());
+        }
+        if record != "ATOM" && record != "HETATM" {
+            return Ok(());
+        }
+        // Chain ID is column 22; a blank chain is ' '
+        let chain = line.get(21..).and_then(|rest| rest.chars().next()).unwrap_or(' ');
+        if !self.chains.is_empty() && !self.chains.contains(&chain) {
+            // Its ANISOU record must not attach to the previous atom
+            self.last_slot = None;
+            return Ok(());
+        }
+
+        let (x, y, z) = self.frame.point(
+            parse_coordinate(line, 31, 38, line_no)?,
+            parse_coordinate(line, 39, 46, line_no)?,
+            parse_coordinate(line, 47, 54, line_no)?,
+        );
+        // Missing occupancy means fully occupied
+        let occupancy = column(line, 55, 60).parse::<f32>().unwrap_or_else(|_| {
+            self.warnings.missing_occupancy();
+            1.0
+        });
+        let element = self.warnings.element(element_symbol(line));
+        let atom = RawAtom { x, y, z, element, occupancy };
+        let b_factor = match column(line, 61, 66).parse::<f32>() {
+            Ok(b) => {
+                self.has_b_factors = true;
+                b
+            }
+            Err(_) => 0.0,
+        };
+        // Alpha carbons are right-shifted " CA " names; calcium is written "CA  "
+        let ca_chain = if record == "ATOM" && line.get(12..16) == Some(" CA ") {
+            self.has_ca = true;
+            chain as u32
+        } else {
+            NOT_ALPHA_CARBON
+        };
+
+        let altloc = column(line, 17, 17);
+        if altloc.is_empty() || self.policy == AltLocPolicy::KeepAll {
+            self.push_atom(atom, b_factor, ca_chain);
+            return Ok(());
+        }
+
+        let key = (
+            column(line, 13, 16).to_string(),
+            column(line, 18, 20).to_string(),
+            column(line, 22, 22).to_string(),
+            column(line, 23, 26).to_string(),
+            column(line, 27, 27).to_string(),
+        );
+        match self.altloc_slots.get(&key) {
+            Some(&slot) => {
+                if occupancy > self.atoms[slot].occupancy {
+                    self.atoms[slot] = atom;
+                    self.adps[slot] = [0.0; 6];
+                    self.b_factors[slot] = b_factor;
+                    self.last_slot = Some(slot);
+                } else {
+                    self.last_slot = None;
+                }
+                self.frame.keep_last_at(self.last_slot);
+            }
+            None => {
+                self.altloc_slots.insert(key, self.atoms.len());
+                self.push_atom(atom, b_factor, ca_chain);
+            }
+        }
+        Ok(())
+    }
+
+    /// Atoms with their ADPs, B-factors and alpha-carbon chains; each extra is
+    /// empty when the file had no ANISOU records, temperature factors or alpha
+    /// carbons respectively
+    pub(crate) fn finish(self) -> ParsedAtoms {
+        ParsedAtoms {
+            adps: if self.has_adps { self.adps } else { Vec::new() },
+            b_factors: if self.has_b_factors { self.b_factors } else { Vec::new() },
+            ca_chains: if self.has_ca { self.ca_chains } else { Vec::new() },
+            warnings: self.warnings.into_messages(),
+            atoms: self.atoms,
+            precise: self.frame.into_precise(),
+            ..Default::default()
+        }
+    }
+}
+
+impl MolecularSystem {
+    // ATOM records for the atoms at `indices`, with their B-factors when
+    // loaded and f64 positions in high precision
+    fn write_pdb(&self, indices: impl Iterator<Item = usize>) -> String {
+        let mut out = String::new();
+        for (serial, i) in indices.enumerate() {
+            let b_factor = self.b_factors.get(i).copied().unwrap_or(0.0);
+            write_atom_record(&mut out, serial + 1, &self.all_atoms[i], self.precise_position(i), b_factor);
+        }
+        out.push_str("END\n");
+        out
+    }
+
+    // Replace the current data with freshly parsed atoms
+    pub(crate) fn adopt_parsed_atoms(&mut self, mut parsed: ParsedAtoms) {
+        self.convert_input_handedness(&mut parsed.atoms);
+        if self.input_handedness == Handedness::LeftHanded {
+            let mirror_z = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]];
+            for u in &mut parsed.adps {
+                *u = rotate_adp(&mirror_z, u);
+            }
+            for v in &mut parsed.velocities {
+                v.2 = -v.2;
+            }
+            for p in &mut parsed.precise {
+                p[2] = -p[2];
+            }
+            if let Some(cell) = &mut parsed.unit_cell {
+                for v in [&mut cell.a, &mut cell.b, &mut cell.c, &mut cell.origin] {
+                    v.2 = -v.2;
+                }
+            }
+        }
+        self.all_atoms = parsed.atoms;
+        self.finish_load();
+        self.adps = parsed.adps;
+        self.b_factors = parsed.b_factors;
+        self.ca_chains = parsed.ca_chains;
+        self.load_warnings = parsed.warnings;
+        self.velocities = parsed.velocities;
+        self.precise_positions = parsed.precise;
+        // A file without a box must not inherit the previous file's
+        self.unit_cell = parsed.unit_cell;
+        if !parsed.bonds.is_empty() {
+            self.bonds = parsed.bonds;
+            self.explicit_bonds = true;
+        }
+        self.loaded_from_file = true;
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Load atoms from PDB text (ATOM/HETATM records, plus ANISOU displacement
+    /// parameters when present), replacing the current data
+    pub fn load_pdb(&mut self, text: &str) -> Result<usize, JsValue> {
+        log!(self, LOG_INFO, "Parsing PDB ({} bytes)...", text.len());
+        let start = now_ms();
+
+        let mut parser = PdbParser::new(self.altloc_policy, self.input_frame(), &self.chain_filter);
+        for (line_no, line) in text.lines().enumerate() {
+            parser.parse_line(line, line_no + 1).map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        self.adopt_parsed_atoms(parser.finish());
+
+        self.timings.load_ms = elapsed_ms(start);
+        log!(self, LOG_INFO, "Loaded {} atoms from PDB", self.all_atoms.len());
+        Ok(self.all_atoms.len())
+    }
+
+    /// Alternate-location handling for subsequent `load_pdb` calls
+    pub fn set_altloc_policy(&mut self, policy: AltLocPolicy) {
+        self.altloc_policy = policy;
+    }
+
+    /// Only ingest these chains in subsequent PDB loads (`load_pdb` and
+    /// streaming), e.g. one chain of a large assembly. Chain IDs are character
+    /// codes (`"A".charCodeAt(0)` is 65; 32 is a blank chain). Other chains'
+    /// atoms are dropped while parsing, so they never take memory or indices.
+    /// Bond detection and every analysis then only see the kept chains: bonds
+    /// to excluded atoms, such as inter-chain disulfides, are not found. An
+    /// empty slice loads every chain again.
+    pub fn set_load_filter_chains(&mut self, chains: &[u32]) {
+        self.chain_filter = chains.iter().filter_map(|&c| char::from_u32(c)).collect();
+    }
+
+    /// All atoms as PDB ATOM records in storage order, readable by `load_pdb`,
+    /// with occupancies and B-factors (0 when none were loaded). Coordinates
+    /// are written from the f64 copy when `set_high_precision` kept one, and
+    /// must lie within PDB's fixed-width range (-999.999 to 9999.999).
+    pub fn to_pdb(&self) -> String {
+        self.write_pdb(0..self.all_atoms.len())
+    }
+
+    /// Like `to_pdb`, for the cached visible set in visible-list order
+    pub fn visible_to_pdb(&self) -> String {
+        self.write_pdb(self.cached_visible_indices.iter().map(|&i| i as usize))
+    }
+
+    /// Per-atom occupancy in storage order (matches `get_all_atom_positions`)
+    pub fn get_atom_occupancies(&self) -> Vec<f32> {
+        self.all_atoms.iter().map(|atom| atom.occupancy).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AltLocPolicy, Camera, MolecularSystem, Projection};
+
+    // One plain atom, then two alternates each of a side-chain atom and a water
+    const ALTLOC_PDB: &str = concat!(
+        "ATOM      1  N   SER A   5       1.000   2.000   3.000  1.00 10.00           N\n",
+        "ATOM      2  OG ASER A   5       4.000   5.000   6.000  0.40 11.00           O\n",
+        "ATOM      3  OG BSER A   5       4.500   5.500   6.500  0.60 12.00           O\n",
+        "HETATM    4  O  AHOH A 101       7.000   8.000   9.000  0.70 20.00           O\n",
+        "HETATM    5  O  BHOH A 101       7.250   8.250   9.250  0.30 21.00           O\n",
+    );
+
+    #[test]
+    fn highest_occupancy_policy_keeps_one_alternate_per_atom() {
+        let mut system = MolecularSystem::new();
+        system.set_altloc_policy(AltLocPolicy::HighestOccupancy);
+        let count = system.load_pdb(ALTLOC_PDB).unwrap();
+
+        assert_eq!(count, 3);
+        assert_eq!(system.get_atom_occupancies(), vec![1.0, 0.6, 0.7]);
+        assert_eq!(system.get_all_atom_positions()[4..7], [4.5, 5.5, 6.5]);
+        assert_eq!(system.b_factors, vec![10.0, 12.0, 20.0]);
+    }
+
+    #[test]
+    fn keep_all_policy_keeps_every_alternate() {
+        let mut system = MolecularSystem::new();
+        system.set_altloc_policy(AltLocPolicy::KeepAll);
+        let count = system.load_pdb(ALTLOC_PDB).unwrap();
+
+        assert_eq!(count, 5);
+        assert_eq!(system.get_atom_occupancies(), vec![1.0, 0.4, 0.6, 0.7, 0.3]);
+    }
+
+    #[test]
+    fn to_pdb_round_trips_through_load_pdb() {
+        let mut system = MolecularSystem::new();
+        system.load_pdb(concat!(
+            "ATOM      1  N   GLY A   1      11.104   6.134  -6.504  1.00 12.50           N\n",
+            "ATOM      2  CA  GLY A   1      11.639   6.071  -5.147  0.75 31.25           C\n",
+            "HETATM    3 FE   HEM A   2      -0.500 102.250   0.000  0.50  8.00          FE\n",
+        )).unwrap();
+
+        let mut reloaded = MolecularSystem::new();
+        assert_eq!(reloaded.load_pdb(&system.to_pdb()).unwrap(), 3);
+
+        let elements = |s: &MolecularSystem| s.all_atoms.iter().map(|a| a.element).collect::<Vec<_>>();
+        assert_eq!(elements(&reloaded), elements(&system));
+        assert_eq!(reloaded.get_all_atom_positions(), system.get_all_atom_positions());
+        assert_eq!(reloaded.get_atom_occupancies(), vec![1.0, 0.75, 0.5]);
+        assert_eq!(reloaded.b_factors, vec![12.5, 31.25, 8.0]);
+    }
+
+    #[test]
+    fn high_precision_round_trips_far_coordinates() {
+        let pdb = concat!(
+            "ATOM      1  C   MOL A   1    9876.543 876.543-765.432  1.00  0.00           C\n",
+            "ATOM      2  O   MOL A   1    9877.765 876.543-765.432  1.00  0.00           O\n",
+            "END\n",
+        );
+        let mut system = MolecularSystem::new();
+        system.set_high_precision(true);
+        system.load_pdb(pdb).unwrap();
+        assert_eq!(system.to_pdb(), pdb);
+
+        let mut reloaded = MolecularSystem::new();
+        reloaded.set_high_precision(true);
+        reloaded.load_pdb(&system.to_pdb()).unwrap();
+        assert_eq!(reloaded.precise_positions, system.precise_positions);
+    }
+
+    #[test]
+    fn high_precision_writes_f64_positions_and_takes_absolute_cameras() {
+        let mut system = MolecularSystem::new();
+        system.set_high_precision(true);
+        // An f32 copy alone would write 98765.430
+        system.load_xyz("1\n\nC 98765.432 1.5 -2.25\n").unwrap();
+        assert!(system.to_pdb().contains("98765.432   1.500  -2.250"));
+
+        // The camera is given in the file's coordinates, not re-centered
+        let x = 98765.432f64 as f32;
+        let mut camera = Camera::new();
+        (camera.x, camera.y, camera.z) = (x, 1.5, 17.75);
+        (camera.target_x, camera.target_y, camera.target_z) = (x, 1.5, -2.25);
+        let visible = system.get_visible_atoms(&camera, &Projection::new(1.0, 1.0, 0.1, 100.0));
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].x, x);
+    }
+}
@@ -0,0 +1,28 @@
+// Optional timing instrumentation
+//
+// With the `perf` feature off, `now_ms` is a constant and all recorded timings
+// stay zero, so nothing calls `performance.now()`.
+
+#[cfg(feature = "perf")]
+pub(crate) fn now_ms() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now())
+}
+
+#[cfg(not(feature = "perf"))]
+pub(crate) fn now_ms() -> f64 {
+    0.0
+}
+
+/// Milliseconds spent in the most recent run of each timed operation
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Timings {
+    pub(crate) load_ms: f32,
+    pub(crate) bonds_ms: f32,
+    pub(crate) cull_ms: f32,
+}
+
+pub(crate) fn elapsed_ms(start: f64) -> f32 {
+    (now_ms() - start) as f32
+}
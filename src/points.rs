@@ -0,0 +1,72 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::{dot, sub};
+use crate::{Camera, MolecularSystem};
+
+/// Smallest attenuated point, so distant atoms never vanish entirely
+const MIN_POINT_PIXELS: f32 = 1.0;
+
+/// How LOD-0 atoms, which are drawn as points, are sized on screen
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PointStyle {
+    /// The same size in pixels at any depth (`set_point_pixel_size`)
+    ConstantPixel,
+    /// The projected diameter of the atom's sphere, shrinking with distance
+    AttenuatedSphere,
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Point sizing for `get_visible_point_sizes`. Default `ConstantPixel`.
+    pub fn set_point_style(&mut self, style: PointStyle) {
+        self.point_style = style;
+    }
+
+    pub fn get_point_style(&self) -> PointStyle {
+        self.point_style
+    }
+
+    /// Diameter in pixels of `ConstantPixel` points (default 2). Non-positive
+    /// or non-finite sizes are ignored.
+    pub fn set_point_pixel_size(&mut self, size: f32) {
+        if !(size > 0.0 && size.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid point size {} - keeping {}", size, self.point_pixel_size);
+            return;
+        }
+        self.point_pixel_size = size;
+    }
+
+    /// Point diameter in pixels for each atom of the cached visible set, in
+    /// visible-list order, following `set_point_style`: `gl_PointSize` for
+    /// LOD-0 atoms, `0` for atoms at higher LODs, which are drawn as meshes.
+    /// Attenuated sizes use the vertical `fov` in radians and the viewport
+    /// height in pixels, and are at least 1 pixel. Whether points are round or
+    /// square is up to the renderer.
+    pub fn get_visible_point_sizes(&self, camera: &Camera, fov: f32, viewport_h: f32) -> Vec<f32> {
+        let camera = self.local_camera(camera);
+        let eye = (camera.x, camera.y, camera.z);
+        let forward = camera.view_basis().map(|(forward, _, _)| forward);
+        let tan_half = (fov * 0.5).tan();
+
+        self.cached_visible_atoms
+            .iter()
+            .map(|atom| {
+                if atom.lod_level != 0 {
+                    return 0.0;
+                }
+                match (self.point_style, forward) {
+                    (PointStyle::AttenuatedSphere, Some(forward)) if tan_half > 0.0 => {
+                        // Projected radius is radius / (depth * tan(fov/2)) * viewport_h / 2
+                        let depth = dot(sub((atom.x, atom.y, atom.z), eye), forward);
+                        if depth <= 0.0 {
+                            return MIN_POINT_PIXELS;
+                        }
+                        (atom.radius * viewport_h / (depth * tan_half)).max(MIN_POINT_PIXELS)
+                    }
+                    _ => self.point_pixel_size,
+                }
+            })
+            .collect()
+    }
+}
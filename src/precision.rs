@@ -0,0 +1,103 @@
+// High-precision storage: parsed coordinates are kept as f64 beside the f32
+// working copy, so far-from-origin structures cull and export without
+// losing f32's resolution
+
+use wasm_bindgen::prelude::*;
+
+use crate::MolecularSystem;
+
+/// Scale applied to coordinates as a parser reads them, keeping the unnarrowed
+/// values in high-precision mode
+#[derive(Clone)]
+pub(crate) struct InputFrame {
+    scale: f64,
+    // Every point read, in read order, when keeping them
+    precise: Option<Vec<[f64; 3]>>,
+}
+
+impl InputFrame {
+    pub(crate) fn new(scale: f32, keep_precise: bool) -> Self {
+        InputFrame { scale: scale as f64, precise: keep_precise.then(Vec::new) }
+    }
+
+    pub(crate) fn scale(&self) -> f32 {
+        self.scale as f32
+    }
+
+    // Stored coordinates of an atom read from a file
+    pub(crate) fn point(&mut self, x: f64, y: f64, z: f64) -> (f32, f32, f32) {
+        let p = [x * self.scale, y * self.scale, z * self.scale];
+        if let Some(precise) = &mut self.precise {
+            precise.push(p);
+        }
+        (p[0] as f32, p[1] as f32, p[2] as f32)
+    }
+
+    // For parsers that do not keep every atom they read: move the point just
+    // read to `slot`, or drop it
+    pub(crate) fn keep_last_at(&mut self, slot: Option<usize>) {
+        let Some(precise) = &mut self.precise else { return };
+        let Some(p) = precise.pop() else { return };
+        if let Some(slot) = slot {
+            precise[slot] = p;
+        }
+    }
+
+    // One f64 position per atom kept, or empty outside high-precision mode
+    pub(crate) fn into_precise(self) -> Vec<[f64; 3]> {
+        self.precise.unwrap_or_default()
+    }
+}
+
+impl MolecularSystem {
+    pub(crate) fn input_frame(&self) -> InputFrame {
+        InputFrame::new(self.input_scale, self.high_precision)
+    }
+
+    // Position of atom `i`, from the f64 copy when there is one
+    pub(crate) fn precise_position(&self, i: usize) -> [f64; 3] {
+        match self.precise_positions.get(i) {
+            Some(&p) => p,
+            None => {
+                let atom = &self.all_atoms[i];
+                [atom.x as f64, atom.y as f64, atom.z as f64]
+            }
+        }
+    }
+
+    // Move the f64 copy with `f`, then narrow it into the f32 working copy;
+    // false, leaving both alone, when there is no f64 copy
+    pub(crate) fn move_precise(&mut self, f: impl Fn([f64; 3]) -> [f64; 3]) -> bool {
+        if self.precise_positions.is_empty() {
+            return false;
+        }
+        for (p, atom) in self.precise_positions.iter_mut().zip(&mut self.all_atoms) {
+            *p = f(*p);
+            (atom.x, atom.y, atom.z) = (p[0] as f32, p[1] as f32, p[2] as f32);
+        }
+        true
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// For structures far from the origin, such as raw crystallographic frames:
+    /// subsequently parsed files (PDB, XYZ, GRO, MOL2, SDF, streaming) keep
+    /// every position as f64 beside the f32 copy the rest of the system uses.
+    /// Culling measures atoms from the camera in f64, so LOD and frustum
+    /// decisions do not jitter with f32's millimetre steps 10⁵ Å out,
+    /// `to_pdb` writes the f64 positions, and moves (wrapping, alignment,
+    /// merging, symmetry expansion) are applied in f64. Nothing needs
+    /// re-centering: cameras, clip planes and positions in or out stay in the
+    /// file's own coordinates.
+    ///
+    /// The f64 copy adds 24 bytes per atom, about doubling the memory the
+    /// atoms take. Returned positions and output buffers stay f32. The camera
+    /// cache compares exact camera coordinates rather than rounding them to
+    /// 0.001. Atoms already loaded are not converted, and generated systems
+    /// and fractional-coordinate loads have no f64 copy. Off by default.
+    pub fn set_high_precision(&mut self, enabled: bool) {
+        self.high_precision = enabled;
+        self.invalidate_camera_cache();
+    }
+}
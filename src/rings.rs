@@ -0,0 +1,236 @@
+// Smallest set of smallest rings from the bond graph
+//
+// Candidates are Horton cycles: for each root atom and bond (x, y), the cycle
+// formed by the shortest paths root->x and root->y plus the bond. Taken
+// shortest first, candidates independent over GF(2) (as bond sets) form a
+// minimum cycle basis, which is the SSSR.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+
+use wasm_bindgen::prelude::*;
+
+use crate::MolecularSystem;
+
+/// Separator between rings in `find_rings` output
+const RING_SEPARATOR: u32 = u32::MAX;
+
+/// `find_rings` output separator, for JS callers
+#[wasm_bindgen]
+pub fn ring_separator() -> u32 {
+    RING_SEPARATOR
+}
+
+struct Candidate {
+    atoms: Vec<u32>,
+    // Sorted bond indices
+    bonds: Vec<usize>,
+}
+
+// Symmetric difference of two sorted index lists
+fn xor_sorted(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => {
+                out.push(a[i]);
+                i += 1;
+            }
+            Ordering::Greater => {
+                out.push(b[j]);
+                j += 1;
+            }
+            Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Smallest set of smallest rings with at most `max_size` atoms, from the
+    /// current bonds. Each ring is its atom indices in ring order followed by
+    /// `ring_separator()`; rings come smallest first.
+    pub fn find_rings(&self, max_size: usize) -> Vec<u32> {
+        let n = self.all_atoms.len();
+        if max_size < 3 {
+            return Vec::new();
+        }
+        let adjacency = self.adjacency();
+        let bond_index: HashMap<(u32, u32), usize> = self.bond_pairs()
+            .iter()
+            .enumerate()
+            .map(|(i, &(a, b))| ((a.min(b), a.max(b)), i))
+            .collect();
+        let bond_of = |a: u32, b: u32| bond_index[&(a.min(b), a.max(b))];
+
+        let max_depth = max_size / 2;
+        let mut candidates = Vec::new();
+        let mut dist = vec![usize::MAX; n];
+        let mut parent = vec![u32::MAX; n];
+        let mut reached = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for root in 0..n {
+            // Depth-limited BFS tree from the root
+            for &v in &reached {
+                dist[v] = usize::MAX;
+                parent[v] = u32::MAX;
+            }
+            reached.clear();
+            dist[root] = 0;
+            reached.push(root);
+            queue.push_back(root);
+            while let Some(u) = queue.pop_front() {
+                if dist[u] == max_depth {
+                    continue;
+                }
+                for &v in adjacency.of(u) {
+                    let v = v as usize;
+                    if dist[v] == usize::MAX {
+                        dist[v] = dist[u] + 1;
+                        parent[v] = u as u32;
+                        reached.push(v);
+                        queue.push_back(v);
+                    }
+                }
+            }
+
+            let path_to_root = |mut v: usize| {
+                let mut path = vec![v as u32];
+                while v != root {
+                    v = parent[v] as usize;
+                    path.push(v as u32);
+                }
+                path
+            };
+
+            for &x in &reached {
+                for &y in adjacency.of(x) {
+                    let y = y as usize;
+                    // Each non-tree bond once, both ends within reach
+                    if y <= x || dist[y] == usize::MAX || parent[x] == y as u32 || parent[y] == x as u32 {
+                        continue;
+                    }
+                    if dist[x] + dist[y] + 1 > max_size {
+                        continue;
+                    }
+
+                    let px = path_to_root(x);
+                    let py = path_to_root(y);
+                    // The two paths may only share the root
+                    if px[..px.len() - 1].iter().any(|a| py[..py.len() - 1].contains(a)) {
+                        continue;
+                    }
+
+                    // root -> ... -> x, then y -> ... -> (before root)
+                    let mut atoms: Vec<u32> = px.iter().rev().copied().collect();
+                    atoms.extend_from_slice(&py[..py.len() - 1]);
+                    let mut bonds: Vec<usize> = atoms
+                        .iter()
+                        .zip(atoms.iter().cycle().skip(1))
+                        .map(|(&a, &b)| bond_of(a, b))
+                        .collect();
+                    bonds.sort_unstable();
+                    candidates.push(Candidate { atoms, bonds });
+                }
+            }
+        }
+
+        // Greedy independence test by Gaussian elimination over GF(2), keyed by
+        // each basis vector's highest bond index
+        candidates.sort_by_key(|c| c.atoms.len());
+        let mut basis: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut rings = Vec::new();
+        for candidate in candidates {
+            let mut vector = candidate.bonds.clone();
+            while let Some(pivot) = vector.last().copied() {
+                match basis.get(&pivot) {
+                    Some(row) => vector = xor_sorted(&vector, row),
+                    None => break,
+                }
+            }
+            if let Some(&pivot) = vector.last() {
+                basis.insert(pivot, vector);
+                rings.extend_from_slice(&candidate.atoms);
+                rings.push(RING_SEPARATOR);
+            }
+        }
+
+        rings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    use super::RING_SEPARATOR;
+
+    #[test]
+    fn benzene_has_one_six_membered_ring() {
+        // Carbon hexagon with 1.39 A sides, bonded by distance
+        let mut xyz = String::from("6\nbenzene\n");
+        for k in 0..6 {
+            let angle = k as f32 * std::f32::consts::PI / 3.0;
+            xyz += &format!("C {} {} 0.0\n", 1.39 * angle.cos(), 1.39 * angle.sin());
+        }
+        let mut system = MolecularSystem::new();
+        system.load_xyz(&xyz).unwrap();
+        assert_eq!(system.detect_bonds(1.2), 6);
+
+        let rings = system.find_rings(8);
+        assert_eq!(rings.len(), 7);
+        assert_eq!(rings[6], RING_SEPARATOR);
+        let mut atoms = rings[..6].to_vec();
+        // Ring order: each atom is bonded to the next
+        for (a, b) in atoms.iter().zip(atoms.iter().cycle().skip(1)) {
+            assert!(system.bond_pairs().contains(&(*a.min(b), *a.max(b))), "{}-{} is not a bond", a, b);
+        }
+        atoms.sort_unstable();
+        assert_eq!(atoms, [0, 1, 2, 3, 4, 5]);
+        assert!(system.find_rings(5).is_empty());
+    }
+
+    #[test]
+    fn naphthalene_has_two_six_membered_rings() {
+        // Two 1.4 A hexagons sharing their vertical edge: atoms 0-5 are the
+        // left ring, 6-9 the rest of the right one, which also holds 0 and 5
+        let side = 1.4f32;
+        let corner = |cx: f32, degrees: f32| {
+            let angle = degrees.to_radians();
+            format!("C {} {} 0.0\n", cx + side * angle.cos(), side * angle.sin())
+        };
+        let mut xyz = String::from("10\nnaphthalene\n");
+        for k in 0..6 {
+            xyz += &corner(0.0, 30.0 + 60.0 * k as f32);
+        }
+        let right = 2.0 * side * 30f32.to_radians().cos();
+        for degrees in [30.0, 90.0, 270.0, 330.0] {
+            xyz += &corner(right, degrees);
+        }
+        let mut system = MolecularSystem::new();
+        system.load_xyz(&xyz).unwrap();
+        assert_eq!(system.detect_bonds(1.2), 11);
+
+        let rings = system.find_rings(10);
+        let mut found: Vec<Vec<u32>> = rings
+            .split(|&i| i == RING_SEPARATOR)
+            .filter(|ring| !ring.is_empty())
+            .map(|ring| {
+                let mut ring = ring.to_vec();
+                ring.sort_unstable();
+                ring
+            })
+            .collect();
+        found.sort();
+        // The ten-membered perimeter is not part of the smallest set
+        assert_eq!(found, [vec![0, 1, 2, 3, 4, 5], vec![0, 5, 6, 7, 8, 9]]);
+    }
+}
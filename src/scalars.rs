@@ -0,0 +1,49 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::{length, sub};
+use crate::MolecularSystem;
+
+/// Per-atom quantity to normalize a color map over, for `scalar_range`
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScalarChannel {
+    /// PDB temperature factor (Å²)
+    BFactor,
+    Occupancy,
+    /// Velocity magnitude, from `set_velocities`
+    Speed,
+    /// Distance from the eye of the last cull, over the cached visible set
+    Depth,
+}
+
+// [min, max] of the values, [0, 0] when there are none
+fn range(values: impl Iterator<Item = f32>) -> Vec<f32> {
+    let (min, max) = values
+        .filter(|v| v.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if min > max {
+        vec![0.0, 0.0]
+    } else {
+        vec![min, max]
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// `[min, max]` of a per-atom channel, for normalizing color maps in one
+    /// pass instead of scanning a returned buffer. B-factor, occupancy and speed
+    /// cover every stored atom; depth covers the atoms of the last cull, as
+    /// returned by `get_visible_atoms`. `[0, 0]` when the channel has no data:
+    /// no B-factors in the file, no velocities set, or no cull yet.
+    pub fn scalar_range(&self, channel: ScalarChannel) -> Vec<f32> {
+        match channel {
+            ScalarChannel::BFactor => range(self.b_factors.iter().copied()),
+            ScalarChannel::Occupancy => range(self.all_atoms.iter().map(|atom| atom.occupancy)),
+            ScalarChannel::Speed => range(self.velocities.iter().map(|&v| length(v))),
+            ScalarChannel::Depth => match self.last_cull_eye {
+                Some(eye) => range(self.cached_visible_atoms.iter().map(|atom| length(sub((atom.x, atom.y, atom.z), eye)))),
+                None => vec![0.0, 0.0],
+            },
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use wasm_bindgen::prelude::*;
+
+use crate::bonds::canonical_bonds;
+use crate::pdb::ParsedAtoms;
+use crate::perf::{elapsed_ms, now_ms};
+use crate::precision::InputFrame;
+use crate::warnings::LoadWarnings;
+use crate::{MolecularSystem, RawAtom};
+
+// Fixed-width molfile column, 0-based start and width
+fn field(line: &str, start: usize, width: usize) -> &str {
+    line.get(start..(start + width).min(line.len())).unwrap_or("").trim()
+}
+
+/// Line-at-a-time SDF / MDL molfile (V2000) reader: three header lines, the
+/// counts line, then the atom and bond blocks. Only the first record is read.
+pub(crate) struct SdfParser {
+    // Applied to every coordinate as it is read
+    frame: InputFrame,
+    atoms: Vec<RawAtom>,
+    bonds: Vec<(u32, u32)>,
+    // Atom and bond counts from the counts line
+    expected: Option<(usize, usize)>,
+    bonds_read: usize,
+    lines_seen: usize,
+    warnings: LoadWarnings,
+}
+
+impl SdfParser {
+    pub(crate) fn new(frame: InputFrame) -> Self {
+        SdfParser {
+            frame,
+            atoms: Vec::new(),
+            bonds: Vec::new(),
+            expected: None,
+            bonds_read: 0,
+            lines_seen: 0,
+            warnings: LoadWarnings::default(),
+        }
+    }
+
+    pub(crate) fn parse_line(&mut self, line: &str, line_no: usize) -> Result<(), String> {
+        self.lines_seen += 1;
+
+        // Name, program and comment lines
+        if self.lines_seen <= 3 {
+            return Ok(());
+        }
+        let (atom_count, bond_count) = match self.expected {
+            Some(expected) => expected,
+            None => {
+                if line.contains("V3000") {
+                    return Err(format!("SDF line {}: V3000 molfiles are not supported", line_no));
+                }
+                let count = |start| field(line, start, 3).parse::<usize>()
+                    .map_err(|_| format!("SDF line {}: expected counts line", line_no));
+                let expected = (count(0)?, count(3)?);
+                self.expected = Some(expected);
+                self.atoms.reserve(expected.0);
+                return Ok(());
+            }
+        };
+
+        if self.atoms.len() < atom_count {
+            let coordinate = |k: usize| -> Result<f64, String> {
+                field(line, k * 10, 10)
+                    .parse::<f64>()
+                    .map_err(|_| format!("SDF line {}: invalid coordinate", line_no))
+            };
+            let (x, y, z) = (coordinate(0)?, coordinate(1)?, coordinate(2)?);
+            let (x, y, z) = self.frame.point(x, y, z);
+            let element = self.warnings.element(field(line, 31, 3));
+            self.atoms.push(RawAtom { x, y, z, element, occupancy: 1.0 });
+        } else if self.bonds_read < bond_count {
+            // 1-based atom numbers
+            let atom = |start| -> Result<u32, String> {
+                match field(line, start, 3).parse::<usize>() {
+                    Ok(n) if (1..=atom_count).contains(&n) => Ok(n as u32 - 1),
+                    _ => Err(format!("SDF line {}: bond to unknown atom", line_no)),
+                }
+            };
+            let (a, b) = (atom(0)?, atom(3)?);
+            if a == b {
+                return Err(format!("SDF line {}: bond joins an atom to itself", line_no));
+            }
+            self.bonds.push((a, b));
+            self.bonds_read += 1;
+        }
+        // The properties block, `M  END` and any further records are ignored
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<ParsedAtoms, String> {
+        let (atom_count, bond_count) = self.expected.ok_or_else(|| "SDF has no counts line".to_string())?;
+        if self.atoms.len() != atom_count {
+            return Err(format!("SDF declares {} atoms but {} were read", atom_count, self.atoms.len()));
+        }
+        if self.bonds_read != bond_count {
+            return Err(format!("SDF declares {} bonds but {} were read", bond_count, self.bonds_read));
+        }
+        Ok(ParsedAtoms {
+            warnings: self.warnings.into_messages(),
+            bonds: canonical_bonds(self.bonds),
+            precise: self.frame.into_precise(),
+            ..self.atoms.into()
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Load atoms and bonds from SDF or MDL molfile (V2000) text, replacing the
+    /// current data. The bond block becomes explicit bonds
+    /// (`has_explicit_bonds`); bond orders and charges are not kept. Only the
+    /// first record of a multi-record SDF is read.
+    pub fn load_sdf(&mut self, text: &str) -> Result<usize, JsValue> {
+        log!(self, LOG_INFO, "Parsing SDF ({} bytes)...", text.len());
+        let start = now_ms();
+
+        let mut parser = SdfParser::new(self.input_frame());
+        for (line_no, line) in text.lines().enumerate() {
+            parser.parse_line(line, line_no + 1).map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        self.adopt_parsed_atoms(parser.finish().map_err(|e| JsValue::from_str(&e))?);
+
+        self.timings.load_ms = elapsed_ms(start);
+        log!(self, LOG_INFO, "Loaded {} atoms and {} bonds from SDF", self.all_atoms.len(), self.bonds.len());
+        Ok(self.all_atoms.len())
+    }
+}
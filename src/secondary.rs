@@ -0,0 +1,177 @@
+// Helix / sheet / coil assignment from the C-alpha trace. Only alpha carbons
+// are kept from PDB files, so the backbone hydrogen bonds DSSP looks for are
+// inferred from C-alpha spacing: an i -> i+4 bond closes a tight helical turn,
+// and paired strands keep their alpha carbons about 5 Å apart.
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::SpatialGrid;
+use crate::math::{cross, dot, length, sub, Vec3};
+use crate::{MolecularSystem, RawAtom};
+
+/// Per-residue secondary structure codes of `get_residue_secondary_structure`
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SecondaryStructure {
+    Coil = 0,
+    /// Alpha helix
+    Helix = 1,
+    /// Beta strand paired into a sheet
+    Sheet = 2,
+}
+
+// C-alpha i -> i+3 and i -> i+4 spacing of a helical turn, in Å
+const HELIX_TURN_3: (f32, f32) = (4.2, 5.9);
+const HELIX_TURN_4: (f32, f32) = (5.0, 7.0);
+// Right-handed helices have a C-alpha dihedral near +50°
+const HELIX_DIHEDRAL: (f32, f32) = (20.0, 90.0);
+
+// Paired strand residues sit within this of each other, and so do their
+// sequence neighbors on the matching side
+const BRIDGE_DISTANCE: f32 = 5.5;
+const BRIDGE_FLANK_DISTANCE: f32 = 6.5;
+// Extended residues span at least this from i-1 to i+1 (helices about 5.4 Å)
+const EXTENDED_SPAN: f32 = 6.0;
+
+fn within(value: f32, (low, high): (f32, f32)) -> bool {
+    (low..=high).contains(&value)
+}
+
+// Dihedral angle p0-p1-p2-p3 in degrees, positive clockwise looking down p1 -> p2
+fn dihedral(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3) -> f32 {
+    let (b1, b2, b3) = (sub(p1, p0), sub(p2, p1), sub(p3, p2));
+    let (n1, n2) = (cross(b1, b2), cross(b2, b3));
+    (length(b2) * dot(b1, n2)).atan2(dot(n1, n2)).to_degrees()
+}
+
+// Codes for one chain's trace: helices where two consecutive helical turns
+// start, as in DSSP's 4-helix rule
+fn assign_helices(trace: &[Vec3], codes: &mut [SecondaryStructure]) {
+    let d = |i: usize, j: usize| length(sub(trace[i], trace[j]));
+    let turns: Vec<bool> = (0..trace.len().saturating_sub(4))
+        .map(|i| {
+            within(d(i, i + 3), HELIX_TURN_3)
+                && within(d(i, i + 4), HELIX_TURN_4)
+                && within(dihedral(trace[i], trace[i + 1], trace[i + 2], trace[i + 3]), HELIX_DIHEDRAL)
+        })
+        .collect();
+    for i in 1..turns.len() {
+        if turns[i - 1] && turns[i] {
+            for code in &mut codes[i..i + 4] {
+                *code = SecondaryStructure::Helix;
+            }
+        }
+    }
+}
+
+impl MolecularSystem {
+    // Label each residue of each chain (`ca_chain_indices` order)
+    fn secondary_structure_codes(&self, chains: &[Vec<usize>]) -> Vec<Vec<SecondaryStructure>> {
+        let traces: Vec<Vec<Vec3>> = chains.iter()
+            .map(|chain| chain.iter().map(|&i| {
+                let atom = &self.all_atoms[i];
+                (atom.x, atom.y, atom.z)
+            }).collect())
+            .collect();
+        let mut codes: Vec<Vec<SecondaryStructure>> = traces.iter()
+            .map(|trace| vec![SecondaryStructure::Coil; trace.len()])
+            .collect();
+        for (trace, codes) in traces.iter().zip(&mut codes) {
+            assign_helices(trace, codes);
+        }
+
+        // Bridge partners: extended, non-helical interior residues
+        let residues: Vec<(usize, usize)> = traces.iter().enumerate()
+            .flat_map(|(c, trace)| (0..trace.len()).map(move |i| (c, i)))
+            .collect();
+        let candidate = |(c, i): (usize, usize)| {
+            let trace = &traces[c];
+            i > 0 && i + 1 < trace.len()
+                && codes[c][i] == SecondaryStructure::Coil
+                && length(sub(trace[i + 1], trace[i - 1])) >= EXTENDED_SPAN
+        };
+        let points: Vec<RawAtom> = residues.iter()
+            .map(|&(c, i)| {
+                let (x, y, z) = traces[c][i];
+                RawAtom { x, y, z, element: 0, occupancy: 1.0 }
+            })
+            .collect();
+        let grid = SpatialGrid::build(&points, BRIDGE_FLANK_DISTANCE);
+        let near = |c: usize, i: usize, d: usize, j: usize| {
+            length(sub(traces[c][i], traces[d][j])) <= BRIDGE_FLANK_DISTANCE
+        };
+
+        let mut bridged: Vec<Vec<bool>> = traces.iter().map(|trace| vec![false; trace.len()]).collect();
+        for (r, &(c, i)) in residues.iter().enumerate() {
+            if !candidate((c, i)) {
+                continue;
+            }
+            let p = traces[c][i];
+            grid.for_each_within(&points, p, BRIDGE_DISTANCE, |other, _| {
+                let (d, j) = residues[other];
+                // Each pair once, and never a residue with its own turn
+                if other <= r || (c == d && j < i + 3) || !candidate((d, j)) {
+                    return;
+                }
+                let parallel = near(c, i - 1, d, j - 1) && near(c, i + 1, d, j + 1);
+                let antiparallel = near(c, i - 1, d, j + 1) && near(c, i + 1, d, j - 1);
+                if parallel || antiparallel {
+                    bridged[c][i] = true;
+                    bridged[d][j] = true;
+                }
+            });
+        }
+
+        // A lone bridge is not a sheet: a neighbor along the strand must pair too
+        for (bridged, codes) in bridged.iter().zip(&mut codes) {
+            for i in 0..bridged.len() {
+                let ladder = (i > 0 && bridged[i - 1]) || bridged.get(i + 1) == Some(&true);
+                if bridged[i] && ladder {
+                    codes[i] = SecondaryStructure::Sheet;
+                }
+            }
+        }
+        codes
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Label every protein residue helix, sheet or coil from its C-alpha
+    /// trace (`get_ca_trace`), a simplified DSSP. Backbone hydrogen bonds are
+    /// inferred from C-alpha geometry, since only alpha carbons are kept:
+    /// two consecutive helical turns (i -> i+3 about 5 Å, i -> i+4 about 6 Å,
+    /// right-handed) make a helix, and extended residues pairing with
+    /// another strand, parallel or antiparallel, in runs of two or more make
+    /// a sheet. Expect boundaries to differ from DSSP by a residue or so.
+    ///
+    /// Labels are kept per alpha carbon, so they follow `deduplicate`,
+    /// `shuffle_storage` and `extract_visible`; call again after atoms move.
+    /// Loading new atoms clears them. Returns the number of residues labeled.
+    pub fn assign_secondary_structure(&mut self) -> usize {
+        let chains = self.ca_chain_indices();
+        let codes = self.secondary_structure_codes(&chains);
+        self.secondary_structure = vec![SecondaryStructure::Coil as u8; self.all_atoms.len()];
+        for (chain, codes) in chains.iter().zip(&codes) {
+            for (&i, &code) in chain.iter().zip(codes) {
+                self.secondary_structure[i] = code as u8;
+            }
+        }
+        let residues = chains.iter().map(Vec::len).sum::<usize>();
+        let helices = self.secondary_structure.iter().filter(|&&c| c == SecondaryStructure::Helix as u8).count();
+        let sheets = self.secondary_structure.iter().filter(|&&c| c == SecondaryStructure::Sheet as u8).count();
+        log!(self, LOG_INFO, "Assigned secondary structure: {} residues, {} helix, {} sheet", residues, helices, sheets);
+        residues
+    }
+
+    /// One `SecondaryStructure` code per residue, in `get_ca_trace` order
+    /// (chain separators aside). All coil until `assign_secondary_structure`
+    /// runs; empty without a protein backbone.
+    pub fn get_residue_secondary_structure(&self) -> Vec<u32> {
+        self.ca_chain_indices()
+            .iter()
+            .flatten()
+            .map(|&i| self.secondary_structure.get(i).map_or(SecondaryStructure::Coil as u32, |&c| c as u32))
+            .collect()
+    }
+}
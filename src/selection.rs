@@ -0,0 +1,46 @@
+use wasm_bindgen::prelude::*;
+
+use crate::MolecularSystem;
+
+impl MolecularSystem {
+    // Selected and flagged to bypass culling and distance LOD
+    pub(crate) fn pinned(&self, index: usize) -> bool {
+        self.force_selection_high_lod && !self.selected.is_empty() && self.selected[index]
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Select the atoms at `indices` (storage order), replacing any previous
+    /// selection; an empty slice clears it. Loading new atoms also clears it.
+    pub fn set_selection(&mut self, indices: &[u32]) -> Result<(), JsValue> {
+        let n = self.all_atoms.len();
+        if let Some(&bad) = indices.iter().find(|&&i| i as usize >= n) {
+            return Err(JsValue::from_str(&format!("selection index {} out of range for {} atoms", bad, n)));
+        }
+        self.selected.clear();
+        if !indices.is_empty() {
+            self.selected.resize(n, false);
+            for &i in indices {
+                self.selected[i as usize] = true;
+            }
+        }
+        self.invalidate_camera_cache();
+        Ok(())
+    }
+
+    /// Storage indices of the selected atoms, ascending
+    pub fn get_selection(&self) -> Vec<u32> {
+        (0..self.selected.len() as u32).filter(|&i| self.selected[i as usize]).collect()
+    }
+
+    /// Keep selected atoms visible at the top LOD level however far away they
+    /// are: they skip frustum and distance culling, `set_visible_atom_cap` and
+    /// `set_max_high_lod_atoms` keep them ahead of every other atom, and
+    /// chunked culling still finds them in rejected chunks. Atoms of hidden
+    /// elements stay hidden. Off by default.
+    pub fn set_force_selection_high_lod(&mut self, enabled: bool) {
+        self.force_selection_high_lod = enabled;
+        self.invalidate_camera_cache();
+    }
+}
@@ -0,0 +1,64 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::SplitMix64;
+use crate::MolecularSystem;
+
+// Gather `values[order[i]]` into slot `i`; empty side data stays empty
+fn permute<T: Copy>(values: &mut Vec<T>, order: &[usize]) {
+    if !values.is_empty() {
+        *values = order.iter().map(|&i| values[i]).collect();
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Reorder the atoms by a seeded random permutation, so any prefix of the
+    /// storage order — a partial upload, `next_visible_batch` ties, a
+    /// per-thread slice — is a spatially uniform sample instead of one corner
+    /// of the structure. The same seed and atoms always give the same order.
+    ///
+    /// Bonds, velocities, forces, displacement parameters, B-factors and the
+    /// selection move with their atoms, and `get_original_indices` maps each
+    /// new position back to the loaded atom. Backbone traces still follow file
+    /// order.
+    pub fn shuffle_storage(&mut self, seed: u64) {
+        let n = self.all_atoms.len();
+        if n < 2 {
+            return;
+        }
+
+        // Fisher-Yates: order[i] is the old index of the atom that lands in slot i
+        let mut rng = SplitMix64::new(seed);
+        let mut order: Vec<usize> = (0..n).collect();
+        for i in (1..n).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            order.swap(i, j);
+        }
+        let mut new_index = vec![0u32; n];
+        for (slot, &old) in order.iter().enumerate() {
+            new_index[old] = slot as u32;
+        }
+
+        self.original_indices = order.iter().map(|&i| self.original_index(i)).collect();
+        permute(&mut self.all_atoms, &order);
+        permute(&mut self.precise_positions, &order);
+        permute(&mut self.velocities, &order);
+        permute(&mut self.forces, &order);
+        permute(&mut self.adps, &order);
+        permute(&mut self.b_factors, &order);
+        permute(&mut self.ca_chains, &order);
+        permute(&mut self.selected, &order);
+        permute(&mut self.secondary_structure, &order);
+        permute(&mut self.importance, &order);
+
+        for bond in &mut self.bonds {
+            let (a, b) = (new_index[bond.0 as usize], new_index[bond.1 as usize]);
+            *bond = (a.min(b), a.max(b));
+        }
+        self.bonds.sort_unstable();
+        self.indices_changed();
+        self.positions_changed();
+
+        log!(self, LOG_INFO, "Shuffled storage order of {} atoms (seed {})", n, seed);
+    }
+}
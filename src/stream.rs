@@ -0,0 +1,142 @@
+use wasm_bindgen::prelude::*;
+
+use crate::pdb::{AltLocPolicy, ParsedAtoms, PdbParser};
+use crate::perf::{elapsed_ms, now_ms};
+use crate::precision::InputFrame;
+use crate::xyz::XyzParser;
+use crate::MolecularSystem;
+
+enum FormatParser {
+    // Format is sniffed from the first non-blank line
+    Undetected,
+    Pdb(PdbParser),
+    Xyz(XyzParser),
+}
+
+/// In-progress chunked load. Only the trailing partial line of the last
+/// chunk is buffered; complete records go straight into the parser.
+pub(crate) struct StreamingLoad {
+    parser: FormatParser,
+    altloc_policy: AltLocPolicy,
+    input_frame: InputFrame,
+    chain_filter: Vec<char>,
+    partial_line: String,
+    line_no: usize,
+    bytes_read: usize,
+    // Time spent parsing across all chunks
+    parse_ms: f32,
+}
+
+impl StreamingLoad {
+    pub(crate) fn new(altloc_policy: AltLocPolicy, input_frame: InputFrame, chain_filter: &[char]) -> Self {
+        StreamingLoad {
+            parser: FormatParser::Undetected,
+            altloc_policy,
+            input_frame,
+            chain_filter: chain_filter.to_vec(),
+            partial_line: String::new(),
+            line_no: 0,
+            bytes_read: 0,
+            parse_ms: 0.0,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        self.line_no += 1;
+
+        if let FormatParser::Undetected = self.parser {
+            if line.trim().is_empty() {
+                return Ok(());
+            }
+            // XYZ files open with a bare atom count
+            self.parser = if line.trim().parse::<usize>().is_ok() {
+                FormatParser::Xyz(XyzParser::new(self.input_frame.clone()))
+            } else {
+                FormatParser::Pdb(PdbParser::new(self.altloc_policy, self.input_frame.clone(), &self.chain_filter))
+            };
+        }
+
+        match &mut self.parser {
+            FormatParser::Undetected => Ok(()),
+            FormatParser::Pdb(parser) => parser.parse_line(line, self.line_no),
+            FormatParser::Xyz(parser) => parser.parse_line(line, self.line_no),
+        }
+    }
+
+    pub(crate) fn push_chunk(&mut self, text: &str) -> Result<(), String> {
+        let start = now_ms();
+        let result = self.push_lines(text);
+        self.parse_ms += elapsed_ms(start);
+        result
+    }
+
+    fn push_lines(&mut self, text: &str) -> Result<(), String> {
+        self.bytes_read += text.len();
+        self.partial_line.push_str(text);
+
+        // Everything up to the last newline is complete; the rest waits for the next chunk
+        let complete = match self.partial_line.rfind('\n') {
+            Some(pos) => pos + 1,
+            None => return Ok(()),
+        };
+        let buffered = std::mem::take(&mut self.partial_line);
+        for line in buffered[..complete].lines() {
+            self.parse_line(line)?;
+        }
+        self.partial_line.push_str(&buffered[complete..]);
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> Result<ParsedAtoms, String> {
+        let last_line = std::mem::take(&mut self.partial_line);
+        if !last_line.is_empty() {
+            self.parse_line(last_line.trim_end_matches('\r'))?;
+        }
+
+        match self.parser {
+            FormatParser::Undetected => Ok(ParsedAtoms::default()),
+            FormatParser::Pdb(parser) => Ok(parser.finish()),
+            FormatParser::Xyz(parser) => parser.finish(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Start a chunked load of PDB or XYZ text. Current atoms stay in place until
+    /// `finish_streaming_load` succeeds.
+    pub fn begin_streaming_load(&mut self) {
+        if self.streaming_load.is_some() {
+            log!(self, LOG_INFO, "Discarding unfinished streaming load");
+        }
+        self.streaming_load = Some(StreamingLoad::new(self.altloc_policy, self.input_frame(), &self.chain_filter));
+    }
+
+    /// Feed the next piece of the file. Records may be split across chunks.
+    /// A parse error aborts the stream; call `begin_streaming_load` to retry.
+    pub fn push_chunk(&mut self, text: &str) -> Result<(), JsValue> {
+        let stream = self.streaming_load.as_mut()
+            .ok_or_else(|| JsValue::from_str("push_chunk called without begin_streaming_load"))?;
+
+        if let Err(e) = stream.push_chunk(text) {
+            self.streaming_load = None;
+            return Err(JsValue::from_str(&e));
+        }
+        Ok(())
+    }
+
+    /// Parse any trailing partial record and replace the current atoms with the streamed ones
+    pub fn finish_streaming_load(&mut self) -> Result<usize, JsValue> {
+        let stream = self.streaming_load.take()
+            .ok_or_else(|| JsValue::from_str("finish_streaming_load called without begin_streaming_load"))?;
+
+        let start = now_ms();
+        let (bytes_read, parse_ms) = (stream.bytes_read, stream.parse_ms);
+        let parsed = stream.finish().map_err(|e| JsValue::from_str(&e))?;
+        self.adopt_parsed_atoms(parsed);
+        self.timings.load_ms = parse_ms + elapsed_ms(start);
+
+        log!(self, LOG_INFO, "Streamed {} atoms from {} bytes", self.all_atoms.len(), bytes_read);
+        Ok(self.all_atoms.len())
+    }
+}
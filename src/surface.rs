@@ -0,0 +1,76 @@
+// Point queries against the union of atom spheres
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{length, sub};
+use crate::MolecularSystem;
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Signed distance from `(x, y, z)` to the surface of the union of atom
+    /// spheres (display radii, see `set_element_radius`): positive outside,
+    /// negative inside an atom, so an atom's center gives minus its radius.
+    /// Hidden elements still count. Searches the coarse grid outwards from
+    /// the point, so it stays cheap near the structure. Infinite when there
+    /// are no atoms; NaN for a non-finite point.
+    pub fn signed_distance(&self, x: f32, y: f32, z: f32) -> f32 {
+        if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+            log!(self, LOG_ERRORS, "Invalid signed_distance point ({}, {}, {})", x, y, z);
+            return f32::NAN;
+        }
+        let sphere = self.bounding_sphere(false);
+        let [cx, cy, cz, bounds_radius] = sphere[..] else { return f32::INFINITY };
+
+        let p = (x, y, z);
+        let grid = self.coarse_grid();
+        let max_radius = self.max_element_radius();
+        // Every atom center lies within this of `p`
+        let farthest = length(sub(p, (cx, cy, cz))) + bounds_radius;
+        let mut reach = grid.cell_size();
+        loop {
+            let mut best = f32::INFINITY;
+            if grid.overlaps(p, reach) {
+                grid.for_each_within(&self.all_atoms, p, reach, |index, d2| {
+                    let radius = self.element_radius(self.all_atoms[index].element);
+                    best = best.min(d2.sqrt() - radius);
+                });
+            }
+            // Atoms beyond `reach` are at least `reach - max_radius` away
+            if best <= reach - max_radius || reach >= farthest {
+                return best;
+            }
+            reach *= 2.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MolecularSystem;
+
+    #[test]
+    fn atom_center_is_minus_its_radius_inside() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("2\n\nC 0.0 0.0 0.0\nH 10.0 0.0 0.0\n").unwrap();
+        system.set_element_radius(4, 1.7);
+
+        let carbon = system.element_radius(4);
+        let hydrogen = system.element_radius(system.all_atoms[1].element);
+        assert!((system.signed_distance(0.0, 0.0, 0.0) + carbon).abs() < 1e-5);
+        assert!((system.signed_distance(10.0, 0.0, 0.0) + hydrogen).abs() < 1e-5);
+        // Outside, the nearest surface wins
+        assert!((system.signed_distance(0.0, 5.0, 0.0) - (5.0 - carbon)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn distant_point_matches_the_nearest_sphere() {
+        let mut system = MolecularSystem::new();
+        system.load_atoms_from_file(500);
+        let (x, y, z) = (60.0, -35.0, 12.5);
+
+        let expected = system.all_atoms.iter()
+            .map(|a| ((a.x - x).powi(2) + (a.y - y).powi(2) + (a.z - z).powi(2)).sqrt() - system.element_radius(a.element))
+            .fold(f32::INFINITY, f32::min);
+        assert!((system.signed_distance(x, y, z) - expected).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,93 @@
+// CPU splatting of the visible atoms into a small RGBA image, for previews
+// where no GPU context exists
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{length, sub};
+use crate::{Camera, MolecularSystem, Projection};
+
+/// Largest thumbnail edge in pixels
+const MAX_THUMBNAIL_SIZE: u32 = 4096;
+
+/// Near plane for the thumbnail cull
+const THUMBNAIL_NEAR: f32 = 0.1;
+
+/// Brightness at a disc's rim; the centre is at full element color
+const RIM_SHADE: f32 = 0.4;
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Render the atoms visible from `camera` into a `width` x `height` image:
+    /// row-major RGBA bytes, top row first, transparent background. Each atom
+    /// is a disc in its element color, darkened towards the rim, depth-tested
+    /// per pixel. Meant for thumbnails in workers or Node, not interactive
+    /// rendering; bonds and impostors are not drawn.
+    ///
+    /// Culls like `get_visible_atoms` (vertical `fov` in radians) with the far
+    /// plane placed so no atom is distance-culled, and replaces the cached
+    /// visible set.
+    /// Sizes above 4096 pixels per edge are rejected with an empty result.
+    pub fn render_thumbnail(&mut self, camera: &Camera, fov: f32, aspect: f32, width: u32, height: u32) -> Vec<u8> {
+        if width == 0 || height == 0 || width > MAX_THUMBNAIL_SIZE || height > MAX_THUMBNAIL_SIZE {
+            log!(self, LOG_ERRORS, "Invalid thumbnail size {}x{} - must be 1 to {} pixels per edge",
+                 width, height, MAX_THUMBNAIL_SIZE);
+            return Vec::new();
+        }
+        let (w, h) = (width as usize, height as usize);
+        let mut pixels = vec![0u8; w * h * 4];
+        let bounds = self.bounding_sphere(false);
+        let [cx, cy, cz, radius] = bounds[..] else { return pixels };
+
+        let local = self.local_camera(camera);
+        // Far enough that the distance cull keeps the whole structure
+        let back = length(sub((cx, cy, cz), (local.x, local.y, local.z))) + radius + THUMBNAIL_NEAR;
+        let far = back / self.far_cull_fraction;
+        self.refresh_visibility(camera, &Projection::new(fov, aspect, THUMBNAIL_NEAR, far));
+
+        // Nearest first, so most hidden pixels fail the depth test early
+        let tan_half = (fov * 0.5).tan();
+        let mut splats: Vec<(f32, f32, f32, f32, f32, [f32; 3])> = self.cached_visible_atoms
+            .iter()
+            .filter_map(|atom| {
+                let (x, y, depth) = local.project_ndc((atom.x, atom.y, atom.z), fov, aspect)?;
+                let px = (x + 1.0) * 0.5 * width as f32;
+                let py = (1.0 - y) * 0.5 * height as f32;
+                let pixel_radius = atom.radius / (depth * tan_half) * height as f32 * 0.5;
+                Some((depth, px, py, pixel_radius, atom.radius, self.element_color(atom.element)))
+            })
+            .collect();
+        splats.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut depths = vec![f32::INFINITY; w * h];
+        for (depth, px, py, pixel_radius, radius, color) in splats {
+            let x0 = (px - pixel_radius).floor().max(0.0) as usize;
+            let y0 = (py - pixel_radius).floor().max(0.0) as usize;
+            let x1 = ((px + pixel_radius).ceil().max(0.0) as usize).min(w);
+            let y1 = ((py + pixel_radius).ceil().max(0.0) as usize).min(h);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let dx = (x as f32 + 0.5 - px) / pixel_radius;
+                    let dy = (y as f32 + 0.5 - py) / pixel_radius;
+                    let d2 = dx * dx + dy * dy;
+                    if d2 > 1.0 {
+                        continue;
+                    }
+                    // Front surface of the sphere under this pixel
+                    let nz = (1.0 - d2).sqrt();
+                    let z = depth - radius * nz;
+                    let i = y * w + x;
+                    if z >= depths[i] {
+                        continue;
+                    }
+                    depths[i] = z;
+                    let shade = RIM_SHADE + (1.0 - RIM_SHADE) * nz;
+                    for (channel, &c) in pixels[i * 4..i * 4 + 3].iter_mut().zip(&color) {
+                        *channel = (c * shade * 255.0).round().clamp(0.0, 255.0) as u8;
+                    }
+                    pixels[i * 4 + 3] = 255;
+                }
+            }
+        }
+        pixels
+    }
+}
@@ -0,0 +1,47 @@
+use wasm_bindgen::prelude::*;
+
+use crate::math::length;
+use crate::MolecularSystem;
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Per-atom velocities as flat `[vx, vy, vz, ...]` in storage order, one
+    /// triple per atom. An empty slice clears them. Loading new atoms also clears
+    /// them, since the indices no longer line up.
+    pub fn set_velocities(&mut self, v: &[f32]) -> Result<(), JsValue> {
+        if !v.is_empty() && v.len() != self.all_atoms.len() * 3 {
+            return Err(JsValue::from_str(&format!(
+                "expected {} velocity components for {} atoms, got {}",
+                self.all_atoms.len() * 3, self.all_atoms.len(), v.len()
+            )));
+        }
+        self.velocities = v.chunks_exact(3).map(|c| (c[0], c[1], c[2])).collect();
+        Ok(())
+    }
+
+    /// Per-atom forces as flat `[fx, fy, fz, ...]` in storage order, in the
+    /// same form as `set_velocities`: an empty slice clears them, and loading
+    /// new atoms clears them too.
+    pub fn set_forces(&mut self, f: &[f32]) -> Result<(), JsValue> {
+        if !f.is_empty() && f.len() != self.all_atoms.len() * 3 {
+            return Err(JsValue::from_str(&format!(
+                "expected {} force components for {} atoms, got {}",
+                self.all_atoms.len() * 3, self.all_atoms.len(), f.len()
+            )));
+        }
+        self.forces = f.chunks_exact(3).map(|c| (c[0], c[1], c[2])).collect();
+        Ok(())
+    }
+
+    /// Speed of each atom of the cached visible set, in visible-list order, for
+    /// color-by-velocity. Empty when no velocities are set.
+    pub fn get_visible_atom_speeds(&self) -> Vec<f32> {
+        if self.velocities.is_empty() {
+            return Vec::new();
+        }
+        self.cached_visible_indices
+            .iter()
+            .map(|&i| length(self.velocities[i as usize]))
+            .collect()
+    }
+}
@@ -0,0 +1,90 @@
+// Recoverable oddities met while parsing, reported after the load succeeds
+
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+use crate::elements::{element_from_symbol, element_info, DEFAULT_ELEMENT};
+use crate::MolecularSystem;
+
+/// Distinct unknown symbols named in the warning; further ones are only counted
+const MAX_LISTED_SYMBOLS: usize = 8;
+
+#[derive(Default)]
+pub(crate) struct LoadWarnings {
+    unknown_elements: usize,
+    unknown_symbols: BTreeSet<String>,
+    missing_occupancy: usize,
+}
+
+impl LoadWarnings {
+    // Element id for `symbol`, counting it when unknown
+    pub(crate) fn element(&mut self, symbol: &str) -> u32 {
+        element_from_symbol(symbol).unwrap_or_else(|| {
+            self.unknown_elements += 1;
+            if self.unknown_symbols.len() < MAX_LISTED_SYMBOLS {
+                self.unknown_symbols.insert(symbol.trim().to_string());
+            }
+            DEFAULT_ELEMENT
+        })
+    }
+
+    pub(crate) fn missing_occupancy(&mut self) {
+        self.missing_occupancy += 1;
+    }
+
+    pub(crate) fn into_messages(self) -> Vec<String> {
+        let mut messages = Vec::new();
+        if self.unknown_elements > 0 {
+            let symbols: Vec<String> = self.unknown_symbols.iter().map(|s| format!("{:?}", s)).collect();
+            let default = element_info(DEFAULT_ELEMENT).map_or("?", |e| e.symbol);
+            messages.push(format!(
+                "{} atoms had unknown elements ({}), defaulted to {}",
+                self.unknown_elements, symbols.join(", "), default
+            ));
+        }
+        if self.missing_occupancy > 0 {
+            messages.push(format!("{} atoms had no occupancy, assumed 1.0", self.missing_occupancy));
+        }
+        messages
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Non-fatal problems from the last successful file load (PDB, XYZ, GRO
+    /// or streaming), one summary line per kind, such as atoms whose element
+    /// was unknown and defaulted to carbon. The load itself still returned
+    /// its atom count; unparseable records are errors instead. Emptied by
+    /// this call and replaced by each load.
+    pub fn take_load_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.load_warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::elements::DEFAULT_ELEMENT;
+    use crate::MolecularSystem;
+
+    #[test]
+    fn unknown_element_loads_and_warns_once() {
+        let mut system = MolecularSystem::new();
+        let count = system.load_xyz("3\n\nC 0 0 0\nQq 1 0 0\nQq 2 0 0\n").unwrap();
+
+        assert_eq!(count, 3);
+        assert_eq!(system.all_atoms[1].element, DEFAULT_ELEMENT);
+        let warnings = system.take_load_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("2 atoms had unknown elements (\"Qq\")"), "{}", warnings[0]);
+        assert!(system.take_load_warnings().is_empty());
+    }
+
+    #[test]
+    fn clean_load_replaces_earlier_warnings() {
+        let mut system = MolecularSystem::new();
+        system.load_xyz("1\n\nQq 0 0 0\n").unwrap();
+        system.load_xyz("1\n\nC 0 0 0\n").unwrap();
+
+        assert!(system.take_load_warnings().is_empty());
+    }
+}
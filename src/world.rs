@@ -0,0 +1,108 @@
+// Model transform applied to the whole system without touching `all_atoms`:
+// culling moves the camera into local space instead of moving every atom
+
+use wasm_bindgen::prelude::*;
+
+use crate::math::{cross, dot, scale, sub, Vec3};
+use crate::{Camera, MolecularSystem};
+
+/// Affine matrices whose bottom row is off `[0, 0, 0, 1]` by more than this are rejected
+const AFFINE_TOLERANCE: f32 = 1e-6;
+
+#[derive(Clone, Copy)]
+pub(crate) struct WorldTransform {
+    translation: Vec3,
+    // Rows of the inverse of the linear part
+    inverse: [Vec3; 3],
+}
+
+// Linear-part columns and translation of a column-major 4x4; None unless it
+// is an invertible affine map
+pub(crate) fn affine_parts(m: &[f32]) -> Option<([Vec3; 3], Vec3)> {
+    let &[
+        m00, m10, m20, m30,
+        m01, m11, m21, m31,
+        m02, m12, m22, m32,
+        tx, ty, tz, m33,
+    ] = m else { return None };
+    if m.iter().any(|v| !v.is_finite())
+        || [m30, m31, m32, m33 - 1.0].iter().any(|v| v.abs() > AFFINE_TOLERANCE)
+    {
+        return None;
+    }
+
+    let (a, b, c) = ((m00, m10, m20), (m01, m11, m21), (m02, m12, m22));
+    if dot(a, cross(b, c)).abs() < 1e-8 {
+        return None;
+    }
+    Some(([a, b, c], (tx, ty, tz)))
+}
+
+impl WorldTransform {
+    fn from_matrix(m: &[f32]) -> Option<Self> {
+        let ([a, b, c], translation) = affine_parts(m)?;
+        let det = dot(a, cross(b, c));
+        Some(WorldTransform {
+            translation,
+            inverse: [
+                scale(cross(b, c), 1.0 / det),
+                scale(cross(c, a), 1.0 / det),
+                scale(cross(a, b), 1.0 / det),
+            ],
+        })
+    }
+
+    fn local_direction(&self, d: Vec3) -> Vec3 {
+        (dot(self.inverse[0], d), dot(self.inverse[1], d), dot(self.inverse[2], d))
+    }
+
+    fn local_point(&self, p: Vec3) -> Vec3 {
+        self.local_direction(sub(p, self.translation))
+    }
+
+    fn local_camera(&self, camera: &Camera) -> Camera {
+        let eye = self.local_point((camera.x, camera.y, camera.z));
+        let target = self.local_point((camera.target_x, camera.target_y, camera.target_z));
+        let up = self.local_direction((camera.up_x, camera.up_y, camera.up_z));
+        Camera {
+            x: eye.0, y: eye.1, z: eye.2,
+            target_x: target.0, target_y: target.1, target_z: target.2,
+            up_x: up.0, up_y: up.1, up_z: up.2,
+        }
+    }
+}
+
+impl MolecularSystem {
+    // `camera` expressed in the atoms' own coordinates
+    pub(crate) fn local_camera(&self, camera: &Camera) -> Camera {
+        match &self.world_transform {
+            Some(transform) => transform.local_camera(camera),
+            None => *camera,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Place the whole system in the world with a column-major 4x4 affine
+    /// matrix (the layout WebGL and gl-matrix use) without rewriting atoms or
+    /// the spatial index: every call taking a camera first moves it into the
+    /// atoms' local space by the inverse matrix. Returned positions, billboards
+    /// and ellipsoids therefore stay in local coordinates; draw them with the
+    /// same matrix as the model transform.
+    ///
+    /// Distances used in culling (far plane, LOD thresholds) are measured in
+    /// local units, so they only match world units for rigid transforms. An
+    /// empty slice removes the transform; a matrix that is not 16 finite values
+    /// forming an invertible affine map is logged and ignored.
+    pub fn set_world_transform(&mut self, matrix: &[f32]) {
+        if matrix.is_empty() {
+            self.world_transform = None;
+            return;
+        }
+        match WorldTransform::from_matrix(matrix) {
+            Some(transform) => self.world_transform = Some(transform),
+            None => log!(self, LOG_ERRORS, "Ignoring world transform: expected an invertible affine 4x4 matrix"),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+use crate::pdb::ParsedAtoms;
+use crate::perf::{elapsed_ms, now_ms};
+use crate::precision::InputFrame;
+use crate::warnings::LoadWarnings;
+use crate::{MolecularSystem, RawAtom};
+
+/// Line-at-a-time XYZ reader: atom count, comment line, then `El x y z` records.
+/// Only the first frame of a multi-frame file is read.
+pub(crate) struct XyzParser {
+    // Applied to every coordinate as it is read
+    frame: InputFrame,
+    atoms: Vec<RawAtom>,
+    expected: Option<usize>,
+    lines_seen: usize,
+    warnings: LoadWarnings,
+}
+
+impl XyzParser {
+    pub(crate) fn new(frame: InputFrame) -> Self {
+        XyzParser { frame, atoms: Vec::new(), expected: None, lines_seen: 0, warnings: LoadWarnings::default() }
+    }
+
+    pub(crate) fn parse_line(&mut self, line: &str, line_no: usize) -> Result<(), String> {
+        self.lines_seen += 1;
+
+        let expected = match self.expected {
+            Some(expected) => expected,
+            None => {
+                let count = line.trim().parse::<usize>()
+                    .map_err(|_| format!("XYZ line {}: expected atom count", line_no))?;
+                self.expected = Some(count);
+                self.atoms.reserve(count);
+                return Ok(());
+            }
+        };
+
+        // Line 2 is a free-form comment; anything past the first frame is ignored
+        if self.lines_seen == 2 || self.atoms.len() >= expected {
+            return Ok(());
+        }
+
+        let mut fields = line.split_whitespace();
+        let symbol = fields.next()
+            .ok_or_else(|| format!("XYZ line {}: missing atom record", line_no))?;
+        let mut coordinate = || -> Result<f64, String> {
+            fields.next()
+                .and_then(|field| field.parse::<f64>().ok())
+                .ok_or_else(|| format!("XYZ line {}: invalid coordinate", line_no))
+        };
+        let (x, y, z) = (coordinate()?, coordinate()?, coordinate()?);
+        let (x, y, z) = self.frame.point(x, y, z);
+
+        let element = self.warnings.element(symbol);
+        self.atoms.push(RawAtom { x, y, z, element, occupancy: 1.0 });
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<ParsedAtoms, String> {
+        let expected = self.expected.unwrap_or(0);
+        if self.atoms.len() != expected {
+            return Err(format!("XYZ declares {} atoms but {} were read", expected, self.atoms.len()));
+        }
+        Ok(ParsedAtoms {
+            warnings: self.warnings.into_messages(),
+            precise: self.frame.into_precise(),
+            ..self.atoms.into()
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl MolecularSystem {
+    /// Load atoms from XYZ text, replacing the current data
+    pub fn load_xyz(&mut self, text: &str) -> Result<usize, JsValue> {
+        log!(self, LOG_INFO, "Parsing XYZ ({} bytes)...", text.len());
+        let start = now_ms();
+
+        let mut parser = XyzParser::new(self.input_frame());
+        for (line_no, line) in text.lines().enumerate() {
+            parser.parse_line(line, line_no + 1).map_err(|e| JsValue::from_str(&e))?;
+        }
+
+        self.adopt_parsed_atoms(parser.finish().map_err(|e| JsValue::from_str(&e))?);
+
+        self.timings.load_ms = elapsed_ms(start);
+        log!(self, LOG_INFO, "Loaded {} atoms from XYZ", self.all_atoms.len());
+        Ok(self.all_atoms.len())
+    }
+}